@@ -0,0 +1,43 @@
+use ansi_to_tui::IntoText;
+
+/// Strips the ANSI escape sequences from a line of output.
+pub fn strip(line: &str) -> String {
+    match line.into_text() {
+        Ok(text) => text
+            .lines
+            .iter()
+            .flat_map(|line| line.spans.iter())
+            .map(|span| span.content.as_ref())
+            .collect(),
+        Err(_) => line.to_string(),
+    }
+}
+
+/// Sequences which clear the whole screen: erase display, erase scrollback and full reset.
+const CLEAR_SEQUENCES: [&str; 3] = ["\x1b[2J", "\x1b[3J", "\x1bc"];
+
+/// Returns the part of the line after the last sequence clearing the screen, or `None` if the
+/// line does not clear the screen.
+pub fn after_clear(line: &str) -> Option<&str> {
+    CLEAR_SEQUENCES
+        .iter()
+        .filter_map(|sequence| line.rfind(sequence).map(|index| index + sequence.len()))
+        .max()
+        .map(|end| &line[end..])
+}
+
+/// Returns where the escape sequence left open at the end of the text starts, e.g. when the text
+/// was cut in the middle of a colour, or `None` if every sequence in it is complete.
+pub fn open_escape(text: &str) -> Option<usize> {
+    let start = text.rfind('\x1b')?;
+    let rest = &text.as_bytes()[start + 1..];
+    let complete = match rest.first() {
+        None => false,
+        // control sequences end with a byte in @..~, after their parameters
+        Some(b'[') => rest[1..].iter().any(|byte| (0x40..=0x7e).contains(byte)),
+        // operating system commands end with BEL, or with ESC \ which would be found instead
+        Some(b']') => rest.contains(&0x07),
+        Some(_) => true,
+    };
+    (!complete).then_some(start)
+}
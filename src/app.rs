@@ -1,110 +1,606 @@
-use crate::console::Console;
+use crate::bell::BellPolicy;
+use crate::config;
+use crate::config::tags::TagFilter;
+use crate::config::{OutputEncoding, TaskConfig};
+use crate::confirm::{Confirm, ConfirmAction};
+use crate::console::{Console, Scroll};
 use crate::context::Context;
+use crate::crash;
 use crate::event::ParallelyEvent;
+use crate::frontend::Frontend;
+use crate::i18n::Lang;
+use crate::inspector::{Inspector, InspectorAction};
+use crate::layout;
+use crate::log_forwarder::LogForwarder;
 use crate::message;
 use crate::message::{Message, MessageSender, MessageStream};
 use crate::parallely::Parallely;
-use crate::shutdown_handler::{ShutdownHandler, ShutdownReason};
+use crate::perf::{PerfOverlay, PerfStats};
+use crate::picker::{Picker, PickerAction};
+use crate::plugin::script::Script;
+use crate::plugin::{self, PluginCommand, Plugins};
+use crate::process_tree::{ProcessTree, ProcessTreeAction};
+use crate::prompt::{Prompt, PromptAction};
+use crate::report::TaskReport;
+use crate::shutdown_handler::{HangupAction, ShutdownHandler, ShutdownReason};
+use crate::snapshot::SnapshotFormat;
+use crate::status_bar::{Mode, StatusBar};
+use crate::status_file::StatusFile;
+use crate::summary;
+use crate::task_executor::child_ext::ChildSignal;
 use crate::task_executor::{Executable, TaskStatus};
+use crate::task_log::{self, LogOptions, TaskLog};
+use crate::task_state::TaskState;
+use crate::timeline::{Timeline, TimelineEntry};
+use crate::timestamp;
+use crate::watchdog::Watchdog;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+};
 use ratatui::buffer::Buffer;
-use ratatui::crossterm::event;
-use ratatui::layout::{Alignment, Constraint, Flex, Layout, Rect};
+use ratatui::layout::{Alignment, Constraint, Direction, Flex, Layout, Rect};
 use ratatui::style::Stylize;
 use ratatui::text::Line;
-use ratatui::widgets::block::Title;
-use ratatui::widgets::{Block, StatefulWidget, Widget};
-use ratatui::{DefaultTerminal, Frame};
+use ratatui::widgets::{Block, Paragraph, StatefulWidget, Widget};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio_stream::StreamExt;
 
+/// How long a notice stays visible in the bottom border.
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+/// How long to wait at most for the rest of the output of the tasks once they have exited.
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
 pub struct App {
     message_sender: MessageSender,
     message_stream: MessageStream,
     shutdown_handler: ShutdownHandler,
     consoles: Vec<Console>,
     exit_on_complete: bool,
+    cancel_on_failure: bool,
+    forward_signals: bool,
+    on_hangup: HangupAction,
+    /// How many of the last lines of output of every task are reported at exit.
+    tail_lines: usize,
+    scroll_step: usize,
+    compact: bool,
+    lang: Lang,
+    /// The encoding of the output of the tasks which do not set one, see
+    /// [`Parallely::encoding`].
+    encoding: Option<OutputEncoding>,
+    /// Whether the consoles are sized by their activity, see [`Console::layout_weight`].
+    auto_layout: bool,
+    preset: Option<String>,
+    log_options: Option<LogOptions>,
+    forwarder: Option<Arc<LogForwarder>>,
+    plugins: Option<Arc<Plugins>>,
+    status_file: Option<StatusFile>,
+    config: Option<PathBuf>,
+    commands: Vec<String>,
+    /// The values of the parameters of the tasks, kept for reloading.
+    params: Vec<(String, String)>,
+    bell: BellPolicy,
+    executing: Vec<bool>,
+    /// The consoles of the tasks removed by a reload, with when they were stopped, kept until
+    /// their process has exited, see [`Console::poll_retired`].
+    retiring: Vec<(Console, Instant)>,
+    focused: usize,
+    picker: Option<Picker<PickerItem>>,
+    prompt: Option<Prompt<PromptPurpose>>,
+    inspector: Option<Inspector>,
+    process_tree: Option<ProcessTree>,
+    confirm: Option<Confirm<Confirmable>>,
+    /// Whether destructive actions are confirmed first, see [`Parallely::confirmations`].
+    confirmations: bool,
+    /// Whether the keys which change the tasks are disabled, see [`Parallely::read_only`].
+    read_only: bool,
+    /// Whether the consoles keep a transcript for the screen reader frontend, see
+    /// [`Parallely::screen_reader`].
+    screen_reader: bool,
+    /// The command of the task to run attached to the terminal once parallely has exited.
+    ejected: Option<TaskConfig>,
+    snapshot: Option<SnapshotFormat>,
+    /// Whether the startup timeline is shown over the consoles.
+    timeline: bool,
+    /// Whether the output of every console is frozen, see [`Console::set_frozen`].
+    frozen: bool,
+    /// Whether keys are typed into the standard input of the focused task.
+    interacting: bool,
+    /// Whether only the focused console is shown, taking up the whole screen.
+    zoomed: bool,
+    /// The tags of the consoles shown, see [`App::filter_tags`].
+    tag_filter: Option<TagFilter>,
+    notice: Option<(String, Instant)>,
+    started_at: SystemTime,
+    /// Detects when the main loop stops making progress.
+    watchdog: Watchdog,
+    perf: PerfStats,
+    /// Whether the [`PerfOverlay`] is shown, toggled with `F12`.
+    perf_overlay: bool,
+    /// The workspace shown, see [`TaskConfig::workspace`], empty if the tasks have none.
+    workspace: String,
+    /// The console focused last on each workspace.
+    workspace_focus: HashMap<String, usize>,
+    /// How many rows each row of consoles on each workspace is resized by, see
+    /// [`layout::resize`].
+    row_offsets: HashMap<String, Vec<i32>>,
 }
 
 impl App {
-    pub fn new(parallely: Parallely) -> Self {
+    pub fn new(parallely: Parallely, tasks: Vec<TaskConfig>) -> color_eyre::Result<Self> {
         let (message_sender, message_stream) = message::message_queue();
         let shutdown_handler = ShutdownHandler::new(message_sender.clone());
-        let consoles = parallely
-            .commands
+        let log_options = parallely.log_options();
+        let forwarder = match parallely.forward_logs {
+            Some(target) => Some(Arc::new(LogForwarder::connect(target)?)),
+            None => None,
+        };
+        let plugin_dir = match parallely.no_plugins {
+            true => None,
+            false => parallely.plugin_dir.clone().or_else(plugin::user_dir),
+        };
+        let (mut plugins, plugin_errors) = match plugin_dir {
+            Some(dir) => Plugins::start(&dir, &message_sender),
+            None => (Plugins::default(), Vec::new()),
+        };
+        if let Some(path) = parallely.script() {
+            plugins.set_script(Script::load(&path, message_sender.clone())?);
+        }
+        let plugins = (!plugins.is_empty()).then(|| Arc::new(plugins));
+        if log_options.is_some() {
+            task_log::check_unique(tasks.iter().map(TaskConfig::key))?;
+        }
+        let mut consoles = tasks
             .into_iter()
-            .map(|command| Console::new(command, message_sender.clone()))
-            .collect();
+            .map(|mut task| {
+                task.encoding = task.encoding.or(parallely.encoding);
+                new_console(
+                    task,
+                    &message_sender,
+                    log_options.as_ref(),
+                    forwarder.as_ref(),
+                    plugins.as_ref(),
+                    parallely.scroll_step,
+                )
+            })
+            .collect::<Vec<_>>();
+        if let Some(console) = consoles.first_mut() {
+            console.set_focused(true);
+        }
+        if parallely.screen_reader {
+            consoles.iter_mut().for_each(Console::keep_transcript);
+        }
         let exit_on_complete = parallely.exit_on_complete;
-        App {
+        let confirmations = parallely.confirmations();
+        let executing = vec![false; consoles.len()];
+        let mut app = App {
             message_sender,
             message_stream,
             shutdown_handler,
             consoles,
             exit_on_complete,
+            cancel_on_failure: parallely.cancel_on_failure,
+            forward_signals: parallely.forward_signals,
+            on_hangup: parallely.on_hangup,
+            scroll_step: parallely.scroll_step,
+            tail_lines: parallely
+                .tail_on_exit
+                .unwrap_or(0)
+                .max(summary::FAILED_TAIL_LINES),
+            compact: parallely.compact,
+            lang: parallely.lang.unwrap_or_else(Lang::detect),
+            encoding: parallely.encoding,
+            auto_layout: parallely.auto_layout,
+            preset: parallely.preset,
+            log_options,
+            forwarder,
+            plugins,
+            status_file: parallely
+                .status_file
+                .map(|path| StatusFile::new(path, parallely.status_lines)),
+            config: parallely.config,
+            commands: parallely.commands,
+            params: parallely.params,
+            bell: parallely.bell,
+            executing,
+            retiring: Vec::new(),
+            focused: 0,
+            picker: None,
+            prompt: None,
+            inspector: None,
+            process_tree: None,
+            confirm: None,
+            confirmations,
+            read_only: parallely.read_only,
+            screen_reader: parallely.screen_reader,
+            ejected: None,
+            snapshot: None,
+            timeline: false,
+            frozen: false,
+            interacting: false,
+            zoomed: parallely.zoom.is_some(),
+            tag_filter: None,
+            notice: None,
+            started_at: SystemTime::now(),
+            watchdog: Watchdog::new(),
+            perf: PerfStats::new(),
+            perf_overlay: false,
+            workspace: String::new(),
+            workspace_focus: HashMap::new(),
+            row_offsets: HashMap::new(),
+        };
+        if let Some(console) = app.consoles.first() {
+            app.workspace = console.workspace().to_string();
+        }
+        if let Some(task) = parallely.focus.as_ref().or(parallely.zoom.as_ref()) {
+            let index = app
+                .consoles
+                .iter()
+                .position(|c| c.task().key() == task || c.task().identifier() == task);
+            match index {
+                Some(index) => app.focus(index),
+                None => {
+                    app.zoomed = false;
+                    app.notify(format!("There is no task {} to focus", task));
+                }
+            }
+        }
+        if !plugin_errors.is_empty() {
+            let errors = plugin_errors.iter().map(ToString::to_string);
+            app.notify(errors.collect::<Vec<_>>().join(", "));
         }
+        Ok(app)
     }
 
-    pub async fn run(&mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<AppResult> {
-        self.listen_events();
+    /// Runs the tasks until parallely is quit or they have completed, shown by `frontend`.
+    pub async fn run(&mut self, frontend: &mut impl Frontend) -> color_eyre::Result<AppResult> {
+        let result = self.run_loop(frontend).await;
+        if let Some(plugins) = self.plugins.as_ref() {
+            plugins.close().await;
+        }
+        // also when the loop ended early with an error, so that no reader or listener is left
+        // sending to a queue which is not read anymore
+        self.message_sender.supervisor().shutdown().await;
+        result
+    }
+
+    async fn run_loop(&mut self, frontend: &mut impl Frontend) -> color_eyre::Result<AppResult> {
+        frontend.handle_input(self.message_sender.clone());
         self.listen_shutdown();
+        self.listen_ticks();
+        self.listen_reload();
+        self.watchdog.watch(self.message_sender.clone());
+        self.started_at = SystemTime::now();
         for console in self.consoles.iter_mut() {
-            console.execute()?;
+            console.start()?;
         }
+        self.executing = vec![true; self.consoles.len()];
 
-        let mut context = Context::default();
-
+        let mut busy_since = Instant::now();
         loop {
+            // every console, including the ones which are not drawn, e.g. hidden ones
+            for console in self.consoles.iter_mut() {
+                console.poll();
+            }
             tracing::trace!("[Main Loop] Drawing frame");
-            terminal.draw(|frame| self.draw(frame, &mut context))?;
+            let frame_started = Instant::now();
+            frontend.render(self)?;
+            self.perf.record_frame(frame_started.elapsed());
             tracing::trace!("[Main Loop] Try-Waiting for events");
+            Console::start_waiting(&mut self.consoles);
             let tasks_status = self
                 .consoles
                 .iter_mut()
                 .map(|c| c.try_wait())
                 .collect::<Vec<_>>();
-            if !tasks_status
+            self.retiring
+                .retain_mut(|(console, since)| !console.poll_retired(*since));
+            let executing = tasks_status
                 .iter()
-                .any(|s| matches!(s, Ok(TaskStatus::Executing { .. })))
-                && self.exit_on_complete
-            {
-                break Ok(AppResult::new(tasks_status, ShutdownReason::End));
+                .zip(self.consoles.iter())
+                .map(|(s, c)| {
+                    c.is_restarting()
+                        || c.is_waiting()
+                        || matches!(s, Ok(TaskStatus::Executing { .. }))
+                })
+                .collect::<Vec<_>>();
+            let failed = self.check_completed(&tasks_status, &executing);
+            self.update_status_file(&tasks_status, false);
+            if let (Some(index), true) = (failed, self.cancel_on_failure) {
+                tracing::info!("[Main Loop] Cancelled by failure of task {}", index);
+                break Ok(self.shutdown(ShutdownReason::Failure(index)).await);
+            }
+            if !executing.contains(&true) && self.exit_on_complete {
+                self.drain().await;
+                self.update_status_file(&tasks_status, true);
+                break Ok(self.result(tasks_status, ShutdownReason::End, self.tail_lines));
+            }
+            if let Some(stalled) = self.watchdog.beat(busy_since.elapsed()) {
+                self.notify(format!(
+                    "parallely did not respond for {}",
+                    summary::format_duration(stalled)
+                ));
             }
             tracing::trace!("[Main Loop] Waiting for message");
             if let Some(message) = self.message_stream.next().await {
+                busy_since = Instant::now();
                 match message {
                     Message::Error(error) => {
                         tracing::error!("[Main Loop] Error: {:?}", error);
                     }
                     Message::Shutdown(reason) => {
                         tracing::trace!("[Main Loop] Shutdown: {:?}", reason);
-                        let handles = self
-                            .consoles
-                            .iter_mut()
-                            .map(|c| c.signal_or_wait(reason))
-                            .collect::<Vec<_>>();
-                        let tasks_status = futures::future::join_all(handles).await;
-                        break Ok(AppResult::new(tasks_status, reason));
+                        break Ok(self.shutdown(reason).await);
                     }
                     Message::EventChunk(events) => {
                         self.handle_events(events)?;
                     }
+                    Message::Reload => {
+                        tracing::trace!("[Main Loop] Reload");
+                        self.reload();
+                    }
+                    Message::Signal(signal) => {
+                        tracing::trace!("[Main Loop] Forward {}", signal);
+                        let count = Console::forward_signal(&mut self.consoles, signal);
+                        self.notify(format!("Forwarded {} to {} tasks", signal, count));
+                    }
+                    Message::Transition(transition) => {
+                        tracing::info!(
+                            "[Main Loop] `{}` {} -> {}",
+                            transition.task,
+                            transition.from,
+                            transition.to
+                        );
+                    }
+                    Message::Plugin(plugin, command) => {
+                        tracing::info!("[Main Loop] Plugin {}: {:?}", plugin, command);
+                        self.run_plugin_command(command);
+                    }
+                    Message::Changed(task, path) => {
+                        tracing::info!("[Main Loop] `{}` changed: {}", task, path.display());
+                        self.restart_changed(&task, &path);
+                    }
                     Message::Update => {
                         tracing::trace!("[Main Loop] Update");
                     }
                 }
+                self.perf.record_message(busy_since.elapsed());
+            }
+        }
+    }
+
+    /// Restarts the task whose watched file changed, or runs it again if it has already exited.
+    /// A task which has not been started yet, e.g. waiting for a dependency, is left alone.
+    fn restart_changed(&mut self, task: &str, path: &Path) {
+        let Some(console) = self
+            .consoles
+            .iter_mut()
+            .find(|console| console.task().key() == task)
+        else {
+            return;
+        };
+        if console.started_at().is_none() {
+            return;
+        }
+        let path = console.watched_path(path).display().to_string();
+        console.annotate(&format!("triggered by change in {}", path));
+        console.restart();
+    }
+
+    fn timeline(&mut self) -> Timeline {
+        let entries = self
+            .consoles
+            .iter_mut()
+            .map(|console| TimelineEntry {
+                label: console.task().key().to_string(),
+                started_at: console.started_at(),
+                ready_at: console.ready_at(),
+                exited_at: console.exited_at(),
+                failed: console.try_wait().is_ok_and(|status| status.is_failed()),
+                waiting: console.is_waiting(),
+            })
+            .collect();
+        Timeline {
+            since: self.started_at,
+            entries,
+            lang: self.lang,
+        }
+    }
+
+    /// Signals every task which is still running and waits for all of them to exit, or leaves
+    /// them running if the reason [`ShutdownReason::detaches`].
+    async fn shutdown(&mut self, reason: ShutdownReason) -> AppResult {
+        if reason.detaches() {
+            let tasks_status = self
+                .consoles
+                .iter_mut()
+                .map(|c| c.try_wait())
+                .collect::<Vec<_>>();
+            self.update_status_file(&tasks_status, true);
+            return self.result(tasks_status, reason, self.tail_lines);
+        }
+        let handles = self
+            .consoles
+            .iter_mut()
+            .map(|c| c.signal_or_wait(reason))
+            .collect::<Vec<_>>();
+        let tasks_status = futures::future::join_all(handles).await;
+        for (console, _) in self.retiring.iter_mut() {
+            if let Err(e) = console.kill().await {
+                tracing::error!("[Shutdown] {:?}", e);
             }
         }
+        self.drain().await;
+        self.update_status_file(&tasks_status, true);
+        self.result(tasks_status, reason, self.tail_lines)
     }
 
-    fn draw(&mut self, frame: &mut Frame, context: &mut Context) {
-        frame.render_stateful_widget(self, frame.area(), context);
+    /// What is known about the tasks right now including most of their output, for a crash dump
+    /// after the main loop failed. The tasks are left running, see [`App::stop`].
+    pub fn crash_result(&mut self) -> AppResult {
+        let tasks_status = self
+            .consoles
+            .iter_mut()
+            .map(|c| c.try_wait())
+            .collect::<Vec<_>>();
+        self.result(tasks_status, ShutdownReason::Quit, crash::DUMP_LINES)
+    }
+
+    /// Stops every task, e.g. after the main loop failed.
+    pub async fn stop(&mut self) {
+        self.shutdown(ShutdownReason::Quit).await;
+    }
+
+    /// Reads the output the tasks wrote right before exiting, which has not been drawn yet.
+    async fn drain(&mut self) {
+        let deadline = tokio::time::Instant::now() + DRAIN_TIMEOUT;
+        for console in self.consoles.iter_mut() {
+            if let Err(e) = console.drain(deadline).await {
+                tracing::error!("[Drain] {:?}", e);
+            }
+        }
+    }
+
+    fn update_status_file(&mut self, tasks_status: &[color_eyre::Result<TaskStatus>], force: bool) {
+        let result = self
+            .status_file
+            .as_mut()
+            .map(|status_file| status_file.update(&self.consoles, tasks_status, force));
+        if let Some(Err(e)) = result {
+            self.notify(e.to_string());
+        }
+    }
+
+    fn result(
+        &self,
+        tasks_status: Vec<color_eyre::Result<TaskStatus>>,
+        shutdown_reason: ShutdownReason,
+        tail_lines: usize,
+    ) -> AppResult {
+        let tasks = self
+            .consoles
+            .iter()
+            .zip(tasks_status)
+            .map(|(console, status)| {
+                let (received_lines, received_bytes) = console.received();
+                TaskReport {
+                    command: console.task().command.to_string(),
+                    status,
+                    test_summary: console.test_summary(),
+                    started_at: console.started_at(),
+                    exited_at: console.exited_at(),
+                    peak_rss: console.peak_rss(),
+                    cpu_time: console.cpu_time(),
+                    io_stats: console.io_stats(),
+                    dropped_lines: console.dropped_lines(),
+                    received_lines,
+                    received_bytes,
+                    last_lines: console.last_lines(tail_lines),
+                }
+            })
+            .collect();
+        AppResult::new(
+            tasks,
+            self.started_at,
+            shutdown_reason,
+            self.ejected.clone(),
+        )
+    }
+
+    /// Notifies the bell policy about every task which stopped executing since the last check.
+    /// Returns the index of the first of them which failed, not counting the tasks the user
+    /// stopped, which are killed by their stop signal.
+    fn check_completed(
+        &mut self,
+        tasks_status: &[color_eyre::Result<TaskStatus>],
+        now_executing: &[bool],
+    ) -> Option<usize> {
+        let mut failed = None;
+        for (index, ((executing, status), &now_executing)) in self
+            .executing
+            .iter_mut()
+            .zip(tasks_status)
+            .zip(now_executing)
+            .enumerate()
+        {
+            if let (true, false, Ok(status)) = (*executing, now_executing, status) {
+                self.bell.notify(status);
+                let stopped = self.consoles[index].is_stop_requested();
+                if status.is_failed() && !stopped && failed.is_none() {
+                    failed = Some(index);
+                }
+            }
+            *executing = now_executing;
+        }
+        failed
     }
 
     fn handle_events(&mut self, events: Vec<ParallelyEvent>) -> color_eyre::Result<()> {
         for mut event in events {
+            if let Some(picker) = self.picker.as_mut() {
+                match picker.handle_event(&mut event) {
+                    PickerAction::None => {}
+                    PickerAction::Picked(item) => {
+                        self.picker = None;
+                        self.pick(item);
+                    }
+                    PickerAction::Cancelled => self.picker = None,
+                }
+            }
+            if let Some(inspector) = self.inspector.as_mut() {
+                match inspector.handle_event(&mut event) {
+                    InspectorAction::None => {}
+                    InspectorAction::Close => self.inspector = None,
+                }
+            }
+            if let Some(tree) = self.process_tree.as_mut().filter(|_| event.propagate()) {
+                match tree.handle_event(&mut event) {
+                    ProcessTreeAction::None => {}
+                    ProcessTreeAction::Close => self.process_tree = None,
+                    ProcessTreeAction::Signal(_) if self.read_only => self.refuse_read_only(),
+                    ProcessTreeAction::Signal(pid) => self.open_process_signal_picker(pid),
+                }
+            }
+            // an overlay opened by the one before does not get the same event
+            if let Some(prompt) = self.prompt.as_mut().filter(|_| event.propagate()) {
+                match prompt.handle_event(&mut event) {
+                    PromptAction::None => {}
+                    PromptAction::Submitted(purpose, input) => {
+                        self.prompt = None;
+                        self.submit_prompt(purpose, input);
+                    }
+                    PromptAction::Cancelled => self.prompt = None,
+                }
+            }
+            if let Some(confirm) = self.confirm.as_mut().filter(|_| event.propagate()) {
+                match confirm.handle_event(&mut event) {
+                    ConfirmAction::None => {}
+                    ConfirmAction::Confirmed(action) => {
+                        self.confirm = None;
+                        self.carry_out(action);
+                    }
+                    ConfirmAction::Cancelled => self.confirm = None,
+                }
+            }
+            if self.read_only && event.propagate() {
+                self.block_mutation(&mut event);
+            }
+            if self.interacting && event.propagate() {
+                self.handle_interact_event(&mut event);
+            }
+            if event.propagate() && self.confirmations {
+                self.confirm_quit(&mut event);
+            }
             if event.propagate() {
                 self.shutdown_handler.handle_event(&mut event);
             }
-            for console in self.consoles.iter_mut() {
+            if event.propagate() {
+                self.handle_event(&mut event);
+            }
+            let workspace = self.workspace.as_str();
+            for console in self.consoles.iter_mut().filter(|c| c.is_shown(workspace)) {
                 if !event.propagate() {
                     break;
                 }
@@ -114,26 +610,1065 @@ impl App {
         Ok(())
     }
 
-    fn listen_events(&self) {
-        let message_sender = self.message_sender.clone();
-        tokio::spawn(async move {
-            let event_stream =
-                event::EventStream::new().chunks_timeout(100, std::time::Duration::from_millis(2));
-            tokio::pin!(event_stream);
-            while let Some(maybe_event) = event_stream.next().await {
-                let events = maybe_event
-                    .into_iter()
-                    .flatten()
-                    .map(Into::into)
-                    .collect::<Vec<_>>();
-                message_sender.send_event_chunk(events);
+    fn handle_event(&mut self, event: &mut ParallelyEvent) {
+        match event.as_ref() {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                if let (KeyCode::Char(key), false) = (
+                    code,
+                    modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT),
+                ) {
+                    if self.run_quick_action(*key) {
+                        event.stop_propagation();
+                        return;
+                    }
+                }
+                let control = modifiers.contains(KeyModifiers::CONTROL);
+                match code {
+                    KeyCode::Char('u') if control => self.scroll_focused(Scroll::HalfPageUp),
+                    KeyCode::Char('d') if control => self.scroll_focused(Scroll::HalfPageDown),
+                    KeyCode::PageUp => self.scroll_focused(Scroll::PageUp),
+                    KeyCode::PageDown => self.scroll_focused(Scroll::PageDown),
+                    KeyCode::Home | KeyCode::Char('g') => self.scroll_focused(Scroll::Top),
+                    KeyCode::End | KeyCode::Char('G') => self.scroll_focused(Scroll::Bottom),
+                    KeyCode::Left if control => self.resize_focused(Direction::Horizontal, -1),
+                    KeyCode::Right if control => self.resize_focused(Direction::Horizontal, 1),
+                    KeyCode::Up if control => self.resize_focused(Direction::Vertical, -1),
+                    KeyCode::Down if control => self.resize_focused(Direction::Vertical, 1),
+                    KeyCode::F(12) => self.perf_overlay = !self.perf_overlay,
+                    KeyCode::F(number @ 1..=11) => self.switch_workspace(*number as usize - 1),
+                    KeyCode::Tab => self.focus_next(true),
+                    KeyCode::BackTab => self.focus_next(false),
+                    KeyCode::Char('z') => self.zoomed = !self.zoomed,
+                    KeyCode::Char('h') => self.hide_focused(),
+                    KeyCode::Char('H') => self.open_unhide_picker(),
+                    KeyCode::Char('c') => self.compact = !self.compact,
+                    KeyCode::Char('b') => {
+                        if let Some(console) = self.consoles.get_mut(self.focused) {
+                            console.toggle_bookmark();
+                        }
+                    }
+                    KeyCode::Char(bracket @ ('[' | ']')) => {
+                        if let Some(console) = self.consoles.get_mut(self.focused) {
+                            console.jump_to_bookmark(*bracket == ']');
+                        }
+                    }
+                    KeyCode::Char('o') => {
+                        let question = format!(
+                            "Eject {}? Everything is stopped and it runs again in the terminal",
+                            self.describe_targets(&[self.focused])
+                        );
+                        self.ask(question, Confirmable::Eject(self.focused));
+                    }
+                    KeyCode::Char('x') => {
+                        let question = format!(
+                            "Clear the output of {}?",
+                            self.describe_targets(&[self.focused])
+                        );
+                        self.ask(question, Confirmable::Clear(self.focused));
+                    }
+                    KeyCode::Char('|') => {
+                        let toggled = self
+                            .consoles
+                            .get_mut(self.focused)
+                            .is_some_and(Console::toggle_split);
+                        if !toggled {
+                            self.notify("stdout and stderr of a terminal are one".to_string());
+                        }
+                    }
+                    KeyCode::Char('\\') => {
+                        let switched = self
+                            .consoles
+                            .get_mut(self.focused)
+                            .is_some_and(Console::switch_scrolled_pane);
+                        if !switched {
+                            self.notify(
+                                "The console is not split, press <|> to split it".to_string(),
+                            );
+                        }
+                    }
+                    KeyCode::Char('n') => {
+                        if let Some(console) = self.consoles.get_mut(self.focused) {
+                            console.toggle_line_numbers();
+                        }
+                    }
+                    KeyCode::Char('p') => {
+                        if let Some(console) = self.consoles.get_mut(self.focused) {
+                            console.toggle_output_paused();
+                        }
+                    }
+                    KeyCode::Char('l') => {
+                        if let Some(console) = self.consoles.get_mut(self.focused) {
+                            console.toggle_rate_limit();
+                        }
+                    }
+                    KeyCode::Char('L') => {
+                        if let Some(console) = self.consoles.get_mut(self.focused) {
+                            console.toggle_long_lines();
+                        }
+                    }
+                    KeyCode::Char('i') => self.start_interacting(),
+                    KeyCode::Char('D') => {
+                        let toggled = self
+                            .consoles
+                            .get_mut(self.focused)
+                            .is_some_and(Console::toggle_diff_view);
+                        if !toggled {
+                            self.notify("No previous run to compare with".to_string());
+                        }
+                    }
+                    KeyCode::Char('m') => {
+                        if let Some(console) = self.consoles.get(self.focused) {
+                            let prompt = Prompt::with_input(
+                                "Restart with command",
+                                console.task().command.to_string(),
+                                PromptPurpose::EditCommand(self.focused),
+                            );
+                            self.prompt = Some(prompt.with_history(console.command_history()));
+                        }
+                    }
+                    KeyCode::Char(':') => {
+                        self.prompt = Some(Prompt::new("Go to line", PromptPurpose::GoToLine));
+                    }
+                    KeyCode::Char('#') => {
+                        let tags = self.tag_filter.as_ref().map(ToString::to_string);
+                        self.prompt = Some(Prompt::with_input(
+                            "Show tags",
+                            tags.unwrap_or_default(),
+                            PromptPurpose::Tags,
+                        ));
+                    }
+                    KeyCode::Char('I') => {
+                        if let Some(console) = self.consoles.get(self.focused) {
+                            self.inspector = Some(Inspector::new(
+                                console.task().key(),
+                                console.command.as_std(),
+                                console.pid(),
+                            ));
+                        }
+                    }
+                    KeyCode::Char('P') => {
+                        self.process_tree = Some(ProcessTree::new());
+                    }
+                    KeyCode::Char('@') => {
+                        let time = self
+                            .consoles
+                            .get(self.focused)
+                            .and_then(|console| console.scroll_position().top_line_time)
+                            .map(timestamp::format)
+                            .unwrap_or_default();
+                        self.prompt = Some(Prompt::with_input(
+                            "Go to time",
+                            time,
+                            PromptPurpose::GoToTime,
+                        ));
+                    }
+                    KeyCode::Char(' ') => {
+                        if let Some(console) = self.consoles.get_mut(self.focused) {
+                            console.set_marked(!console.is_marked());
+                        }
+                    }
+                    KeyCode::Char('t') => self.timeline = !self.timeline,
+                    KeyCode::Char('M') => self.for_targets(Console::toggle_muted),
+                    KeyCode::Char('f') => {
+                        self.frozen = !self.frozen;
+                        let frozen = self.frozen;
+                        self.consoles.iter_mut().for_each(|c| c.set_frozen(frozen));
+                    }
+                    KeyCode::Esc if self.timeline => self.timeline = false,
+                    KeyCode::Esc => self.consoles.iter_mut().for_each(|c| c.set_marked(false)),
+                    KeyCode::Char('r') => self.for_targets(Console::restart),
+                    KeyCode::Char('s') => {
+                        let targets = self.targets();
+                        let question = format!("Stop {}?", self.describe_targets(&targets));
+                        self.ask(question, Confirmable::Stop(targets));
+                    }
+                    KeyCode::Char('S') => self.open_signal_picker(),
+                    KeyCode::Char('R') => self.reload(),
+                    KeyCode::Char('e') => self.snapshot = Some(SnapshotFormat::Ansi),
+                    KeyCode::Char('E') => self.snapshot = Some(SnapshotFormat::Html),
+                    _ => return,
+                }
+                event.stop_propagation();
+            }
+            // a zoomed console covers the others
+            Event::Mouse(mouse_event)
+                if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) && !self.zoomed =>
+            {
+                if let Some(index) = self.consoles.iter().position(|c| {
+                    c.is_shown(&self.workspace) && c.contains(mouse_event.column, mouse_event.row)
+                }) {
+                    self.focus(index);
+                }
+            }
+            Event::Resize(width, height) => {
+                tracing::debug!("[Main Loop] Resized to {}x{}", width, height);
+                self.consoles
+                    .iter_mut()
+                    .for_each(Console::invalidate_layout);
+            }
+            _ => {}
+        }
+    }
+
+    fn start_interacting(&mut self) {
+        let Some(console) = self.consoles.get(self.focused) else {
+            return;
+        };
+        if console.stdin_connected() {
+            self.interacting = true;
+        } else {
+            self.notify(format!(
+                "`{}` does not take input, set `interactive = true` in its config",
+                console.task().key()
+            ));
+        }
+    }
+
+    /// Sends the keys to the focused task while interacting, `Ctrl+V` pastes the clipboard,
+    /// `Ctrl+D` closes its input and `Esc` stops interacting. Other keys with `Control` are left
+    /// to the global shortcuts.
+    fn handle_interact_event(&mut self, event: &mut ParallelyEvent) {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        else {
+            return;
+        };
+        let Some(console) = self.consoles.get_mut(self.focused) else {
+            self.interacting = false;
+            return;
+        };
+        let input = match (code, modifiers.contains(KeyModifiers::CONTROL)) {
+            (KeyCode::Char('v'), true) => {
+                match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        event.stop_propagation();
+                        self.notify(format!("Failed to read the clipboard: {}", e));
+                        return;
+                    }
+                }
+            }
+            (KeyCode::Char('d'), true) => {
+                event.stop_propagation();
+                let term = console.is_term();
+                let key = console.task().key().to_string();
+                match console.close_stdin() {
+                    // a terminal takes input after `Ctrl+D` again, e.g. the next command of a shell
+                    Ok(()) if term => {}
+                    Ok(()) => {
+                        self.interacting = false;
+                        self.notify(format!("Closed the input of `{}`", key));
+                    }
+                    Err(e) => {
+                        self.interacting = false;
+                        self.notify(e.to_string());
+                    }
+                }
+                return;
+            }
+            (_, true) => return,
+            (KeyCode::Esc, _) => {
+                event.stop_propagation();
+                self.interacting = false;
+                return;
             }
+            (KeyCode::Char(c), _) => c.to_string(),
+            (KeyCode::Enter, _) => "\n".to_string(),
+            (KeyCode::Tab, _) => "\t".to_string(),
+            (KeyCode::Backspace, _) if console.is_term() => "\x7f".to_string(),
+            _ => String::new(),
+        };
+        event.stop_propagation();
+        if input.is_empty() {
+            return;
+        }
+        if let Err(e) = console.send_input(&input) {
+            self.interacting = false;
+            self.notify(e.to_string());
+        }
+    }
+
+    /// Runs the action bound to the key in the config of the focused task, returns `false` if
+    /// there is none.
+    fn run_quick_action(&mut self, key: char) -> bool {
+        let Some(console) = self.consoles.get_mut(self.focused) else {
+            return false;
+        };
+        let Some(action) = console
+            .task()
+            .actions
+            .iter()
+            .find(|action| action.key == key)
+            .cloned()
+        else {
+            return false;
+        };
+        if let Err(e) = console.run_action(&action) {
+            self.notify(format!("Failed to {}: {}", action.label(), e));
+        }
+        true
+    }
+
+    /// The consoles bulk actions apply to: every marked console, or the focused one if none is
+    /// marked.
+    fn targets(&self) -> Vec<usize> {
+        let marked = self
+            .consoles
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_marked())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if marked.is_empty() {
+            vec![self.focused]
+        } else {
+            marked
+        }
+    }
+
+    fn for_targets<F>(&mut self, mut action: F)
+    where
+        F: FnMut(&mut Console),
+    {
+        for index in self.targets() {
+            if let Some(console) = self.consoles.get_mut(index) {
+                action(console);
+            }
+        }
+    }
+
+    fn pick(&mut self, item: PickerItem) {
+        match item {
+            PickerItem::Unhide(index) => {
+                self.consoles[index].show();
+                self.focus(index);
+            }
+            PickerItem::Signal(signal) => self.send_signal(signal),
+            PickerItem::SignalProcess(pid, signal) => {
+                let result = self
+                    .process_tree
+                    .as_ref()
+                    .map(|tree| tree.signal(pid, signal));
+                match result {
+                    Some(Ok(())) => self.notify(format!("Sent {} to process {}", signal, pid)),
+                    Some(Err(e)) => self.notify(e),
+                    None => {}
+                }
+            }
+            PickerItem::OtherSignal => {
+                self.prompt = Some(Prompt::new("Signal name or number", PromptPurpose::Signal));
+            }
+            PickerItem::RestartChanged(index) => {
+                for (i, console) in self.consoles.iter_mut().enumerate() {
+                    if index.is_none_or(|index| index == i) && console.command_changed() {
+                        console.restart();
+                    }
+                }
+                self.open_restart_picker();
+            }
+        }
+    }
+
+    /// Carries out the action right away if confirmations are turned off, or asks first.
+    fn ask(&mut self, question: String, action: Confirmable) {
+        if self.confirmations {
+            self.confirm = Some(Confirm::new(question, action));
+        } else {
+            self.carry_out(action);
+        }
+    }
+
+    fn carry_out(&mut self, action: Confirmable) {
+        match action {
+            Confirmable::Stop(targets) => {
+                for index in targets {
+                    if let Some(console) = self.consoles.get_mut(index) {
+                        console.stop();
+                    }
+                }
+            }
+            Confirmable::Kill(targets) => {
+                for index in targets {
+                    if let Some(console) = self.consoles.get_mut(index) {
+                        console.signal_child(ChildSignal::Kill);
+                    }
+                }
+            }
+            Confirmable::Clear(index) => {
+                if let Some(console) = self.consoles.get_mut(index) {
+                    console.clear();
+                }
+            }
+            Confirmable::Eject(index) => {
+                if let Some(console) = self.consoles.get(index) {
+                    self.ejected = Some(console.task().clone());
+                    self.message_sender.send_shutdown(ShutdownReason::Quit);
+                }
+            }
+            Confirmable::Quit => self.message_sender.send_shutdown(ShutdownReason::Quit),
+        }
+    }
+
+    /// The task if there is one target, or how many tasks.
+    /// The states of the copies of each task with `replicas` counted together, e.g.
+    /// `client ×8: 6 running, 2 failed`.
+    fn replica_summaries(&self) -> Vec<String> {
+        let mut groups: Vec<(&str, Vec<(TaskState, usize)>)> = Vec::new();
+        for console in self.consoles.iter() {
+            let Some(replica) = console.task().replica.as_ref() else {
+                continue;
+            };
+            let index = match groups.iter().position(|(group, _)| *group == replica.group) {
+                Some(index) => index,
+                None => {
+                    groups.push((&replica.group, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            let states = &mut groups[index].1;
+            let state = console.state();
+            match states.iter_mut().find(|(s, _)| *s == state) {
+                Some((_, count)) => *count += 1,
+                None => states.push((state, 1)),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(group, states)| {
+                let total = states.iter().map(|(_, count)| count).sum::<usize>();
+                let states = states
+                    .into_iter()
+                    .map(|(state, count)| format!("{} {}", count, self.lang.state(state)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} ×{}: {}", group, total, states)
+            })
+            .collect()
+    }
+
+    fn describe_targets(&self, targets: &[usize]) -> String {
+        match targets {
+            [index] => self
+                .consoles
+                .get(*index)
+                .map_or_else(String::new, |console| format!("`{}`", console.task().key())),
+            targets => format!("{} tasks", targets.len()),
+        }
+    }
+
+    /// Asks before `q` quits while tasks are still running, `Ctrl+C` still quits right away.
+    /// Swallows the keys which would change the tasks or quit, in read-only mode.
+    fn block_mutation(&mut self, event: &mut ParallelyEvent) {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        else {
+            return;
+        };
+        let control = modifiers.contains(KeyModifiers::CONTROL);
+        let quick_action = self.consoles.get(self.focused).is_some_and(|console| {
+            console
+                .task()
+                .actions
+                .iter()
+                .any(|action| KeyCode::Char(action.key) == *code)
         });
+        let mutating = match code {
+            KeyCode::Char('c' | '\\') if control => true,
+            _ if control => false,
+            // pausing and rate limiting the output slow down the tasks writing it
+            KeyCode::Char('q' | 'r' | 's' | 'S' | 'R' | 'm' | 'i' | 'o' | 'x' | 'p' | 'l') => true,
+            _ => quick_action,
+        };
+        if mutating {
+            self.refuse_read_only();
+            event.stop_propagation();
+        }
+    }
+
+    /// Carries out a command sent by a plugin. Plugins cannot stop or restart tasks with
+    /// `--read-only` either.
+    fn run_plugin_command(&mut self, command: PluginCommand) {
+        let task = match &command {
+            PluginCommand::Notify { text } => return self.notify(text.clone()),
+            PluginCommand::Start { task }
+            | PluginCommand::Restart { task }
+            | PluginCommand::Stop { task }
+            | PluginCommand::Annotate { task, .. } => task,
+        };
+        let Some(console) = self.consoles.iter_mut().find(|c| c.task().key() == task) else {
+            return self.notify(format!("A plugin referred to the unknown task {}", task));
+        };
+        match command {
+            PluginCommand::Annotate { text, .. } => console.annotate(&text),
+            _ if self.read_only => {
+                tracing::warn!("[Plugin] Refused {:?}, parallely is read-only", command)
+            }
+            PluginCommand::Start { .. } => {
+                if !matches!(console.try_wait(), Ok(TaskStatus::Executing { .. })) {
+                    console.restart();
+                }
+            }
+            PluginCommand::Restart { .. } => console.restart(),
+            PluginCommand::Stop { .. } => console.stop(),
+            PluginCommand::Notify { .. } => {}
+        }
+    }
+
+    fn refuse_read_only(&mut self) {
+        self.notify("parallely is read-only, only looking at the output is allowed".to_string());
+    }
+
+    fn confirm_quit(&mut self, event: &mut ParallelyEvent) {
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char('q'),
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        {
+            let running = self
+                .executing
+                .iter()
+                .filter(|executing| **executing)
+                .count();
+            if running > 0 {
+                let question = match running {
+                    1 => "A task is still running, quit?".to_string(),
+                    running => format!("{} tasks are still running, quit?", running),
+                };
+                self.confirm = Some(Confirm::new(question, Confirmable::Quit));
+                event.stop_propagation();
+            }
+        }
+    }
+
+    /// Reloads the config file: new tasks are started, removed tasks are stopped and tasks with
+    /// a changed command are offered to be restarted.
+    fn reload(&mut self) {
+        let Some(path) = self.config.clone() else {
+            self.notify("No config file to reload".to_string());
+            return;
+        };
+        let tasks = match config::load_tasks(
+            self.preset.as_deref(),
+            Some(&path),
+            &self.commands,
+            &self.params,
+        ) {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                self.notify(format!("Failed to reload config: {:#}", e));
+                return;
+            }
+        };
+        if self.log_options.is_some() {
+            if let Err(e) = task_log::check_unique(tasks.iter().map(TaskConfig::key)) {
+                self.notify(format!("Failed to reload config: {}", e));
+                return;
+            }
+        }
+
+        let mut previous = std::mem::take(&mut self.consoles)
+            .into_iter()
+            .zip(std::mem::take(&mut self.executing))
+            .map(Some)
+            .collect::<Vec<_>>();
+        let (mut added, mut changed) = (0, 0);
+        for mut task in tasks {
+            task.encoding = task.encoding.or(self.encoding);
+            task.detached |= self.on_hangup == HangupAction::Detach;
+            let existing = previous.iter_mut().find(|entry| {
+                entry
+                    .as_ref()
+                    .is_some_and(|(console, _)| console.task().key() == task.key())
+            });
+            match existing.and_then(Option::take) {
+                Some((mut console, executing)) => {
+                    if console.update_task(task) {
+                        changed += 1;
+                    }
+                    self.consoles.push(console);
+                    self.executing.push(executing);
+                }
+                None => {
+                    let mut console = new_console(
+                        task,
+                        &self.message_sender,
+                        self.log_options.as_ref(),
+                        self.forwarder.as_ref(),
+                        self.plugins.as_ref(),
+                        self.scroll_step,
+                    );
+                    console.set_frozen(self.frozen);
+                    if self.screen_reader {
+                        console.keep_transcript();
+                    }
+                    if let Err(e) = console.start() {
+                        self.message_sender.send_error(e);
+                    }
+                    self.consoles.push(console);
+                    self.executing.push(true);
+                    added += 1;
+                }
+            }
+        }
+        let mut removed = 0;
+        for (mut console, _) in previous.into_iter().flatten() {
+            console.stop();
+            self.retiring.push((console, Instant::now()));
+            removed += 1;
+        }
+
+        self.picker = None;
+        let focused = self.focused.min(self.consoles.len().saturating_sub(1));
+        self.consoles.iter_mut().for_each(|c| c.set_focused(false));
+        self.focus(focused);
+        self.notify(format!(
+            "Reloaded {}: {} added, {} removed, {} changed",
+            path.display(),
+            added,
+            removed,
+            changed
+        ));
+        self.open_restart_picker();
+    }
+
+    /// Asks whether to restart the tasks whose command changed on reload.
+    fn open_restart_picker(&mut self) {
+        let mut items = self
+            .consoles
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.command_changed() && !c.is_restarting())
+            .map(|(index, c)| {
+                (
+                    format!("Restart {}", c.task().key()),
+                    PickerItem::RestartChanged(Some(index)),
+                )
+            })
+            .collect::<Vec<_>>();
+        if items.len() > 1 {
+            items.insert(
+                0,
+                ("Restart all".to_string(), PickerItem::RestartChanged(None)),
+            );
+        }
+        if !items.is_empty() {
+            self.picker = Some(Picker::new("Commands changed", items));
+        }
+    }
+
+    fn open_signal_picker(&mut self) {
+        let items = ChildSignal::ALL
+            .into_iter()
+            .map(|signal| (signal.to_string(), PickerItem::Signal(signal)))
+            .chain([("Other…".to_string(), PickerItem::OtherSignal)])
+            .collect();
+        let title = format!("Send signal to {}", self.describe_targets(&self.targets()));
+        self.picker = Some(Picker::new(title, items));
+    }
+
+    fn open_process_signal_picker(&mut self, pid: u32) {
+        let items = ChildSignal::ALL
+            .into_iter()
+            .map(|signal| (signal.to_string(), PickerItem::SignalProcess(pid, signal)))
+            .collect();
+        self.picker = Some(Picker::new(
+            format!("Send signal to process {}", pid),
+            items,
+        ));
+    }
+
+    /// Sends the signal to the marked (or focused) consoles, `SIGKILL` after confirming.
+    fn send_signal(&mut self, signal: ChildSignal) {
+        let targets = self.targets();
+        if signal == ChildSignal::Kill {
+            let question = format!("Kill {}?", self.describe_targets(&targets));
+            self.ask(question, Confirmable::Kill(targets));
+            return;
+        }
+        self.for_targets(|c| c.signal_child(signal));
+        self.notify(format!(
+            "Sent {} to {}",
+            signal,
+            self.describe_targets(&targets)
+        ));
+    }
+
+    fn submit_prompt(&mut self, purpose: PromptPurpose, input: String) {
+        match purpose {
+            PromptPurpose::GoToLine => match input.parse::<usize>() {
+                Ok(line) => {
+                    if let Some(console) = self.consoles.get_mut(self.focused) {
+                        console.go_to_line(line);
+                    }
+                }
+                Err(_) => self.notify(format!("Invalid line number: {}", input)),
+            },
+            PromptPurpose::Signal => match input.parse::<ChildSignal>() {
+                Ok(signal) => self.send_signal(signal),
+                Err(e) => self.notify(e.to_string()),
+            },
+            PromptPurpose::GoToTime => match timestamp::parse(&input) {
+                Ok(time) => {
+                    let found = self
+                        .consoles
+                        .get_mut(self.focused)
+                        .is_some_and(|console| console.go_to_time(time));
+                    if !found {
+                        self.notify(format!("No output at or after {}", input));
+                    }
+                }
+                Err(e) => self.notify(e.to_string()),
+            },
+            PromptPurpose::Tags if input.trim().is_empty() => self.filter_tags(None),
+            PromptPurpose::Tags => match input.parse::<TagFilter>() {
+                Ok(filter) => self.filter_tags(Some(filter)),
+                Err(e) => self.notify(e.to_string()),
+            },
+            PromptPurpose::EditCommand(index) => {
+                if input.is_empty() {
+                    self.notify("No command given".to_string());
+                } else if let Some(console) = self.consoles.get_mut(index) {
+                    console.restart_with(input);
+                }
+            }
+        }
+    }
+
+    /// Shows a short-lived notice in the bottom border of the app.
+    /// The format of the snapshot of the screen requested with `e` or `E`, if any.
+    pub fn take_snapshot(&mut self) -> Option<SnapshotFormat> {
+        self.snapshot.take()
+    }
+
+    pub fn notify(&mut self, notice: String) {
+        tracing::info!("[Notice] {}", notice);
+        self.notice = Some((notice, Instant::now()));
+        self.message_sender.need_update();
+    }
+
+    /// The notice shown right now, if any.
+    pub fn notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(_, since)| since.elapsed() < NOTICE_DURATION)
+            .map(|(notice, _)| notice.as_str())
+    }
+
+    pub fn lang(&self) -> Lang {
+        self.lang
+    }
+
+    pub fn consoles_mut(&mut self) -> &mut [Console] {
+        &mut self.consoles
+    }
+
+    pub fn focused_console(&self) -> Option<&Console> {
+        self.consoles.get(self.focused)
+    }
+
+    /// What is shown over the consoles as plain text, for the screen reader frontend: the
+    /// question, prompt or picker waiting for an answer, or else the open view.
+    pub fn describe_overlay(&mut self) -> Option<String> {
+        if let Some(confirm) = self.confirm.as_ref() {
+            return Some(confirm.describe());
+        }
+        if let Some(prompt) = self.prompt.as_ref() {
+            return Some(prompt.describe());
+        }
+        if let Some(picker) = self.picker.as_ref() {
+            return Some(picker.describe());
+        }
+        if let Some(tree) = self.process_tree.as_mut() {
+            let tasks = self
+                .consoles
+                .iter()
+                .map(|console| (console.task().key().to_string(), console.pid()))
+                .collect::<Vec<_>>();
+            tree.refresh(&tasks);
+            return Some(tree.describe());
+        }
+        if let Some(inspector) = self.inspector.as_ref() {
+            return Some(inspector.describe());
+        }
+        if self.interacting {
+            let target = self.describe_targets(&[self.focused]);
+            return Some(format!("Typing into {}, Esc stops", target));
+        }
+        None
+    }
+
+    fn scroll_focused(&mut self, scroll: Scroll) {
+        if let Some(console) = self.consoles.get_mut(self.focused) {
+            console.scroll(scroll);
+        }
+    }
+
+    /// Focuses the console, showing its workspace.
+    fn focus(&mut self, index: usize) {
+        if let Some(console) = self.consoles.get_mut(self.focused) {
+            console.set_focused(false);
+        }
+        self.focused = index;
+        if let Some(console) = self.consoles.get_mut(self.focused) {
+            console.set_focused(true);
+            self.workspace = console.workspace().to_string();
+            self.workspace_focus.insert(self.workspace.clone(), index);
+        }
+    }
+
+    /// The workspaces of the tasks in the order they first appear, none if the tasks have none.
+    fn workspaces(&self) -> Vec<String> {
+        let mut workspaces = Vec::<String>::new();
+        for console in self.consoles.iter() {
+            let workspace = console.workspace();
+            if !workspace.is_empty() && !workspaces.iter().any(|w| w == workspace) {
+                workspaces.push(workspace.to_string());
+            }
+        }
+        workspaces
+    }
+
+    /// Shows the workspace with the given 0-based number, focusing the console focused last on
+    /// it.
+    fn switch_workspace(&mut self, number: usize) {
+        let Some(workspace) = self.workspaces().into_iter().nth(number) else {
+            return;
+        };
+        let last_focused = self
+            .workspace_focus
+            .get(&workspace)
+            .copied()
+            .filter(|&index| {
+                self.consoles
+                    .get(index)
+                    .is_some_and(|c| c.is_shown(&workspace))
+            });
+        let index =
+            last_focused.or_else(|| self.consoles.iter().position(|c| c.is_shown(&workspace)));
+        match index {
+            Some(index) => self.focus(index),
+            // every console on it is hidden
+            None => self.workspace = workspace,
+        }
+    }
+
+    /// Moves the focus to the next (or previous) visible console, wrapping around.
+    fn focus_next(&mut self, forward: bool) {
+        let len = self.consoles.len();
+        let next = (1..=len)
+            .map(|step| {
+                if forward {
+                    (self.focused + step) % len
+                } else {
+                    (self.focused + len - step) % len
+                }
+            })
+            .find(|&index| self.consoles[index].is_shown(&self.workspace));
+        if let Some(index) = next {
+            self.focus(index);
+        }
+    }
+
+    /// Grows (or shrinks) the focused console by a cell: its width within its row of consoles,
+    /// or the height of its row.
+    fn resize_focused(&mut self, direction: Direction, delta: i32) {
+        let Some(console) = self.consoles.get_mut(self.focused) else {
+            return;
+        };
+        if !console.is_shown(&self.workspace) {
+            return;
+        }
+        match direction {
+            Direction::Horizontal => console.set_width_offset(console.width_offset() + delta),
+            Direction::Vertical => {
+                let workspace = self.workspace.as_str();
+                let columns = grid_columns(self.consoles.iter().filter(|c| c.is_shown(workspace)));
+                let position = self.consoles[..self.focused]
+                    .iter()
+                    .filter(|c| c.is_shown(workspace))
+                    .count();
+                let row_offsets = self.row_offsets.entry(self.workspace.clone()).or_default();
+                if let Some(offset) = row_offsets.get_mut(position / columns) {
+                    *offset += delta;
+                }
+            }
+        }
+    }
+
+    fn hide_focused(&mut self) {
+        if let Some(console) = self.consoles.get_mut(self.focused) {
+            if !console.is_hidden() {
+                console.hide();
+                self.focus_next(true);
+            }
+        }
+    }
+
+    /// Shows the consoles whose tasks match the filter and hides the others, or shows every
+    /// console again without a filter.
+    fn filter_tags(&mut self, filter: Option<TagFilter>) {
+        for console in self.consoles.iter_mut() {
+            match filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(console.task()))
+            {
+                true => console.show(),
+                false => console.hide(),
+            }
+        }
+        let shown = self.consoles.iter().filter(|c| !c.is_hidden()).count();
+        match filter.as_ref() {
+            Some(filter) => self.notify(format!(
+                "Showing {} of {} consoles tagged {}",
+                shown,
+                self.consoles.len(),
+                filter
+            )),
+            None => self.notify("Showing every console".to_string()),
+        }
+        if self
+            .consoles
+            .get(self.focused)
+            .is_some_and(Console::is_hidden)
+        {
+            self.focus_next(true);
+        }
+        self.tag_filter = filter;
+    }
+
+    fn open_unhide_picker(&mut self) {
+        let items = self
+            .consoles
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_hidden())
+            .map(|(index, c)| (c.raw_command().to_string(), PickerItem::Unhide(index)))
+            .collect::<Vec<_>>();
+        let picker = Picker::new("Unhide", items);
+        if !picker.is_empty() {
+            self.picker = Some(picker);
+        }
+    }
+
+    /// Reloads the config file when parallely receives `SIGHUP`, unless signals are forwarded
+    /// to the tasks or the terminal has been closed.
+    fn listen_reload(&self) {
+        if self.forward_signals {
+            self.shutdown_handler
+                .listen_for_forwarded_signals(self.on_hangup);
+        } else {
+            self.shutdown_handler
+                .listen_for_hangup(self.on_hangup, true);
+        }
     }
 
     fn listen_shutdown(&self) {
         self.shutdown_handler.listen_for_signal();
     }
+
+    /// Periodically requests a redraw, so that status changes (e.g. a task exiting without a
+    /// final line of output) and animations are shown even if no other message arrives.
+    fn listen_ticks(&self) {
+        let message_sender = self.message_sender.clone();
+        self.message_sender.supervisor().spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_millis(250));
+            loop {
+                interval.tick().await;
+                message_sender.need_update();
+            }
+        });
+    }
+}
+
+/// How many consoles are laid out side by side: all of them, or in a grid as wide as the widest
+/// matrix.
+fn grid_columns<'a>(visible: impl Iterator<Item = &'a Console> + Clone) -> usize {
+    let count = visible.clone().count();
+    visible
+        .filter_map(|console| console.task().matrix_columns)
+        .max()
+        .unwrap_or(count)
+        .clamp(1, count.max(1))
+}
+
+/// Lays the panes out again with their sizes resized by `offsets` (see [`layout::resize`]). The
+/// offsets are clamped to how far the panes could be resized, so that pressing `Ctrl+→` on a
+/// pane which can't grow anymore doesn't need as many `Ctrl+←` to undo.
+fn resize_panes(
+    direction: Direction,
+    area: Rect,
+    panes: &[Rect],
+    offsets: &mut [i32],
+) -> Vec<Rect> {
+    if offsets.iter().all(|offset| *offset == 0) {
+        return panes.to_vec();
+    }
+    let size = |rect: &Rect| match direction {
+        Direction::Horizontal => rect.width,
+        Direction::Vertical => rect.height,
+    };
+    let base = panes.iter().map(size).collect::<Vec<_>>();
+    let sizes = layout::resize(&base, offsets);
+    for ((offset, size), base) in offsets.iter_mut().zip(sizes.iter()).zip(base) {
+        if *offset != 0 {
+            *offset = *size as i32 - base as i32;
+        }
+    }
+    // the panes may not fill the area, e.g. the last row of a grid, which stays centered
+    let start = panes.first().map_or(area, |first| *first);
+    let area = match direction {
+        Direction::Horizontal => Rect {
+            x: start.x,
+            width: sizes.iter().sum(),
+            ..area
+        },
+        Direction::Vertical => Rect {
+            y: start.y,
+            height: sizes.iter().sum(),
+            ..area
+        },
+    };
+    Layout::new(direction, sizes.into_iter().map(Constraint::Length))
+        .split(area)
+        .to_vec()
+}
+
+fn new_console(
+    task: TaskConfig,
+    message_sender: &MessageSender,
+    log_options: Option<&LogOptions>,
+    forwarder: Option<&Arc<LogForwarder>>,
+    plugins: Option<&Arc<Plugins>>,
+    scroll_step: usize,
+) -> Console {
+    let log = log_options.map(|options| TaskLog::new(options.clone(), task.key()));
+    let mut console = Console::new(task, message_sender.clone());
+    console.set_scroll_step(scroll_step);
+    if let Some(log) = log {
+        console.set_log(log);
+    }
+    if let Some(forwarder) = forwarder {
+        console.set_forwarder(forwarder.clone());
+    }
+    if let Some(plugins) = plugins {
+        console.set_plugins(plugins.clone());
+    }
+    console.watch();
+    console
 }
 
 impl StatefulWidget for &mut App {
@@ -143,43 +1678,217 @@ impl StatefulWidget for &mut App {
     where
         Self: Sized,
     {
-        let pid = std::process::id();
-        let title = Title::from(format!(" Parallely - ({pid})").bold());
-        let instructions = Title::from(Line::from(vec![" Quit ".into(), "<Q> ".blue().bold()]));
-        let container = Block::default()
-            .title(title.alignment(Alignment::Center))
-            .title(instructions.alignment(Alignment::Right));
-
-        let areas = Layout::horizontal(
-            self.consoles
-                .iter()
-                .map(|_| Constraint::Fill(0))
-                .collect::<Vec<_>>(),
-        )
-        .flex(Flex::Center)
-        .split(container.inner(area));
+        let marked = self.consoles.iter().filter(|c| c.is_marked()).count();
+        let mode = if self.prompt.is_some() {
+            Mode::Prompt
+        } else if self.interacting {
+            Mode::Interact
+        } else if marked > 0 {
+            Mode::Select(marked)
+        } else {
+            Mode::Normal
+        };
+        let status_bar = StatusBar {
+            states: self.consoles.iter().map(Console::state).collect(),
+            workspaces: self.workspaces(),
+            workspace: self.workspace.clone(),
+            elapsed: self.started_at.elapsed().unwrap_or_default(),
+            mode,
+            frozen: self.frozen,
+            zoomed: self.zoomed,
+            read_only: self.read_only,
+            lang: self.lang,
+        };
+        let [status_area, area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        status_bar.render(status_area, buf);
+        let mut container = Block::default();
+        match &self.notice {
+            Some((notice, since)) if since.elapsed() < NOTICE_DURATION => {
+                container = container.title_bottom(Line::from(format!(" {} ", notice)).yellow());
+            }
+            Some(_) => self.notice = None,
+            None => {}
+        }
+        for summary in self.replica_summaries() {
+            container = container.title_bottom(Line::from(format!(" {} ", summary)).cyan());
+        }
+        if let Some(console) = self.consoles.get(self.focused) {
+            let mut actions = Line::default();
+            for action in console.task().actions.iter() {
+                actions.push_span(format!(" {} ", action.label()));
+                actions.push_span(format!("<{}> ", action.key).blue().bold());
+            }
+            if !actions.spans.is_empty() {
+                container = container.title_bottom(actions.right_aligned());
+            }
+        }
 
-        for (index, rect) in areas.iter().enumerate() {
-            self.consoles[index].render(*rect, buf, context);
+        let inner_area = container.inner(area);
+        context.compact = self.compact;
+        context.lang = self.lang;
+        let workspace = self.workspace.as_str();
+        let zoomed = self.zoomed
+            && self
+                .consoles
+                .get(self.focused)
+                .is_some_and(|console| console.is_shown(workspace));
+        let mut visible = self
+            .consoles
+            .iter_mut()
+            .filter(|c| c.is_shown(workspace))
+            .collect::<Vec<_>>();
+
+        if zoomed {
+            self.consoles[self.focused].render(inner_area, buf, context);
+        } else if visible.is_empty() {
+            Paragraph::new("All consoles are hidden, press <Shift+H> to unhide".dark_gray())
+                .alignment(Alignment::Center)
+                .render(inner_area, buf);
+        } else {
+            let columns = grid_columns(visible.iter().map(|console| &**console));
+            let weights = visible
+                .iter_mut()
+                .map(|console| match self.auto_layout {
+                    true => console.layout_weight(),
+                    false => 0,
+                })
+                .collect::<Vec<_>>();
+            let rows = Layout::vertical(
+                weights
+                    .chunks(columns)
+                    .map(|weights| {
+                        Constraint::Fill(weights.iter().copied().max().unwrap_or(0).max(1))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .split(inner_area);
+            let row_offsets = self.row_offsets.entry(workspace.to_string()).or_default();
+            row_offsets.resize(rows.len(), 0);
+            let rows = resize_panes(Direction::Vertical, inner_area, &rows, row_offsets);
+            for ((row, consoles), weights) in rows
+                .iter()
+                .zip(visible.chunks_mut(columns))
+                .zip(weights.chunks(columns))
+            {
+                let areas = Layout::horizontal(
+                    weights
+                        .iter()
+                        .map(|weight| Constraint::Fill(*weight))
+                        .collect::<Vec<_>>(),
+                )
+                .flex(Flex::Center)
+                .split(*row);
+                let mut offsets = consoles
+                    .iter()
+                    .map(|console| console.width_offset())
+                    .collect::<Vec<_>>();
+                let areas = resize_panes(Direction::Horizontal, *row, &areas, &mut offsets);
+                for ((console, rect), offset) in consoles.iter_mut().zip(areas.iter()).zip(offsets)
+                {
+                    console.set_width_offset(offset);
+                    console.render(*rect, buf, context);
+                }
+            }
         }
 
         container.render(area, buf);
+
+        if self.timeline {
+            self.timeline().render(inner_area, buf);
+        }
+        if let Some(inspector) = self.inspector.as_mut() {
+            inspector.render(inner_area, buf);
+        }
+        if let Some(tree) = self.process_tree.as_mut() {
+            let tasks = self
+                .consoles
+                .iter()
+                .map(|console| (console.task().key().to_string(), console.pid()))
+                .collect::<Vec<_>>();
+            tree.refresh(&tasks);
+            tree.render(inner_area, buf);
+        }
+        if let Some(picker) = self.picker.as_mut() {
+            picker.render(inner_area, buf);
+        }
+        if let Some(prompt) = self.prompt.as_ref() {
+            prompt.render(inner_area, buf);
+        }
+        if let Some(confirm) = self.confirm.as_ref() {
+            confirm.render(inner_area, buf);
+        }
+        if self.perf_overlay {
+            let overlay = PerfOverlay {
+                stats: &self.perf,
+                queued_messages: self.message_stream.len(),
+                consoles: self.consoles.iter().map(Console::buffers).collect(),
+            };
+            overlay.render(inner_area, buf);
+        }
     }
 }
 
+/// What an entry of the picker stands for.
+#[derive(Debug, Copy, Clone)]
+enum PickerItem {
+    Unhide(usize),
+    Signal(ChildSignal),
+    /// Type in the name or number of a signal which is not listed.
+    OtherSignal,
+    /// Send the signal to a process started by a task, picked in the process tree.
+    SignalProcess(u32, ChildSignal),
+    /// Restart the task at the index (or every task) whose command changed on reload.
+    RestartChanged(Option<usize>),
+}
+
+/// A destructive action which is confirmed first.
+#[derive(Debug, Clone)]
+enum Confirmable {
+    /// Stop the tasks at the indices.
+    Stop(Vec<usize>),
+    Kill(Vec<usize>),
+    /// Clear the output of the console at the index.
+    Clear(usize),
+    /// Quit and run the task at the index again attached to the terminal.
+    Eject(usize),
+    Quit,
+}
+
+/// What the text entered into the prompt is used for.
+#[derive(Debug, Copy, Clone)]
+enum PromptPurpose {
+    GoToLine,
+    GoToTime,
+    /// Send the signal typed in, by name or number as in the config file.
+    Signal,
+    /// Restart the console with the command typed in.
+    EditCommand(usize),
+    /// Show the consoles with the tags typed in, see [`TagFilter`].
+    Tags,
+}
+
 pub struct AppResult {
-    pub tasks_status: Vec<color_eyre::Result<TaskStatus>>,
+    pub tasks: Vec<TaskReport>,
+    /// When the app started executing the tasks.
+    pub started_at: SystemTime,
     pub shutdown_reason: ShutdownReason,
+    /// The task ejected with `o`.
+    pub ejected: Option<TaskConfig>,
 }
 
 impl AppResult {
     pub fn new(
-        tasks_status: Vec<color_eyre::Result<TaskStatus>>,
+        tasks: Vec<TaskReport>,
+        started_at: SystemTime,
         shutdown_reason: ShutdownReason,
+        ejected: Option<TaskConfig>,
     ) -> Self {
         Self {
-            tasks_status,
+            tasks,
+            started_at,
             shutdown_reason,
+            ejected,
         }
     }
 }
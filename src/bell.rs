@@ -0,0 +1,36 @@
+use crate::task_executor::TaskStatus;
+use clap::ValueEnum;
+use std::io::Write;
+
+/// When to emit the terminal bell, useful when parallely runs in a background tmux pane.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum BellPolicy {
+    /// Never ring the bell.
+    #[default]
+    Never,
+    /// Ring the bell whenever a task fails.
+    OnFailure,
+    /// Ring the bell whenever a task exits, successfully or not.
+    OnComplete,
+}
+
+impl BellPolicy {
+    /// Rings the bell if the policy matches a task which has just stopped executing.
+    pub fn notify(&self, status: &TaskStatus) {
+        let ring = match self {
+            BellPolicy::Never => false,
+            BellPolicy::OnFailure => status.is_failed(),
+            BellPolicy::OnComplete => true,
+        };
+        if ring {
+            Self::ring();
+        }
+    }
+
+    fn ring() {
+        let mut stdout = std::io::stdout();
+        if let Err(e) = stdout.write_all(b"\x07").and_then(|_| stdout.flush()) {
+            tracing::warn!("Failed to ring the bell: {}", e);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::config::Shell;
+use crate::message;
+use crate::message::Message;
+use crate::summary;
+use crate::task_executor::{Executable, TaskExecutor, TaskStatus};
+use color_eyre::eyre::WrapErr;
+use serde_json::json;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio_stream::StreamExt;
+
+#[derive(Debug, Error)]
+pub enum BenchError {
+    #[error("Interrupted after {0} of {1} runs")]
+    Interrupted(usize, usize),
+    #[error("{0} of {1} runs failed")]
+    Failed(usize, usize),
+}
+
+/// The minimum, mean, maximum, 95th percentile and standard deviation of the durations of the
+/// runs.
+struct Stats {
+    min: Duration,
+    mean: Duration,
+    max: Duration,
+    p95: Duration,
+    stddev: Duration,
+}
+
+impl Stats {
+    fn of(durations: &[Duration]) -> Option<Self> {
+        let mut sorted = durations.to_vec();
+        sorted.sort();
+        let (min, max) = (*sorted.first()?, *sorted.last()?);
+        let mean = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+        let variance = sorted
+            .iter()
+            .map(|duration| (duration.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / sorted.len() as f64;
+        // nearest rank, the slowest run of 10 or less
+        let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+        Some(Stats {
+            min,
+            mean,
+            max,
+            p95: sorted[rank.clamp(1, sorted.len()) - 1],
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+/// Runs `command` `runs` times for `parallely bench`, `parallel` runs at a time, and prints the
+/// statistics of how long the runs took. The output of the command is discarded. Failed runs
+/// count into the statistics as well, but make the benchmark fail in the end.
+pub async fn run(
+    command: &str,
+    runs: usize,
+    parallel: usize,
+    json: Option<&Path>,
+) -> color_eyre::Result<()> {
+    let (message_sender, mut message_stream) = message::message_queue();
+    message_sender.supervisor().spawn(async move {
+        while let Some(message) = message_stream.next().await {
+            if let Message::Error(error) = message {
+                eprintln!("parallely: {:#}", error);
+            }
+        }
+    });
+
+    let result = run_all(command, runs, parallel, &message_sender).await;
+    // the runs are done or killed, stop draining their messages
+    message_sender.supervisor().shutdown().await;
+    let (durations, failed) = result?;
+
+    let Some(stats) = Stats::of(&durations) else {
+        return Ok(());
+    };
+    println!(
+        "Benchmark `{}`: {} runs, {} at a time",
+        command, runs, parallel
+    );
+    println!(
+        "  min {}  mean {} ± {}  max {}  p95 {}",
+        summary::format_duration(stats.min),
+        summary::format_duration(stats.mean),
+        summary::format_duration(stats.stddev),
+        summary::format_duration(stats.max),
+        summary::format_duration(stats.p95)
+    );
+    if let Some(path) = json {
+        write_json(command, parallel, failed, &durations, &stats, path)?;
+    }
+    if failed > 0 {
+        return Err(BenchError::Failed(failed, runs).into());
+    }
+    Ok(())
+}
+
+/// Runs the command `runs` times, `parallel` runs at a time, returns how long the runs took and
+/// how many of them failed.
+async fn run_all(
+    command: &str,
+    runs: usize,
+    parallel: usize,
+    message_sender: &message::MessageSender,
+) -> color_eyre::Result<(Vec<Duration>, usize)> {
+    let mut durations = Vec::with_capacity(runs);
+    let mut failed = 0;
+    while durations.len() < runs {
+        let count = parallel.min(runs - durations.len());
+        let batch = futures::future::join_all(
+            (0..count).map(|_| run_once(command, message_sender.clone())),
+        );
+        let results = tokio::select! {
+            results = batch => results,
+            // the runs of the batch are killed as they are dropped
+            _ = tokio::signal::ctrl_c() => {
+                return Err(BenchError::Interrupted(durations.len(), runs).into());
+            }
+        };
+        for result in results {
+            let (duration, success) = result?;
+            if !success {
+                failed += 1;
+            }
+            durations.push(duration);
+            eprintln!(
+                "run {}/{}: {}{}",
+                durations.len(),
+                runs,
+                summary::format_duration(duration),
+                if success { "" } else { " (failed)" }
+            );
+        }
+    }
+    Ok((durations, failed))
+}
+
+/// Runs the command once, returns how long it took and whether it succeeded.
+async fn run_once(
+    command: &str,
+    message_sender: message::MessageSender,
+) -> color_eyre::Result<(Duration, bool)> {
+    let mut executor = TaskExecutor::new(command.to_string(), Shell::default(), message_sender);
+    executor.command.kill_on_drop(true);
+    let started_at = Instant::now();
+    let mut output = executor.execute()?;
+    let drain = async { while output.recv().await.is_some() {} };
+    let (status, ()) = tokio::join!(executor.wait(), drain);
+    let duration = started_at.elapsed();
+    let success = matches!(status?, TaskStatus::Exited { success: true, .. });
+    Ok((duration, success))
+}
+
+fn write_json(
+    command: &str,
+    parallel: usize,
+    failed: usize,
+    durations: &[Duration],
+    stats: &Stats,
+    path: &Path,
+) -> color_eyre::Result<()> {
+    let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let json = json!({
+        "command": command,
+        "runs": durations.len(),
+        "parallel": parallel,
+        "failed": failed,
+        "durations_ms": durations.iter().copied().map(millis).collect::<Vec<_>>(),
+        "min_ms": millis(stats.min),
+        "mean_ms": millis(stats.mean),
+        "max_ms": millis(stats.max),
+        "p95_ms": millis(stats.p95),
+        "stddev_ms": millis(stats.stddev),
+    });
+    std::fs::write(path, serde_json::to_string_pretty(&json)?)
+        .wrap_err_with(|| format!("Failed to write the benchmark to {}", path.display()))
+}
@@ -0,0 +1,240 @@
+use crate::config::Shell;
+use color_eyre::eyre::WrapErr;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::ffi::OsString;
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use thiserror::Error;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Commands starting with this run a helper built into parallely instead of a program, e.g.
+/// `builtin:static ./dist:8080` or `builtin:proxy 3000->8080`.
+pub const PREFIX: &str = "builtin:";
+
+#[derive(Debug, Error)]
+pub enum BuiltinError {
+    #[error(
+        "Unknown builtin `{0}`, expected `builtin:static DIR:PORT` or `builtin:proxy PORT->PORT`"
+    )]
+    Unknown(String),
+    #[error("Invalid argument `{argument}` of builtin:{builtin}, expected {expected}")]
+    Argument {
+        builtin: String,
+        argument: String,
+        expected: &'static str,
+    },
+}
+
+/// A helper task served by parallely itself with hyper, so that simple setups do not need extra
+/// tools. It runs in a child process of parallely like any other task, printing a line per
+/// request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Builtin {
+    /// Serves the files of the directory on the port, `index.html` for a directory.
+    Static { dir: PathBuf, port: u16 },
+    /// Forwards the requests to the first port to the second one.
+    Proxy { listen: u16, target: u16 },
+}
+
+impl Builtin {
+    pub fn parse(name: &str, argument: &str) -> Result<Self, BuiltinError> {
+        let invalid = |expected| BuiltinError::Argument {
+            builtin: name.to_string(),
+            argument: argument.to_string(),
+            expected,
+        };
+        match name {
+            "static" => {
+                let (dir, port) = argument.rsplit_once(':').ok_or(invalid("DIR:PORT"))?;
+                Ok(Builtin::Static {
+                    dir: PathBuf::from(dir),
+                    port: port.parse().map_err(|_| invalid("DIR:PORT"))?,
+                })
+            }
+            "proxy" => {
+                let (listen, target) = argument.split_once("->").ok_or(invalid("PORT->PORT"))?;
+                Ok(Builtin::Proxy {
+                    listen: listen.parse().map_err(|_| invalid("PORT->PORT"))?,
+                    target: target.parse().map_err(|_| invalid("PORT->PORT"))?,
+                })
+            }
+            _ => Err(BuiltinError::Unknown(name.to_string())),
+        }
+    }
+
+    pub async fn run(self) -> color_eyre::Result<()> {
+        match self {
+            Builtin::Static { dir, port } => {
+                let dir = Arc::new(dir);
+                println!("Serving {} on http://localhost:{}", dir.display(), port);
+                serve(port, move |request| serve_file(dir.clone(), request)).await
+            }
+            Builtin::Proxy { listen, target } => {
+                println!(
+                    "Forwarding http://localhost:{} to http://localhost:{}",
+                    listen, target
+                );
+                serve(listen, move |request| forward(target, request)).await
+            }
+        }
+    }
+}
+
+/// The program and the arguments to run the command with the shell, or else split on whitespace.
+/// A builtin runs as `parallely builtin <name> <argument>`, never with a shell.
+pub fn command_line(raw_command: &str, shell: Shell) -> (OsString, Vec<String>) {
+    if !raw_command.trim_start().starts_with(PREFIX) {
+        if let Some(command_line) = shell.command_line(raw_command) {
+            return command_line;
+        }
+    }
+    let mut args = raw_command
+        .split_whitespace()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let program = args.remove(0);
+    match program.strip_prefix(PREFIX) {
+        Some(name) => {
+            let exe = std::env::current_exe()
+                .map(PathBuf::into_os_string)
+                .unwrap_or_else(|_| OsString::from("parallely"));
+            args.splice(0..0, ["builtin".to_string(), name.to_string()]);
+            (exe, args)
+        }
+        None => (OsString::from(program), args),
+    }
+}
+
+/// Accepts connections on the port of localhost and answers each request with `handle`,
+/// printing a line per request.
+async fn serve<F, Fut>(port: u16, handle: F) -> color_eyre::Result<()>
+where
+    F: Fn(Request<Incoming>) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Response<BoxBody<Bytes, hyper::Error>>> + Send,
+{
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = TcpListener::bind(address)
+        .await
+        .wrap_err_with(|| format!("Failed to listen on {}", address))?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let handle = handle.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request: Request<Incoming>| {
+                let handle = handle.clone();
+                async move {
+                    let started = Instant::now();
+                    let method = request.method().clone();
+                    let uri = request.uri().clone();
+                    let response = handle(request).await;
+                    println!(
+                        "{} {} {} {}ms",
+                        method,
+                        uri,
+                        response.status().as_u16(),
+                        started.elapsed().as_millis()
+                    );
+                    Ok::<_, Infallible>(response)
+                }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                eprintln!("Connection failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn serve_file(
+    dir: Arc<PathBuf>,
+    request: Request<Incoming>,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return text_response(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+    }
+    // only plain components, so that nothing outside of the directory is served
+    let relative = Path::new(request.uri().path().trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return text_response(StatusCode::FORBIDDEN, "forbidden");
+    }
+    let mut path = dir.join(relative);
+    if path.is_dir() {
+        path = path.join("index.html");
+    }
+    match tokio::fs::read(&path).await {
+        Ok(content) => Response::builder()
+            .header("content-type", content_type(&path))
+            .body(full(content))
+            .unwrap_or_default(),
+        Err(_) => text_response(StatusCode::NOT_FOUND, "not found"),
+    }
+}
+
+async fn forward(
+    target: u16,
+    request: Request<Incoming>,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, target));
+    let result = async {
+        let stream = TcpStream::connect(address).await?;
+        let (mut sender, connection) =
+            hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+        tokio::spawn(connection);
+        color_eyre::Result::<_>::Ok(sender.send_request(request).await?)
+    }
+    .await;
+    match result {
+        Ok(response) => response.map(BodyExt::boxed),
+        Err(e) => text_response(StatusCode::BAD_GATEWAY, &format!("{}: {}", address, e)),
+    }
+}
+
+fn full(content: impl Into<Bytes>) -> BoxBody<Bytes, hyper::Error> {
+    Full::new(content.into())
+        .map_err(|never| match never {})
+        .boxed()
+}
+
+fn text_response(status: StatusCode, text: &str) -> Response<BoxBody<Bytes, hyper::Error>> {
+    let mut response = Response::new(full(format!("{}\n", text)));
+    *response.status_mut() = status;
+    response
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "wasm" => "application/wasm",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,825 @@
+pub mod matrix;
+pub mod params;
+pub mod platform;
+pub mod preset;
+pub mod replicas;
+pub mod service;
+pub mod tags;
+
+use crate::config::matrix::Matrix;
+use crate::config::params::Param;
+use crate::config::platform::TaskCommand;
+use crate::config::replicas::Replica;
+use crate::config::service::ServiceConfig;
+use crate::counters::CounterPattern;
+use crate::instance::InstanceMode;
+use crate::probe::ReadyProbe;
+use crate::resolve;
+use crate::task_executor::child_ext::ChildSignal;
+use color_eyre::eyre::WrapErr;
+use encoding_rs::Encoding;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The name of the config file picked up by [`discover`].
+pub const FILE_NAME: &str = "parallely.toml";
+
+/// Searches `dir` and its ancestors for a `parallely.toml`, like cargo does for `Cargo.toml`.
+pub fn discover(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The directory of the configuration of the user, `~/.config/parallely` (`%APPDATA%\parallely`
+/// on Windows), which holds e.g. the presets and plugins.
+pub fn user_dir() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let config_dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    config_dir.map(|dir| dir.join("parallely"))
+}
+
+/// Loads the tasks from the preset and the config file (if any) followed by the given commands,
+/// with their parameters replaced by `params`.
+pub fn load_tasks(
+    preset: Option<&str>,
+    config: Option<&Path>,
+    commands: &[String],
+    params: &[(String, String)],
+) -> color_eyre::Result<Vec<TaskConfig>> {
+    let tasks = load_templates(preset, config, commands)?;
+    let tasks = params::apply(tasks, params)?;
+    let tasks = matrix::expand(tasks);
+    let mut tasks = replicas::expand(tasks);
+    assign_workspaces(&mut tasks);
+    check_dependencies(&tasks)?;
+    Ok(tasks)
+}
+
+/// The tasks as configured, before their parameters are replaced and their matrices expanded.
+pub fn load_templates(
+    preset: Option<&str>,
+    config: Option<&Path>,
+    commands: &[String],
+) -> color_eyre::Result<Vec<TaskConfig>> {
+    let mut tasks = match preset {
+        Some(name) => preset::load(name)?.into_tasks()?,
+        None => Vec::new(),
+    };
+    if let Some(path) = config {
+        tasks.extend(Config::load(path)?.into_tasks()?);
+    }
+    tasks.extend(commands.iter().cloned().map(TaskConfig::new));
+    Ok(tasks)
+}
+
+/// Prints the tasks for `--dry-run`: their names and commands, the platform variant of the
+/// command which was picked, the executable it runs and the tasks they wait for.
+pub fn print(tasks: &[TaskConfig]) {
+    let width = tasks.iter().map(|task| task.key().len()).max().unwrap_or(0);
+    for task in tasks {
+        let variant = match task.command.variant() {
+            Some(variant) => format!("  (command.{})", variant),
+            None => String::new(),
+        };
+        println!("{:<width$}  {}{}", task.key(), task.command, variant);
+        println!("{:<width$}  runs {}", "", resolve::resolve_task(task));
+        if !task.depends_on.is_empty() {
+            println!("{:<width$}  after {}", "", task.depends_on.join(", "));
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DependencyError {
+    #[error("Task `{task}` depends on `{dependency}`, but there is no task with that name")]
+    Unknown { task: String, dependency: String },
+    #[error("Task `{0}` can never start, its dependencies form a cycle")]
+    Cycle(String),
+}
+
+/// Puts the tasks without a workspace on the first one, if any task has a workspace.
+fn assign_workspaces(tasks: &mut [TaskConfig]) {
+    let Some(first) = tasks.iter().find_map(|task| task.workspace.clone()) else {
+        return;
+    };
+    for task in tasks.iter_mut().filter(|task| task.workspace.is_none()) {
+        task.workspace = Some(first.clone());
+    }
+}
+
+/// Makes sure every dependency names a task and there are no cycles, which would never start.
+fn check_dependencies(tasks: &[TaskConfig]) -> Result<(), DependencyError> {
+    let index_of = |key: &str| tasks.iter().position(|task| task.key() == key);
+    for task in tasks {
+        for dependency in task.depends_on.iter() {
+            if index_of(dependency).is_none() {
+                return Err(DependencyError::Unknown {
+                    task: task.key().to_string(),
+                    dependency: dependency.clone(),
+                });
+            }
+        }
+    }
+    // resolve the tasks whose dependencies are all resolved until none are left, whatever
+    // remains when no task can be resolved anymore is on a cycle
+    let mut resolved = vec![false; tasks.len()];
+    loop {
+        let resolvable = (0..tasks.len())
+            .filter(|&index| !resolved[index])
+            .filter(|&index| {
+                tasks[index]
+                    .depends_on
+                    .iter()
+                    .all(|dependency| index_of(dependency).is_some_and(|d| resolved[d]))
+            })
+            .collect::<Vec<_>>();
+        if resolvable.is_empty() {
+            break;
+        }
+        resolvable
+            .into_iter()
+            .for_each(|index| resolved[index] = true);
+    }
+    match resolved.iter().position(|resolved| !resolved) {
+        Some(index) => Err(DependencyError::Cycle(tasks[index].key().to_string())),
+        None => Ok(()),
+    }
+}
+
+/// The content of a parallely config file, e.g.
+///
+/// ```toml
+/// [[tasks]]
+/// name = "api"
+/// command = "nodemon server.js"
+/// restart_signal = "SIGUSR2"
+/// actions = [{ key = "m", send = "rs\n", label = "restart" }]
+///
+/// [[tasks]]
+/// name = "db"
+/// command = "postgres -D data"
+/// ready = { socket = "/tmp/.s.PGSQL.5432" }
+///
+/// [[tasks]]
+/// command = "npm run migrate"
+/// depends_on = ["db"]
+///
+/// [[tasks]]
+/// command = "nginx -g 'daemon off;'"
+/// restart_signal = "HUP"
+/// stop_signal = "QUIT"
+/// ```
+#[derive(Default, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Shown by `parallely presets`.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Ask before stopping or killing tasks and before quitting while tasks run, `false` turns
+    /// the confirmations off like `--yes`.
+    #[serde(default)]
+    pub confirm: Option<bool>,
+
+    /// What happens when a session of this config file is already running, see `--instance`.
+    #[serde(default)]
+    pub instance: Option<InstanceMode>,
+
+    /// A Lua script of automation rules, relative to this file, see `--script`.
+    #[serde(default)]
+    pub script: Option<PathBuf>,
+
+    /// Config files whose services and tasks come first, relative to this file. A task or
+    /// service of the same name here replaces the included one in its place, see
+    /// [`Config::merge`].
+    #[serde(default)]
+    pub include: Vec<PathBuf>,
+
+    /// Containers started before the tasks and stopped with them.
+    #[serde(default)]
+    pub services: Vec<ServiceConfig>,
+
+    #[serde(default)]
+    pub tasks: Vec<TaskConfig>,
+}
+
+#[derive(Debug, Error)]
+pub enum IncludeError {
+    #[error("Config file {} includes itself", .0.display())]
+    Cycle(PathBuf),
+}
+
+impl Config {
+    /// Loads the config file along with the files it includes.
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        Self::load_included(path, &mut Vec::new())
+    }
+
+    /// `including` are the files on the way to this one, to catch a file including itself.
+    fn load_included(path: &Path, including: &mut Vec<PathBuf>) -> color_eyre::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read config file {}", path.display()))?;
+        let mut config: Config = toml::from_str(&content)
+            .wrap_err_with(|| format!("Failed to parse config file {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for task in config.tasks.iter_mut() {
+            task.cwd = task.cwd.take().map(|cwd| dir.join(cwd));
+        }
+        config.script = config.script.take().map(|script| dir.join(script));
+        if config.include.is_empty() {
+            return Ok(config);
+        }
+        let canonical = std::fs::canonicalize(path)?;
+        if including.contains(&canonical) {
+            return Err(IncludeError::Cycle(canonical).into());
+        }
+        including.push(canonical);
+        let mut merged = Config::default();
+        for include in std::mem::take(&mut config.include) {
+            let included = Self::load_included(&dir.join(include), including)
+                .wrap_err_with(|| format!("Failed to include into {}", path.display()))?;
+            merged.merge(included);
+        }
+        including.pop();
+        merged.merge(config);
+        Ok(merged)
+    }
+
+    /// Adds the services and tasks of `other` after the ones so far, except for those with the
+    /// name (or command) of one so far, which they replace in its place. The description and
+    /// settings of `other` win where they are given.
+    fn merge(&mut self, other: Config) {
+        self.description = other.description.or(self.description.take());
+        self.confirm = other.confirm.or(self.confirm);
+        self.instance = other.instance.or(self.instance);
+        self.script = other.script.or(self.script.take());
+        for service in other.services {
+            match self.services.iter_mut().find(|s| s.name == service.name) {
+                Some(existing) => *existing = service,
+                None => self.services.push(service),
+            }
+        }
+        for task in other.tasks {
+            match self.tasks.iter_mut().find(|t| t.key() == task.key()) {
+                Some(existing) => *existing = task,
+                None => self.tasks.push(task),
+            }
+        }
+    }
+
+    /// The tasks running the services followed by the tasks, which all depend on the services.
+    pub fn into_tasks(self) -> color_eyre::Result<Vec<TaskConfig>> {
+        let mut tasks = self
+            .services
+            .iter()
+            .map(ServiceConfig::to_task)
+            .collect::<Result<Vec<_>, _>>()?;
+        for mut task in self.tasks {
+            for service in self.services.iter() {
+                if !task.depends_on.contains(&service.name) {
+                    task.depends_on.push(service.name.clone());
+                }
+            }
+            tasks.push(task);
+        }
+        Ok(tasks)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskConfig {
+    /// A plain command or one per platform, see [`TaskCommand`].
+    pub command: TaskCommand,
+
+    /// Identifies the task across config reloads, defaults to the command.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Sent on restart instead of stopping and spawning the task again, for tasks which reload
+    /// themselves on a signal.
+    #[serde(default)]
+    pub restart_signal: Option<ChildSignal>,
+
+    /// Sent to stop the task gracefully, `SIGTERM` by default.
+    #[serde(default)]
+    pub stop_signal: Option<ChildSignal>,
+
+    /// What to do when the task clears the screen.
+    #[serde(default)]
+    pub on_clear: ClearMode,
+
+    /// How the output of the task is shown.
+    #[serde(default)]
+    pub ui: TaskUi,
+
+    /// When the task counts as ready. Without a probe, a task is ready once it has exited
+    /// successfully, e.g. a build step.
+    #[serde(default)]
+    pub ready: Option<ReadyProbe>,
+
+    /// The names of the tasks which have to be ready before this one is started.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    /// Read at most this many lines of output per second, which slows down a task printing
+    /// faster once its pipe is full.
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+
+    /// Single keystroke actions while the console of the task is focused.
+    #[serde(default)]
+    pub actions: Vec<QuickAction>,
+
+    /// Connect the standard input of the task, so that it can be typed into with `i`.
+    #[serde(default)]
+    pub interactive: bool,
+
+    /// Keep at most this many lines of output, the oldest ones are dropped beyond that.
+    #[serde(default)]
+    pub max_lines: Option<usize>,
+
+    /// The workspace the console of the task is on, e.g. `backend`. Only the consoles of one
+    /// workspace are shown at a time, switched with `F1` … `F11` in the order the workspaces
+    /// first appear. Tasks without one are on the first workspace.
+    #[serde(default)]
+    pub workspace: Option<String>,
+
+    /// Show at most this many bytes of a line, 16 KiB by default and `0` for no limit. The rest
+    /// is replaced by how much was cut off until expanded with `Shift+L`.
+    #[serde(default)]
+    pub max_line_length: Option<usize>,
+
+    /// Restart the task when a file in these files or directories changes, relative to its
+    /// working directory. A task which has already exited is run again. Hidden directories,
+    /// `target` and `node_modules` are not watched.
+    #[serde(default)]
+    pub watch: Vec<PathBuf>,
+
+    /// Tags to pick the tasks to run with `--tags`, e.g. `["backend", "slow"]`, and the consoles
+    /// to show with `#`, see [`tags::TagFilter`].
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Regular expressions whose matching lines are counted live in a sidebar next to the
+    /// output, along with the last line which matched, e.g. `["requests? served", "ERROR"]`.
+    #[serde(default)]
+    pub counters: Vec<CounterPattern>,
+
+    /// Whether signals received by parallely are passed on to the task with `--forward-signals`.
+    #[serde(default = "default_true")]
+    pub forward_signals: bool,
+
+    /// What to show when the task writes output which is not text.
+    #[serde(default)]
+    pub on_binary: BinaryMode,
+
+    /// The encoding of the output of the task, if it is not UTF-8, see [`OutputEncoding`].
+    /// `--encoding` sets it for the tasks without one.
+    #[serde(default)]
+    pub encoding: Option<OutputEncoding>,
+
+    /// Run when the task exits successfully, with `PARALLELY_TASK`, `PARALLELY_EXIT_CODE`,
+    /// `PARALLELY_DURATION` (in seconds) and `PARALLELY_LOG_FILE` (with `--log-dir`) set.
+    #[serde(default)]
+    pub after_success: Option<String>,
+
+    /// Run when the task exits with a failure, with the same variables as `after_success`.
+    #[serde(default)]
+    pub after_failure: Option<String>,
+
+    /// The exit codes the task succeeds with, `[0]` by default, e.g. `[0, 130]` for a task which
+    /// exits with 130 when interrupted, so that it is not reported as failed nor cancels the
+    /// others with `--cancel-on-failure`. A task terminated by a signal exits with 128 plus the
+    /// signal, as a shell reports it.
+    #[serde(default = "default_success_exit_codes")]
+    pub success_exit_codes: Vec<i32>,
+
+    /// Leave the task running if parallely is killed or crashes, e.g. a service meant to outlive
+    /// it. Otherwise it is killed along with parallely on Linux and Windows, see
+    /// [`crate::task_executor::TaskExecutor::kill_with_parent`]. Every task is detached with
+    /// `--on-hangup detach`.
+    #[serde(default)]
+    pub detached: bool,
+
+    /// Whether the task is started again when it exits on its own.
+    #[serde(default)]
+    pub restart: RestartPolicy,
+
+    /// When the task stops being restarted, see [`StartLimit`].
+    #[serde(default)]
+    pub start_limit: StartLimit,
+
+    /// The working directory of the task, relative to the config file it is given in.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+
+    /// The variables of the environment of the working directory, from its `.env` and its
+    /// `.envrc` (with direnv), which are passed to the task, see [`crate::direnv::load`]. None
+    /// are loaded by default.
+    #[serde(default)]
+    pub load_env: Vec<String>,
+
+    /// Put the `node_modules/.bin` of the working directory and its ancestors first on `PATH`,
+    /// so that e.g. `vite` runs without `npx`, see [`crate::project_path::env`].
+    #[serde(default)]
+    pub node_modules: bool,
+
+    /// Run the task in the nearest Python virtual environment, `.venv` or `venv` in the working
+    /// directory or its ancestors, as if it was activated, so that e.g. `pytest` runs.
+    #[serde(default)]
+    pub venv: bool,
+
+    /// The shell the command is run with, none by default.
+    #[serde(default)]
+    pub shell: Shell,
+
+    /// Show stdout and stderr in panes of their own, toggled with `|`.
+    #[serde(default)]
+    pub split_output: bool,
+
+    /// Values asked for at startup and replaced in the command, see [`Param`].
+    #[serde(default)]
+    pub params: Vec<Param>,
+
+    /// Run the task once per combination of these variables, see [`Matrix`].
+    #[serde(default)]
+    pub matrix: Option<Matrix>,
+
+    /// How many columns the tasks expanded from a matrix are laid out in.
+    #[serde(skip)]
+    pub matrix_columns: Option<usize>,
+
+    /// Run this many identical copies of the task, see [`replicas::expand`].
+    #[serde(default)]
+    pub replicas: Option<usize>,
+
+    /// Which copy of a task with `replicas` this is.
+    #[serde(skip)]
+    pub replica: Option<Replica>,
+}
+
+/// An action bound to a key of the console of a task, e.g.
+///
+/// ```toml
+/// actions = [{ key = "m", send = "rs\n", label = "restart" }, { key = "u", signal = "SIGUSR1" }]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RawQuickAction")]
+pub struct QuickAction {
+    pub key: char,
+    pub label: Option<String>,
+    pub effect: ActionEffect,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionEffect {
+    /// Write the text to the standard input of the task.
+    Send(String),
+    Signal(ChildSignal),
+}
+
+impl QuickAction {
+    pub fn label(&self) -> String {
+        match (&self.label, &self.effect) {
+            (Some(label), _) => label.clone(),
+            (None, ActionEffect::Send(text)) => format!("send {:?}", text),
+            (None, ActionEffect::Signal(signal)) => signal.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawQuickAction {
+    key: char,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    send: Option<String>,
+    #[serde(default)]
+    signal: Option<ChildSignal>,
+}
+
+#[derive(Debug, Error)]
+pub enum QuickActionError {
+    #[error("The action of key `{0}` needs either `send` or `signal`")]
+    Missing(char),
+    #[error("The action of key `{0}` can only have one of `send` and `signal`")]
+    Ambiguous(char),
+}
+
+impl TryFrom<RawQuickAction> for QuickAction {
+    type Error = QuickActionError;
+
+    fn try_from(raw: RawQuickAction) -> Result<Self, Self::Error> {
+        let effect = match (raw.send, raw.signal) {
+            (Some(text), None) => ActionEffect::Send(text),
+            (None, Some(signal)) => ActionEffect::Signal(signal),
+            (None, None) => return Err(QuickActionError::Missing(raw.key)),
+            (Some(_), Some(_)) => return Err(QuickActionError::Ambiguous(raw.key)),
+        };
+        Ok(Self {
+            key: raw.key,
+            label: raw.label,
+            effect,
+        })
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskUi {
+    /// Collect the lines of stdout and stderr, with scrolling, line numbers and diffs.
+    #[default]
+    Lines,
+    /// Run the task in a pseudo terminal and show its screen, for tasks which move the cursor
+    /// and redraw in place. Only supported on unix, elsewhere it falls back to `lines`.
+    Term,
+}
+
+/// How a console handles the task clearing the screen, e.g. a dev server before every rebuild.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClearMode {
+    /// Keep the output and mark where the screen was cleared.
+    #[default]
+    Separator,
+    /// Discard the output received so far.
+    Reset,
+    /// Keep the output as if the screen was not cleared.
+    Ignore,
+}
+
+/// Whether a task is started again when it exits on its own.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    /// When it exits with a failure.
+    OnFailure,
+    /// Whenever it exits.
+    Always,
+}
+
+/// When a task restarted by its [`RestartPolicy`] is crash-looping, like the start limit of
+/// systemd: once it has exited within `interval` seconds of starting more than `burst` times in
+/// a row, it is not restarted anymore until it is restarted by hand.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct StartLimit {
+    pub burst: u32,
+    pub interval: u64,
+}
+
+impl Default for StartLimit {
+    fn default() -> Self {
+        StartLimit {
+            burst: 5,
+            interval: 10,
+        }
+    }
+}
+
+/// The shell a task's command is run with. Without one, the command is split on whitespace and
+/// run directly, so that nothing in it is interpreted.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Shell {
+    #[default]
+    None,
+    Sh,
+    Bash,
+    Zsh,
+    Fish,
+    Nu,
+    Pwsh,
+    Cmd,
+}
+
+impl Shell {
+    /// The program and the arguments which run `command` with the shell, `None` without one.
+    pub fn command_line(self, command: &str) -> Option<(OsString, Vec<String>)> {
+        let (program, flags): (&str, &[&str]) = match self {
+            Shell::None => return None,
+            Shell::Sh => ("sh", &["-c"]),
+            Shell::Bash => ("bash", &["-c"]),
+            Shell::Zsh => ("zsh", &["-c"]),
+            Shell::Fish => ("fish", &["-c"]),
+            Shell::Nu => ("nu", &["-c"]),
+            Shell::Pwsh => ("pwsh", &["-NoProfile", "-Command"]),
+            Shell::Cmd => ("cmd", &["/C"]),
+        };
+        let mut args = flags
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect::<Vec<_>>();
+        args.push(command.to_string());
+        Some((OsString::from(program), args))
+    }
+}
+
+/// How a console shows output which is not text, e.g. a task writing an image to stdout.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BinaryMode {
+    /// Replace it with a notice of how many bytes were suppressed.
+    #[default]
+    Suppress,
+    /// Add a hexdump of the first bytes to the notice.
+    Hexdump,
+}
+
+/// The character encoding of the output of a task, given by its label, e.g. `gbk`, `shift_jis`,
+/// `euc-kr`, `windows-1252` or `latin1`. The output is transcoded to UTF-8 before it is split into
+/// lines, so that tasks on a system with another locale, e.g. Windows in China, show up readable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct OutputEncoding(&'static Encoding);
+
+#[derive(Debug, Error)]
+pub enum EncodingError {
+    #[error("Unknown encoding `{0}`, expected e.g. `gbk`, `shift_jis` or `latin1`")]
+    Unknown(String),
+    #[error("The encoding `{0}` is not supported, it has to encode line breaks as in ASCII")]
+    Unsupported(String),
+}
+
+impl OutputEncoding {
+    pub fn encoding(&self) -> &'static Encoding {
+        self.0
+    }
+}
+
+impl FromStr for OutputEncoding {
+    type Err = EncodingError;
+
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        let encoding = Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| EncodingError::Unknown(label.to_string()))?;
+        // UTF-16 and the like cannot be split into lines before they are decoded
+        if !encoding.is_ascii_compatible() {
+            return Err(EncodingError::Unsupported(label.to_string()));
+        }
+        Ok(Self(encoding))
+    }
+}
+
+impl TryFrom<String> for OutputEncoding {
+    type Error = EncodingError;
+
+    fn try_from(label: String) -> Result<Self, Self::Error> {
+        label.parse()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_success_exit_codes() -> Vec<i32> {
+    vec![0]
+}
+
+impl TaskConfig {
+    pub fn new(command: String) -> Self {
+        Self {
+            command: TaskCommand::new(command),
+            name: None,
+            restart_signal: None,
+            stop_signal: None,
+            on_clear: ClearMode::default(),
+            ui: TaskUi::default(),
+            ready: None,
+            depends_on: Vec::new(),
+            rate_limit: None,
+            actions: Vec::new(),
+            interactive: false,
+            max_lines: None,
+            workspace: None,
+            watch: Vec::new(),
+            tags: Vec::new(),
+            counters: Vec::new(),
+            max_line_length: None,
+            forward_signals: true,
+            on_binary: BinaryMode::default(),
+            encoding: None,
+            after_success: None,
+            after_failure: None,
+            success_exit_codes: default_success_exit_codes(),
+            detached: false,
+            restart: RestartPolicy::default(),
+            start_limit: StartLimit::default(),
+            cwd: None,
+            load_env: Vec::new(),
+            node_modules: false,
+            venv: false,
+            shell: Shell::default(),
+            split_output: false,
+            params: Vec::new(),
+            matrix: None,
+            matrix_columns: None,
+            replicas: None,
+            replica: None,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.command)
+    }
+
+    /// A short name for the task in external tools: its name, or the program it runs.
+    pub fn identifier(&self) -> &str {
+        self.name.as_deref().unwrap_or_else(|| {
+            let program = self.command.split_whitespace().next().unwrap_or_default();
+            program.rsplit(['/', '\\']).next().unwrap_or(program)
+        })
+    }
+
+    pub fn stop_signal(&self) -> ChildSignal {
+        self.stop_signal.unwrap_or(ChildSignal::Terminate)
+    }
+
+    /// Whether the task is interactive or an action writes to its standard input, which is
+    /// only connected then.
+    pub fn takes_input(&self) -> bool {
+        self.interactive
+            || self
+                .actions
+                .iter()
+                .any(|action| matches!(action.effect, ActionEffect::Send(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discover, FILE_NAME};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// An empty directory of its own for a test, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "parallely-discover-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn finds_the_file_of_an_ancestor() {
+        let root = TempDir::new("ancestor");
+        let nested = root.0.join("a/b/c");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.0.join(FILE_NAME), "").unwrap();
+        assert_eq!(discover(&nested), Some(root.0.join(FILE_NAME)));
+    }
+
+    #[test]
+    fn prefers_the_nearest_file() {
+        let root = TempDir::new("nearest");
+        let nested = root.0.join("a/b/c");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.0.join(FILE_NAME), "").unwrap();
+        fs::write(root.0.join("a/b").join(FILE_NAME), "").unwrap();
+        assert_eq!(discover(&nested), Some(root.0.join("a/b").join(FILE_NAME)));
+        assert_eq!(discover(&root.0.join("a")), Some(root.0.join(FILE_NAME)));
+    }
+
+    #[test]
+    fn ignores_a_directory_of_the_name() {
+        let root = TempDir::new("directory");
+        fs::create_dir_all(root.0.join("a").join(FILE_NAME)).unwrap();
+        fs::write(root.0.join(FILE_NAME), "").unwrap();
+        assert_eq!(discover(&root.0.join("a")), Some(root.0.join(FILE_NAME)));
+    }
+
+    #[test]
+    fn finds_nothing_past_the_root() {
+        let root = Path::new("/");
+        assert!(!root.join(FILE_NAME).exists());
+        assert_eq!(discover(root), None);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::config::TaskConfig;
+use thiserror::Error;
+
+/// The variables a task is expanded over, e.g.
+///
+/// ```toml
+/// [[tasks]]
+/// name = "test-{node}-{package}"
+/// command = "npx -p node@{node} npm test -w {package}"
+/// matrix = { node = [18, 20], package = ["api", "web"] }
+/// ```
+///
+/// runs one task per combination of the values, with `{variable}` replaced in `command`, `name`
+/// and `depends_on`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(try_from = "toml::Table")]
+pub struct Matrix(Vec<(String, Vec<String>)>);
+
+#[derive(Debug, Error)]
+pub enum MatrixError {
+    #[error("The matrix variable `{0}` needs a list of values")]
+    NotList(String),
+    #[error("The matrix variable `{0}` has no values")]
+    Empty(String),
+    #[error("The value of the matrix variable `{0}` cannot be a table or a list")]
+    Nested(String),
+}
+
+impl TryFrom<toml::Table> for Matrix {
+    type Error = MatrixError;
+
+    fn try_from(table: toml::Table) -> Result<Self, Self::Error> {
+        let variables = table
+            .into_iter()
+            .map(|(variable, values)| {
+                let toml::Value::Array(values) = values else {
+                    return Err(MatrixError::NotList(variable));
+                };
+                if values.is_empty() {
+                    return Err(MatrixError::Empty(variable));
+                }
+                let values = values
+                    .into_iter()
+                    .map(|value| match value {
+                        toml::Value::String(value) => Ok(value),
+                        toml::Value::Array(_) | toml::Value::Table(_) => {
+                            Err(MatrixError::Nested(variable.clone()))
+                        }
+                        value => Ok(value.to_string()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((variable, values))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(variables))
+    }
+}
+
+impl Matrix {
+    /// Every combination of the values as `(variable, value)` pairs, the last variable changes
+    /// the fastest.
+    fn combinations(&self) -> Vec<Vec<(&str, &str)>> {
+        self.0
+            .iter()
+            .fold(vec![Vec::new()], |combinations, (variable, values)| {
+                combinations
+                    .into_iter()
+                    .flat_map(|combination| {
+                        values.iter().map(move |value| {
+                            let mut combination = combination.clone();
+                            combination.push((variable.as_str(), value.as_str()));
+                            combination
+                        })
+                    })
+                    .collect()
+            })
+    }
+
+    /// How many values the last variable has, which is used as the number of columns to lay out
+    /// the expanded tasks in.
+    fn columns(&self) -> usize {
+        self.0.last().map_or(1, |(_, values)| values.len())
+    }
+}
+
+/// Replaces every task with a matrix by one task per combination of its values. A task without a
+/// `name` is named after its program and the values, e.g. `npx (node=18, package=api)`.
+pub fn expand(tasks: Vec<TaskConfig>) -> Vec<TaskConfig> {
+    tasks
+        .into_iter()
+        .flat_map(|mut task| {
+            let Some(matrix) = task.matrix.take() else {
+                return vec![task];
+            };
+            matrix
+                .combinations()
+                .into_iter()
+                .map(|combination| {
+                    let substitute = |template: &str| {
+                        combination
+                            .iter()
+                            .fold(template.to_string(), |text, (variable, value)| {
+                                text.replace(&format!("{{{}}}", variable), value)
+                            })
+                    };
+                    let mut expanded = task.clone();
+                    expanded.command.set_line(substitute(&task.command));
+                    expanded.name = Some(match task.name.as_deref() {
+                        Some(name) => substitute(name),
+                        None => {
+                            let values = combination
+                                .iter()
+                                .map(|(variable, value)| format!("{}={}", variable, value))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            format!("{} ({})", task.identifier(), values)
+                        }
+                    });
+                    expanded.depends_on = task
+                        .depends_on
+                        .iter()
+                        .map(|dependency| substitute(dependency))
+                        .collect();
+                    expanded.matrix_columns = Some(matrix.columns());
+                    expanded
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,139 @@
+use crate::config::TaskConfig;
+use serde::Deserialize;
+use std::io::{BufRead, IsTerminal, Write};
+use thiserror::Error;
+
+/// A value a task asks for at startup, e.g.
+///
+/// ```toml
+/// [[tasks]]
+/// name = "api-{port}"
+/// command = "npm run dev -w {package} -- --port {port}"
+/// params = ["package", "port=3000"]
+/// ```
+///
+/// `{param}` is replaced in `command`, `name`, `depends_on`, `cwd` and `load_env` by the value given with
+/// `--param port=3001`, else typed in when parallely starts in a terminal, else the default
+/// after `=`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub struct Param {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ParamError {
+    #[error("Invalid parameter `{0}`, expected NAME=VALUE")]
+    Invalid(String),
+    #[error("Task `{task}` needs a value for `{param}`, give it with `--param {param}=VALUE`")]
+    Missing { task: String, param: String },
+}
+
+impl From<String> for Param {
+    fn from(value: String) -> Self {
+        match value.split_once('=') {
+            Some((name, default)) => Param {
+                name: name.trim().to_string(),
+                default: Some(default.to_string()),
+            },
+            None => Param {
+                name: value.trim().to_string(),
+                default: None,
+            },
+        }
+    }
+}
+
+/// Parses a `--param NAME=VALUE`.
+pub fn parse_assignment(s: &str) -> Result<(String, String), ParamError> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(ParamError::Invalid(s.to_string())),
+    }
+}
+
+/// Asks for the value of every parameter of the tasks which was not given, if the standard
+/// input is a terminal. An empty answer takes the default. Returns the values typed in.
+pub fn prompt(
+    tasks: &[TaskConfig],
+    given: &[(String, String)],
+) -> color_eyre::Result<Vec<(String, String)>> {
+    let mut answers: Vec<(String, String)> = Vec::new();
+    if !std::io::stdin().is_terminal() {
+        return Ok(answers);
+    }
+    let params = tasks.iter().flat_map(|task| task.params.iter());
+    for param in params {
+        let known = given.iter().chain(answers.iter());
+        if known.map(|(name, _)| name).any(|name| *name == param.name) {
+            continue;
+        }
+        let answer = loop {
+            match param.default.as_deref() {
+                Some(default) => eprint!("{} [{}]: ", param.name, default),
+                None => eprint!("{}: ", param.name),
+            }
+            std::io::stderr().flush()?;
+            let mut line = String::new();
+            if std::io::stdin().lock().read_line(&mut line)? == 0 {
+                return Ok(answers);
+            }
+            match (line.trim(), param.default.as_ref()) {
+                ("", Some(default)) => break default.clone(),
+                ("", None) => continue,
+                (answer, _) => break answer.to_string(),
+            }
+        };
+        answers.push((param.name.clone(), answer));
+    }
+    Ok(answers)
+}
+
+/// Replaces the parameters of every task by their values, the last value given for a name wins.
+pub fn apply(
+    tasks: Vec<TaskConfig>,
+    values: &[(String, String)],
+) -> Result<Vec<TaskConfig>, ParamError> {
+    tasks
+        .into_iter()
+        .map(|mut task| {
+            if task.params.is_empty() {
+                return Ok(task);
+            }
+            let mut substitutions = Vec::new();
+            for param in task.params.iter() {
+                let value = values
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| *name == param.name)
+                    .map(|(_, value)| value)
+                    .or(param.default.as_ref())
+                    .ok_or_else(|| ParamError::Missing {
+                        task: task.key().to_string(),
+                        param: param.name.clone(),
+                    })?;
+                substitutions.push((format!("{{{}}}", param.name), value.clone()));
+            }
+            let substitute = |template: &str| {
+                substitutions
+                    .iter()
+                    .fold(template.to_string(), |text, (placeholder, value)| {
+                        text.replace(placeholder, value)
+                    })
+            };
+            let command = substitute(&task.command);
+            task.command.set_line(command);
+            task.name = task.name.as_deref().map(substitute);
+            task.depends_on = task.depends_on.iter().map(|d| substitute(d)).collect();
+            task.load_env = task.load_env.iter().map(|name| substitute(name)).collect();
+            // a directory which is not valid UTF-8 cannot contain a placeholder either
+            if let Some(cwd) = task.cwd.as_ref().and_then(|cwd| cwd.to_str()) {
+                task.cwd = Some(substitute(cwd).into());
+            }
+            Ok(task)
+        })
+        .collect()
+}
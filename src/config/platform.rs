@@ -0,0 +1,131 @@
+use serde::{Deserialize, Deserializer};
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+
+/// The command of a task, which may differ per platform so that one config file works for a
+/// whole team, e.g.
+///
+/// ```toml
+/// [[tasks]]
+/// name = "clean"
+/// command = { unix = "rm -rf dist", windows = "cmd /c rmdir /s /q dist" }
+/// ```
+///
+/// The variant of the most specific platform is picked while loading the config file: `linux`
+/// or `macos` before `unix`, else `default`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskCommand {
+    line: String,
+    /// The key of the variant which was picked, `None` for a plain command.
+    variant: Option<&'static str>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawCommand {
+    Plain(String),
+    PerPlatform(PlatformCommands),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlatformCommands {
+    #[serde(default)]
+    windows: Option<String>,
+    #[serde(default)]
+    unix: Option<String>,
+    #[serde(default)]
+    linux: Option<String>,
+    #[serde(default)]
+    macos: Option<String>,
+    #[serde(default)]
+    default: Option<String>,
+}
+
+impl PlatformCommands {
+    /// The variants applying to this platform, the most specific one first.
+    fn candidates(self) -> [(&'static str, Option<String>); 3] {
+        if cfg!(windows) {
+            [
+                ("windows", self.windows),
+                ("default", self.default),
+                ("", None),
+            ]
+        } else if cfg!(target_os = "macos") {
+            [
+                ("macos", self.macos),
+                ("unix", self.unix),
+                ("default", self.default),
+            ]
+        } else if cfg!(target_os = "linux") {
+            [
+                ("linux", self.linux),
+                ("unix", self.unix),
+                ("default", self.default),
+            ]
+        } else {
+            [("unix", self.unix), ("default", self.default), ("", None)]
+        }
+    }
+}
+
+impl TaskCommand {
+    pub fn new(line: String) -> Self {
+        TaskCommand {
+            line,
+            variant: None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.line
+    }
+
+    /// Replaces the command line, keeping which variant it came from.
+    pub fn set_line(&mut self, line: String) {
+        self.line = line;
+    }
+
+    /// `command.<variant>` if the command was picked for this platform.
+    pub fn variant(&self) -> Option<&'static str> {
+        self.variant
+    }
+}
+
+impl Deref for TaskCommand {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.line
+    }
+}
+
+impl Display for TaskCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.line)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskCommand {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match RawCommand::deserialize(deserializer)? {
+            RawCommand::Plain(line) => Ok(TaskCommand::new(line)),
+            RawCommand::PerPlatform(commands) => commands
+                .candidates()
+                .into_iter()
+                .find_map(|(variant, line)| {
+                    line.map(|line| TaskCommand {
+                        line,
+                        variant: Some(variant),
+                    })
+                })
+                .ok_or_else(|| {
+                    serde::de::Error::custom(format!(
+                        "no command for {}, give `{}` or `default`",
+                        std::env::consts::OS,
+                        if cfg!(windows) { "windows" } else { "unix" }
+                    ))
+                }),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::config;
+use crate::config::Config;
+use color_eyre::eyre::WrapErr;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// The presets shipped with parallely as `(name, content)`.
+const BUILTIN: [(&str, &str); 3] = [
+    (
+        "fullstack-node",
+        include_str!("presets/fullstack-node.toml"),
+    ),
+    ("rust", include_str!("presets/rust.toml")),
+    ("django", include_str!("presets/django.toml")),
+];
+
+#[derive(Debug, Error)]
+pub enum PresetError {
+    #[error("Unknown preset `{0}`, run `parallely presets` to list the available ones")]
+    Unknown(String),
+}
+
+pub struct Preset {
+    pub name: String,
+    pub description: Option<String>,
+    /// Where the preset was loaded from, `None` if it is built in.
+    pub path: Option<PathBuf>,
+}
+
+/// The directory of the user presets, `<name>.toml` in there overrides the built-in preset of
+/// the same name.
+pub fn user_dir() -> Option<PathBuf> {
+    config::user_dir().map(|dir| dir.join("presets"))
+}
+
+pub fn load(name: &str) -> color_eyre::Result<Config> {
+    let user_preset = user_dir()
+        .map(|dir| dir.join(format!("{}.toml", name)))
+        .filter(|path| path.is_file());
+    if let Some(path) = user_preset {
+        return Config::load(&path);
+    }
+    let (_, content) = BUILTIN
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .ok_or_else(|| PresetError::Unknown(name.to_string()))?;
+    toml::from_str(content).wrap_err_with(|| format!("Failed to parse preset {}", name))
+}
+
+/// The built-in and the user presets sorted by name.
+pub fn list() -> color_eyre::Result<Vec<Preset>> {
+    let mut presets = BUILTIN
+        .iter()
+        .map(|(name, content)| {
+            let config: Config = toml::from_str(content)
+                .wrap_err_with(|| format!("Failed to parse preset {}", name))?;
+            Ok(Preset {
+                name: name.to_string(),
+                description: config.description,
+                path: None,
+            })
+        })
+        .collect::<color_eyre::Result<Vec<_>>>()?;
+    let entries = user_dir().and_then(|dir| std::fs::read_dir(dir).ok());
+    for path in entries.into_iter().flatten().flatten().map(|e| e.path()) {
+        let Some(name) = path
+            .extension()
+            .filter(|extension| *extension == "toml")
+            .and(path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        let preset = Preset {
+            description: Config::load(&path)?.description,
+            name,
+            path: Some(path),
+        };
+        presets.retain(|p| p.name != preset.name);
+        presets.push(preset);
+    }
+    presets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(presets)
+}
+
+/// Prints the presets for `parallely presets`.
+pub fn print() -> color_eyre::Result<()> {
+    let presets = list()?;
+    let width = presets.iter().map(|p| p.name.len()).max().unwrap_or(0);
+    for preset in presets {
+        let source = match &preset.path {
+            Some(path) => format!(" ({})", path.display()),
+            None => String::new(),
+        };
+        println!(
+            "{:<width$}  {}{}",
+            preset.name,
+            preset.description.unwrap_or_default(),
+            source
+        );
+    }
+    if let Some(dir) = user_dir() {
+        println!(
+            "\nAdd or override presets with <name>.toml in {}",
+            dir.display()
+        );
+    }
+    Ok(())
+}
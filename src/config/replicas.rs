@@ -0,0 +1,74 @@
+use crate::config::TaskConfig;
+
+/// Which of the identical copies of a task, see [`expand`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replica {
+    /// The name of the task the copies were made of.
+    pub group: String,
+    /// From 1 to `count`.
+    pub index: usize,
+    pub count: usize,
+}
+
+/// Replaces every task with `replicas` by that many copies of it, e.g.
+///
+/// ```toml
+/// [[tasks]]
+/// name = "client"
+/// command = "node client.js --id {replica}"
+/// replicas = 8
+/// ```
+///
+/// runs `client#1` to `client#8` with `{replica}` replaced by the number of the copy in `command`,
+/// and `PARALLELY_REPLICA` and `PARALLELY_REPLICAS` set. A task which depends on the task waits
+/// for all of its copies.
+pub fn expand(tasks: Vec<TaskConfig>) -> Vec<TaskConfig> {
+    let groups = tasks
+        .iter()
+        .filter_map(|task| Some((task.key().to_string(), task.replicas?)))
+        .collect::<Vec<_>>();
+    tasks
+        .into_iter()
+        .flat_map(|mut task| {
+            task.depends_on = task
+                .depends_on
+                .iter()
+                .flat_map(
+                    |dependency| match groups.iter().find(|(group, _)| group == dependency) {
+                        Some((group, count)) => (1..=*count)
+                            .map(|index| replica_name(group, index))
+                            .collect(),
+                        None => vec![dependency.clone()],
+                    },
+                )
+                .collect();
+            let Some(count) = task.replicas.take() else {
+                return vec![task];
+            };
+            let group = task.key().to_string();
+            (1..=count)
+                .map(|index| {
+                    let mut replica = task.clone();
+                    replica
+                        .command
+                        .set_line(task.command.replace("{replica}", &index.to_string()));
+                    replica.name = Some(replica_name(&group, index));
+                    // a grid about as wide as it is high
+                    replica.matrix_columns = task
+                        .matrix_columns
+                        .or(Some((count as f64).sqrt().ceil() as usize));
+                    replica.replica = Some(Replica {
+                        group: group.clone(),
+                        index,
+                        count,
+                    });
+                    replica
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn replica_name(group: &str, index: usize) -> String {
+    format!("{}#{}", group, index)
+}
@@ -0,0 +1,107 @@
+use crate::config::TaskConfig;
+use crate::instance::INSTANCE_VAR;
+use crate::probe::ReadyProbe;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// A container the tasks depend on, run with docker for as long as parallely runs, e.g.
+///
+/// ```toml
+/// [[services]]
+/// name = "db"
+/// image = "postgres:16"
+/// ports = ["5432:5432"]
+/// env = { POSTGRES_PASSWORD = "postgres" }
+/// ready = { line = "ready to accept connections" }
+/// ```
+///
+/// Every service becomes a task which the other tasks depend on, so they are only started once
+/// the services are ready.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceConfig {
+    pub name: String,
+    pub image: String,
+
+    /// Published ports as given to `docker run -p`, e.g. `"5432:5432"`.
+    #[serde(default)]
+    pub ports: Vec<String>,
+
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Mounts as given to `docker run -v`, e.g. `"./data:/var/lib/postgresql/data"`.
+    #[serde(default)]
+    pub volumes: Vec<String>,
+
+    /// Passed to the container after the image, replacing its default command.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// When the service is ready, by default once the container is healthy. If its image has no
+    /// healthcheck, once the host port of the first published port accepts connections, which
+    /// docker accepts as soon as it published the port, or else once it is running.
+    #[serde(default)]
+    pub ready: Option<ReadyProbe>,
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("`{value}` of service `{service}` cannot contain whitespace")]
+    Whitespace { service: String, value: String },
+}
+
+impl ServiceConfig {
+    /// The name of the container, prefixed to keep clear of containers started otherwise and
+    /// suffixed with the number of a namespaced session, see [`crate::instance::InstanceMode`].
+    fn container_name(&self) -> String {
+        match std::env::var(INSTANCE_VAR) {
+            Ok(instance) => format!("parallely-{}-{}", self.name, instance),
+            Err(_) => format!("parallely-{}", self.name),
+        }
+    }
+
+    /// The host port of the first published port, if it is fixed.
+    fn host_port(&self) -> Option<u16> {
+        let parts = self.ports.first()?.split(':').collect::<Vec<_>>();
+        parts.get(parts.len().checked_sub(2)?)?.parse().ok()
+    }
+
+    /// A task running the container in the foreground. It is removed once stopped, docker
+    /// forwards the stop signal to the container.
+    pub fn to_task(&self) -> Result<TaskConfig, ServiceError> {
+        let mut args = vec![
+            "docker".to_string(),
+            "run".to_string(),
+            "--rm".to_string(),
+            "--name".to_string(),
+            self.container_name(),
+        ];
+        for port in self.ports.iter() {
+            args.extend(["-p".to_string(), port.clone()]);
+        }
+        for (key, value) in self.env.iter() {
+            args.extend(["-e".to_string(), format!("{}={}", key, value)]);
+        }
+        for volume in self.volumes.iter() {
+            args.extend(["-v".to_string(), volume.clone()]);
+        }
+        args.push(self.image.clone());
+        args.extend(self.args.iter().cloned());
+        // the command is split on whitespace when it is executed
+        if let Some(arg) = args.iter().find(|arg| arg.contains(char::is_whitespace)) {
+            return Err(ServiceError::Whitespace {
+                service: self.name.clone(),
+                value: arg.clone(),
+            });
+        }
+        let mut task = TaskConfig::new(args.join(" "));
+        task.name = Some(self.name.clone());
+        task.ready = Some(self.ready.clone().unwrap_or_else(|| ReadyProbe::Container {
+            name: self.container_name(),
+            port: self.host_port(),
+        }));
+        Ok(task)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::config::TaskConfig;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Which tasks to run or show by their [`TaskConfig::tags`], e.g. `backend,!slow` for the tasks
+/// tagged `backend` which are not tagged `slow`. Without any tag to include, every task which
+/// has none of the excluded tags matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum TagFilterError {
+    #[error("Invalid tags `{0}`, expected e.g. `backend,!slow`")]
+    Empty(String),
+}
+
+impl FromStr for TagFilter {
+    type Err = TagFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = TagFilter::default();
+        for tag in s.split(',').map(str::trim) {
+            match tag.strip_prefix('!').map(str::trim) {
+                Some("") => return Err(TagFilterError::Empty(s.to_string())),
+                Some(tag) => filter.exclude.push(tag.to_string()),
+                None if tag.is_empty() => return Err(TagFilterError::Empty(s.to_string())),
+                None => filter.include.push(tag.to_string()),
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl Display for TagFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let include = self.include.iter().cloned();
+        let exclude = self.exclude.iter().map(|tag| format!("!{}", tag));
+        let tags = include.chain(exclude).collect::<Vec<_>>();
+        write!(f, "{}", tags.join(","))
+    }
+}
+
+impl TagFilter {
+    pub fn matches(&self, task: &TaskConfig) -> bool {
+        let tagged = |tag: &String| task.tags.contains(tag);
+        (self.include.is_empty() || self.include.iter().any(tagged))
+            && !self.exclude.iter().any(tagged)
+    }
+
+    /// The tasks which match along with the tasks they depend on, whatever their tags, so that
+    /// they can start.
+    pub fn apply(&self, tasks: Vec<TaskConfig>) -> Vec<TaskConfig> {
+        let mut needed = tasks
+            .iter()
+            .map(|task| self.matches(task))
+            .collect::<Vec<_>>();
+        let mut pending = (0..tasks.len())
+            .filter(|&index| needed[index])
+            .collect::<Vec<_>>();
+        while let Some(index) = pending.pop() {
+            for dependency in tasks[index].depends_on.iter() {
+                if let Some(position) = tasks
+                    .iter()
+                    .position(|task| task.key() == dependency)
+                    .filter(|&position| !needed[position])
+                {
+                    needed[position] = true;
+                    pending.push(position);
+                }
+            }
+        }
+        tasks
+            .into_iter()
+            .zip(needed)
+            .filter(|(_, needed)| *needed)
+            .map(|(task, _)| task)
+            .collect()
+    }
+}
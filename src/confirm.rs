@@ -0,0 +1,96 @@
+use crate::event::ParallelyEvent;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, BorderType, Clear, Paragraph, Widget};
+
+/// A popup asking whether to go ahead with a destructive action. `T` is the action, which is
+/// handed back once confirmed.
+pub struct Confirm<T> {
+    question: String,
+    action: T,
+}
+
+pub enum ConfirmAction<T> {
+    None,
+    Confirmed(T),
+    Cancelled,
+}
+
+impl<T> Confirm<T>
+where
+    T: Clone,
+{
+    pub fn new(question: impl Into<String>, action: T) -> Self {
+        Self {
+            question: question.into(),
+            action,
+        }
+    }
+
+    /// The question as plain text, for the screen reader frontend.
+    pub fn describe(&self) -> String {
+        format!("{} (y/n)", self.question)
+    }
+
+    /// `y` or `Enter` confirms, `n` or `Esc` cancels. Consumes every other key event without a
+    /// `Control` modifier, so that global shortcuts such as `Ctrl+C` keep working.
+    pub fn handle_event(&mut self, event: &mut ParallelyEvent) -> ConfirmAction<T> {
+        if let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                return ConfirmAction::None;
+            }
+            let action = match code {
+                KeyCode::Char('y' | 'Y') | KeyCode::Enter => {
+                    ConfirmAction::Confirmed(self.action.clone())
+                }
+                KeyCode::Char('n' | 'N') | KeyCode::Esc => ConfirmAction::Cancelled,
+                _ => ConfirmAction::None,
+            };
+            event.stop_propagation();
+            return action;
+        }
+        ConfirmAction::None
+    }
+}
+
+impl<T> Widget for &Confirm<T> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let width = (self.question.chars().count() as u16 + 4).clamp(30, area.width);
+        let [popup_area] = Layout::vertical([Constraint::Length(4)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::bordered()
+            .title(" Confirm ".red().bold())
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().red());
+        let lines = vec![
+            Line::from(self.question.as_str()),
+            Line::from(vec![
+                "<y> ".blue().bold(),
+                "Yes  ".into(),
+                "<n> ".blue().bold(),
+                "No".into(),
+            ])
+            .centered(),
+        ];
+
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines).block(block).render(popup_area, buf);
+    }
+}
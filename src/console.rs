@@ -1,93 +1,1801 @@
+mod stream_pane;
+mod term;
+mod text_cache;
+
+use crate::ansi;
+use crate::config::{
+    ActionEffect, BinaryMode, ClearMode, QuickAction, RestartPolicy, TaskConfig, TaskUi,
+};
+use crate::console::stream_pane::StreamPane;
+use crate::console::text_cache::TextCache;
 use crate::context::Context;
+use crate::counters::{self, Counters};
+use crate::diff::RunDiff;
+use crate::direnv;
 use crate::event::ParallelyEvent;
+use crate::i18n::Phrase;
+use crate::log_forwarder::LogForwarder;
 use crate::message::MessageSender;
-use crate::task_executor::{Executable, TaskExecutor, TaskOutputReceiver};
-use ansi_to_tui::IntoText;
+use crate::perf::ConsoleBuffers;
+use crate::plugin::{PluginEvent, Plugins};
+use crate::probe::{ReadyProbe, PROBE_INTERVAL};
+use crate::project_path;
+use crate::report;
+use crate::resolve::{self, ResolvedCommand};
+use crate::task_events::{self, EventFormat};
+use crate::task_executor::child_ext::ChildSignal;
+use crate::task_executor::{
+    hexdump, Executable, IoStats, OutputRate, Pipe, TaskExecutor, TaskOutput, TaskOutputReceiver,
+    TaskPtyReceiver, TaskStatus,
+};
+use crate::task_log::TaskLog;
+use crate::task_state::{TaskState, Transition};
+use crate::test_summary::TestSummary;
+use crate::watcher::Watcher;
+use color_eyre::eyre::{eyre, WrapErr};
 use crossterm::event::{Event, MouseEventKind};
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Alignment, Constraint, Layout, Margin, Rect};
-use ratatui::style::Stylize;
-use ratatui::text::{Line, Text};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
 use ratatui::widgets::block::Title;
 use ratatui::widgets::{
-    Block, BorderType, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget,
-    Widget,
+    Block, BorderType, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    StatefulWidget, Widget,
 };
 use std::borrow::Cow;
 use std::cmp::min;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::oneshot;
+
+/// How long the border flashes after the task transitions to failed.
+const FAILURE_FLASH_DURATION: Duration = Duration::from_millis(1500);
+const FAILURE_FLASH_INTERVAL_MS: u128 = 250;
+/// How many lines of a run are kept for the diff view without [`TaskConfig::max_lines`].
+const DEFAULT_RUN_LINES: usize = 10_000;
+/// How many rows scrolled off the screen of a task with `ui = "term"` are kept.
+const TERM_SCROLLBACK: usize = 1000;
+/// How long a restarting task may take to stop before it gets killed.
+const RESTART_KILL_TIMEOUT: Duration = Duration::from_secs(5);
+/// How many lines the mouse wheel scrolls without `--scroll-step`.
+pub const DEFAULT_SCROLL_STEP: usize = 3;
+/// The rate limit toggled with `l` for a task without [`TaskConfig::rate_limit`].
+const DEFAULT_RATE_LIMIT: u32 = 10;
+/// How many bytes of a line are shown without [`TaskConfig::max_line_length`].
+const DEFAULT_MAX_LINE_LENGTH: usize = 16 * 1024;
+/// From how many bytes on a line is cut into rows as wide as the console instead of being wrapped
+/// at words, which takes too long for e.g. minified JavaScript.
+const CHUNK_WRAP_LENGTH: usize = 4 * 1024;
+/// How much more room an active or failed console gets than an idle one with `--auto-layout`.
+const ACTIVE_LAYOUT_WEIGHT: f64 = 3.0;
+/// How long a console stays active after its last output, so that a task printing in bursts
+/// does not make the layout jump back and forth.
+const IDLE_AFTER: Duration = Duration::from_secs(5);
+/// How quickly the layout follows a change of activity, the time for about two thirds of it.
+const LAYOUT_SMOOTHING: Duration = Duration::from_millis(600);
+
+/// A way to scroll the output of a console, see [`Console::scroll`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scroll {
+    Up(usize),
+    Down(usize),
+    HalfPageUp,
+    HalfPageDown,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+}
+
+/// Where the output of a console is scrolled to, see [`Console::scroll_position`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScrollPosition {
+    /// The number of the line at the top of the viewport, 0 without any output.
+    pub top_line: usize,
+    /// When the line at the top of the viewport was received.
+    pub top_line_time: Option<SystemTime>,
+    /// Whether the console is scrolled to the bottom and follows new output.
+    pub follows: bool,
+}
 
 pub struct Console {
+    task: TaskConfig,
     executor: TaskExecutor,
     output: Option<TaskOutputReceiver>,
-    output_text: Text<'static>,
+    /// The terminal screen of a task with `ui = "term"`, which replaces the output lines.
+    screen: Option<vt100::Parser>,
+    screen_output: Option<TaskPtyReceiver>,
+    /// The output wrapped to `wrap_width`, which is only parsed into styled text once it is
+    /// shown, see [`TextCache`].
+    output_rows: VecDeque<String>,
+    text_cache: TextCache,
+    /// Every received line before wrapping, so that the output can be wrapped again on resize.
+    output_lines: VecDeque<String>,
+    /// When each of `output_lines` was received.
+    output_line_times: VecDeque<SystemTime>,
+    /// The width `output_rows` is wrapped to, `0` forces wrapping it again on the next render.
+    wrap_width: usize,
+    /// The 1-based number of the received line each row of `output_rows` was wrapped from.
+    output_row_lines: VecDeque<usize>,
+    output_line_count: usize,
+    /// How long [`Console::receive`] took the last time, for the [`crate::perf::PerfOverlay`].
+    last_receive: Duration,
+    /// How many lines were dropped from the start of the output, see [`TaskConfig::max_lines`].
+    dropped_lines: usize,
+    /// How many lines and bytes of output were received over all runs.
+    received_lines: u64,
+    received_bytes: u64,
+    /// Whether the exit of the current run has been handled, see [`Console::run_exit_hook`].
+    exit_handled: bool,
+    line_numbers: bool,
+    /// The numbers of the bookmarked lines.
+    bookmarks: BTreeSet<usize>,
+    /// The raw lines of the current and the previous run, for the diff view.
+    run_lines: VecDeque<String>,
+    previous_run_lines: Option<VecDeque<String>>,
+    diff_view: bool,
+    /// The diff shown by the diff view, computed when it is toggled on and when the task is
+    /// restarted while it is shown, not as the output arrives.
+    diff_cache: Option<RunDiff>,
+    test_summary: Option<TestSummary>,
+    /// The matches of [`TaskConfig::counters`] in the current run.
+    counters: Counters,
+    log: Option<TaskLog>,
+    forwarder: Option<Arc<LogForwarder>>,
+    plugins: Option<Arc<Plugins>>,
     output_rect: Option<Rect>,
+    /// The row showing the command, the full command is shown while the mouse hovers it.
+    title_rect: Option<Rect>,
+    title_hovered: bool,
     output_vertical_scroll: usize,
     output_vertical_scroll_max: Option<usize>,
+    /// How many lines the mouse wheel scrolls, see `--scroll-step`.
+    scroll_step: usize,
     message_sender: MessageSender,
     scroll_bottom: bool,
+    area: Option<Rect>,
+    focused: bool,
+    hidden: bool,
+    failed: bool,
+    flash_until: Option<Instant>,
+    marked: bool,
+    restart_requested: Option<Instant>,
+    /// Not started yet because a dependency is not ready, see [`TaskConfig::depends_on`].
+    waiting: bool,
+    /// When the current run of the task became ready, see [`TaskConfig::ready`].
+    ready_at: Option<SystemTime>,
+    state: TaskState,
+    frozen: bool,
+    /// Print every line of output prefixed by the task, when running without the UI.
+    echo: bool,
+    /// Print the events of the task among the lines it echoes, see [`task_events::print`].
+    events: Option<EventFormat>,
+    /// The lines of output without ANSI escape sequences which have not been taken yet, kept
+    /// for the screen reader frontend.
+    transcript: Option<Vec<String>>,
+    /// The output received while frozen.
+    held_lines: Vec<(Pipe, String, SystemTime)>,
+    /// How many lines of output were not shown since the console was muted, `None` while it is
+    /// not muted, see [`Console::toggle_muted`].
+    muted: Option<usize>,
+    last_output_at: Option<Instant>,
+    /// The share of the screen with `--auto-layout`, see [`Console::layout_weight`].
+    layout_weight: Option<(f64, Instant)>,
+    /// How many cells wider the console is than its share of its row, changed with `Ctrl+←/→`.
+    width_offset: i32,
+    /// Whether stdout and stderr are shown in panes of their own, see [`StreamPane`].
+    split: bool,
+    stdout_pane: StreamPane,
+    stderr_pane: StreamPane,
+    /// Whether lines longer than [`TaskConfig::max_line_length`] are shown in full.
+    long_lines_expanded: bool,
+    /// Whether the keys scroll the stderr pane of a split console instead of the stdout one.
+    scroll_stderr: bool,
+    held_bytes: Vec<u8>,
+    /// The commands the task ran with since it was first restarted by [`Console::restart_with`],
+    /// the most recent last.
+    edited_commands: Vec<String>,
+    /// Whether the task was asked to stop, so that its [`RestartPolicy`] does not apply.
+    stop_requested: bool,
+    /// How many times in a row the task exited soon after starting, see [`crate::config::StartLimit`].
+    quick_exits: u32,
+    /// Whether the task is not restarted by its [`RestartPolicy`] anymore until it is
+    /// restarted by hand.
+    crash_looping: bool,
+    /// Watches the files of [`TaskConfig::watch`], until the console is dropped.
+    watcher: Option<Watcher>,
+    /// The executable the task was last started with, see [`resolve::resolve`].
+    resolved: Option<ResolvedCommand>,
+    /// The status the task had when it was last polled, which is drawn.
+    status: Option<TaskStatus>,
+    /// The environment being loaded in the background before the task starts, see
+    /// [`Console::execute`].
+    env_loading: Option<oneshot::Receiver<Result<BTreeMap<String, String>, direnv::DirenvError>>>,
+    /// Told once the probe of the current run has passed, see [`Console::start_probing`].
+    probing: Option<oneshot::Receiver<()>>,
 }
 
 impl Console {
-    pub fn new(command: String, message_sender: MessageSender) -> Self {
-        let executor = TaskExecutor::new(command, message_sender.clone());
+    pub fn new(task: TaskConfig, message_sender: MessageSender) -> Self {
+        let executor = Self::new_executor(&task, &message_sender);
+        executor.set_output_rate(Self::configured_rate(&task));
+        let split = task.split_output;
+        let counters = Counters::new(&task.counters);
         Self {
+            task,
             executor,
             output: None,
+            screen: None,
+            screen_output: None,
             output_rect: None,
-            output_text: Text::default(),
+            title_rect: None,
+            title_hovered: false,
+            output_rows: VecDeque::new(),
+            text_cache: TextCache::default(),
+            output_lines: VecDeque::new(),
+            output_line_times: VecDeque::new(),
+            wrap_width: 0,
+            output_row_lines: VecDeque::new(),
+            output_line_count: 0,
+            last_receive: Duration::ZERO,
+            dropped_lines: 0,
+            received_lines: 0,
+            received_bytes: 0,
+            exit_handled: false,
+            line_numbers: false,
+            bookmarks: BTreeSet::new(),
+            run_lines: VecDeque::new(),
+            previous_run_lines: None,
+            diff_view: false,
+            diff_cache: None,
+            test_summary: None,
+            counters,
+            log: None,
+            forwarder: None,
+            plugins: None,
             output_vertical_scroll: 0,
             output_vertical_scroll_max: None,
+            scroll_step: DEFAULT_SCROLL_STEP,
             message_sender,
             scroll_bottom: true,
+            area: None,
+            focused: false,
+            hidden: false,
+            failed: false,
+            flash_until: None,
+            marked: false,
+            restart_requested: None,
+            waiting: false,
+            ready_at: None,
+            state: TaskState::Pending,
+            frozen: false,
+            echo: false,
+            events: None,
+            transcript: None,
+            held_lines: Vec::new(),
+            muted: None,
+            last_output_at: None,
+            layout_weight: None,
+            width_offset: 0,
+            split,
+            stdout_pane: StreamPane::new("stdout"),
+            stderr_pane: StreamPane::new("stderr"),
+            scroll_stderr: false,
+            long_lines_expanded: false,
+            held_bytes: Vec::new(),
+            edited_commands: Vec::new(),
+            stop_requested: false,
+            quick_exits: 0,
+            crash_looping: false,
+            watcher: None,
+            resolved: None,
+            status: None,
+            env_loading: None,
+            probing: None,
+        }
+    }
+
+    fn new_executor(task: &TaskConfig, message_sender: &MessageSender) -> TaskExecutor {
+        let mut executor =
+            TaskExecutor::new(task.command.to_string(), task.shell, message_sender.clone());
+        if task.takes_input() {
+            executor.pipe_stdin();
+        }
+        executor.set_encoding(task.encoding);
+        executor.set_success_exit_codes(task.success_exit_codes.clone());
+        if !task.detached {
+            executor.kill_with_parent();
+        }
+        if let Some(replica) = task.replica.as_ref() {
+            executor
+                .command
+                .env("PARALLELY_REPLICA", replica.index.to_string())
+                .env("PARALLELY_REPLICAS", replica.count.to_string());
+        }
+        if let Some(cwd) = task.cwd.as_ref() {
+            executor.command.current_dir(cwd);
+        }
+        executor.command.envs(project_path::env(task));
+        executor
+    }
+
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        self.area
+            .map(|area| area.contains((column, row).into()))
+            .unwrap_or(false)
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Hides the console from the layout. The task keeps running and its output keeps buffering.
+    pub fn hide(&mut self) {
+        self.hidden = true;
+        self.area = None;
+        self.output_rect = None;
+        self.title_rect = None;
+    }
+
+    pub fn show(&mut self) {
+        self.hidden = false;
+    }
+
+    /// The workspace of the task, see [`TaskConfig::workspace`], empty if it has none.
+    pub fn workspace(&self) -> &str {
+        self.task.workspace.as_deref().unwrap_or_default()
+    }
+
+    /// Whether the console is laid out: it is not hidden and on the workspace shown.
+    pub fn is_shown(&self, workspace: &str) -> bool {
+        !self.hidden && self.workspace() == workspace
+    }
+
+    /// Executes the task, or waits for its dependencies to become ready first.
+    pub fn start(&mut self) -> color_eyre::Result<()> {
+        if self.task.depends_on.is_empty() {
+            self.execute()
+        } else {
+            self.waiting = true;
+            Ok(())
         }
     }
 
+    /// Executes the task, after loading its environment in the background if it has
+    /// [`TaskConfig::load_env`], since direnv can take seconds, see [`Console::poll_env`].
     pub fn execute(&mut self) -> color_eyre::Result<()> {
+        self.waiting = false;
+        self.transition(TaskState::Starting);
+        if self.task.load_env.is_empty() {
+            return self.spawn_run();
+        }
+        let dir = self.task.cwd.clone().unwrap_or_else(|| ".".into());
+        let allow = self.task.load_env.clone();
+        let (sender, receiver) = oneshot::channel();
+        let message_sender = self.message_sender.clone();
+        self.message_sender.supervisor().spawn(async move {
+            let load = tokio::task::spawn_blocking(move || direnv::load(&dir, &allow));
+            if let Ok(env) = load.await {
+                let _ = sender.send(env);
+            }
+            message_sender.need_update();
+        });
+        self.env_loading = Some(receiver);
+        Ok(())
+    }
+
+    /// Starts the task once its environment has been loaded, which is reloaded on every start,
+    /// so that changes to it apply on restart. The task starts with the environment it had
+    /// before if loading it failed.
+    pub fn poll_env(&mut self) {
+        let Some(receiver) = self.env_loading.as_mut() else {
+            return;
+        };
+        match receiver.try_recv() {
+            Err(oneshot::error::TryRecvError::Empty) => return,
+            Ok(Ok(env)) => self.executor.set_loaded_env(env),
+            Ok(Err(e)) => self.annotate(&format!("failed to load the environment: {}", e)),
+            Err(oneshot::error::TryRecvError::Closed) => {
+                self.annotate("failed to load the environment")
+            }
+        }
+        self.env_loading = None;
+        if let Err(e) = self.spawn_run() {
+            self.message_sender.send_error(e);
+        }
+    }
+
+    /// Spawns the process of a run of the task, which is [`TaskState::Starting`].
+    fn spawn_run(&mut self) -> color_eyre::Result<()> {
+        self.ready_at = None;
+        self.exit_handled = false;
+        self.stop_requested = false;
+        let resolved = resolve::resolve(self.executor.command.as_std());
+        let result = self
+            .spawn()
+            .wrap_err_with(|| format!("Failed to start `{}`: {}", self.task.key(), resolved));
+        self.resolved = Some(resolved);
+        match result {
+            Ok(()) => {
+                self.transition(TaskState::Running);
+                self.start_probing();
+            }
+            Err(_) => self.transition(TaskState::Failed),
+        }
+        result
+    }
+
+    /// Checks the probe of the task in the background every [`PROBE_INTERVAL`] until it passes,
+    /// then wakes the main loop, which marks the task as ready in [`Console::poll_ready`]. The
+    /// checks stop once the run they are for has ended, the receiver is dropped then.
+    fn start_probing(&mut self) {
+        self.probing = None;
+        let Some(probe) = self.task.ready.clone().filter(ReadyProbe::is_polled) else {
+            return;
+        };
+        let (mut sender, receiver) = oneshot::channel();
+        let message_sender = self.message_sender.clone();
+        self.message_sender.supervisor().spawn(async move {
+            loop {
+                tokio::select! {
+                    passed = probe.passes() => if passed {
+                        break;
+                    },
+                    _ = sender.closed() => return,
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(PROBE_INTERVAL) => {}
+                    _ = sender.closed() => return,
+                }
+            }
+            if sender.send(()).is_ok() {
+                message_sender.need_update();
+            }
+        });
+        self.probing = Some(receiver);
+    }
+
+    fn spawn(&mut self) -> color_eyre::Result<()> {
+        #[cfg(unix)]
+        if self.task.ui == TaskUi::Term {
+            let (rows, cols) = self
+                .output_rect
+                .map(|rect| {
+                    (
+                        rect.height.saturating_sub(2).max(1),
+                        rect.width.saturating_sub(2).max(1),
+                    )
+                })
+                .unwrap_or((24, 80));
+            self.screen_output = Some(self.executor.execute_pty(rows, cols)?);
+            self.screen = Some(vt100::Parser::new(rows, cols, TERM_SCROLLBACK));
+            return Ok(());
+        }
         let output_receiver = self.executor.execute()?;
         self.output = Some(output_receiver);
         Ok(())
     }
 
     pub fn handle_event(&mut self, event: &mut ParallelyEvent) {
-        if let (Event::Mouse(mouse_event), Some(output_rect), Some(output_vertical_scroll_max)) = (
-            event.as_ref(),
-            self.output_rect,
-            self.output_vertical_scroll_max,
-        ) {
+        if let Event::Mouse(mouse_event) = event.as_ref() {
+            self.title_hovered = self
+                .title_rect
+                .is_some_and(|rect| rect.contains((mouse_event.column, mouse_event.row).into()));
+        }
+        if let (Event::Mouse(mouse_event), true) = (event.as_ref(), self.is_split()) {
+            let (column, row) = (mouse_event.column, mouse_event.row);
+            let pane = [&mut self.stdout_pane, &mut self.stderr_pane]
+                .into_iter()
+                .find(|pane| pane.contains(column, row));
+            match (pane, mouse_event.kind) {
+                (Some(pane), MouseEventKind::ScrollUp) => {
+                    event.stop_propagation();
+                    pane.scroll(self.scroll_step, 0);
+                }
+                (Some(pane), MouseEventKind::ScrollDown) => {
+                    event.stop_propagation();
+                    pane.scroll(0, self.scroll_step);
+                }
+                _ => {}
+            }
+            return;
+        }
+        if let (Event::Mouse(mouse_event), Some(output_rect)) = (event.as_ref(), self.output_rect) {
             if output_rect.contains((mouse_event.column, mouse_event.row).into()) {
-                self.output_vertical_scroll = match mouse_event.kind {
+                match mouse_event.kind {
                     MouseEventKind::ScrollUp => {
                         event.stop_propagation();
-                        self.scroll_bottom = false;
-                        self.output_vertical_scroll.saturating_sub(1)
+                        self.scroll(Scroll::Up(self.scroll_step));
                     }
                     MouseEventKind::ScrollDown => {
                         event.stop_propagation();
-                        let offset = min(
-                            self.output_vertical_scroll.saturating_add(1),
-                            output_vertical_scroll_max,
-                        );
-                        self.scroll_bottom = offset == output_vertical_scroll_max;
-                        offset
+                        self.scroll(Scroll::Down(self.scroll_step));
                     }
-                    _ => self.output_vertical_scroll,
+                    _ => {}
                 }
             }
         }
     }
 
+    /// How many lines the mouse wheel scrolls at a time.
+    pub fn set_scroll_step(&mut self, step: usize) {
+        self.scroll_step = step.max(1);
+    }
+
+    /// Scrolls the output, the stdout (or stderr, see [`Console::switch_scrolled_pane`]) pane of
+    /// a split console, or the scrollback of the screen of a task with `ui = "term"`.
+    pub fn scroll(&mut self, scroll: Scroll) {
+        let page = match self.is_split() {
+            true => self.scrolled_pane().page(),
+            false => self
+                .output_rect
+                .map_or(1, |rect| rect.height.saturating_sub(2).max(1) as usize),
+        };
+        let (up, down) = match scroll {
+            Scroll::Up(rows) => (rows, 0),
+            Scroll::Down(rows) => (0, rows),
+            Scroll::HalfPageUp => ((page / 2).max(1), 0),
+            Scroll::HalfPageDown => (0, (page / 2).max(1)),
+            Scroll::PageUp => (page, 0),
+            Scroll::PageDown => (0, page),
+            Scroll::Top => (usize::MAX, 0),
+            Scroll::Bottom => (0, usize::MAX),
+        };
+        if self.is_split() {
+            self.scrolled_pane().scroll(up, down);
+            return;
+        }
+        if let Some(parser) = self.screen.as_mut() {
+            // the scrollback is clamped to the rows actually kept
+            let scrollback = parser.screen().scrollback();
+            let scrollback = scrollback.saturating_add(up).min(TERM_SCROLLBACK);
+            parser
+                .screen_mut()
+                .set_scrollback(scrollback.saturating_sub(down));
+            return;
+        }
+        let scroll_max = self.output_vertical_scroll_max.unwrap_or(0);
+        self.output_vertical_scroll = min(
+            self.output_vertical_scroll
+                .saturating_sub(up)
+                .saturating_add(down),
+            scroll_max,
+        );
+        self.scroll_bottom = self.output_vertical_scroll == scroll_max;
+    }
+
+    /// Takes in the output received so far and handles the exit of the task, for every console
+    /// whether it is drawn or not. The output is wrapped to the width it was last drawn with.
+    pub fn poll(&mut self) -> Option<TaskStatus> {
+        self.poll_env();
+        if let Err(e) = self.receive(self.wrap_width) {
+            self.message_sender.send_error(e);
+        }
+        self.update_status()
+    }
+
+    /// Wraps the output to the width it is drawn with, if that changed.
+    pub fn fit_width(&mut self, width_limit: usize) {
+        if width_limit != self.wrap_width {
+            self.rewrap(width_limit);
+        }
+    }
+
     pub fn receive(&mut self, width_limit: usize) -> color_eyre::Result<()> {
-        if let Some(output) = self.output.as_mut() {
-            while let Ok(line) = output.try_recv() {
-                let wrapped_lines = Self::wrap_text(&line, width_limit);
-                Self::append_text(&mut self.output_text, wrapped_lines);
+        let started = Instant::now();
+        let result = self.receive_pending(width_limit);
+        self.last_receive = started.elapsed();
+        result
+    }
+
+    fn receive_pending(&mut self, width_limit: usize) -> color_eyre::Result<()> {
+        self.fit_width(width_limit);
+        while let Some(bytes) = self
+            .screen_output
+            .as_mut()
+            .and_then(|output| output.try_recv().ok())
+        {
+            self.receive_bytes(bytes);
+        }
+        while let Some(output) = self
+            .output
+            .as_mut()
+            .and_then(|output| output.try_recv().ok())
+        {
+            self.receive_output(output.0, output.1);
+        }
+        if let Some(log) = self.log.as_mut() {
+            log.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Waits until the rest of the output of the exited task has been read, at most until
+    /// `deadline`, so that output written right before exiting, e.g. the error of a task which
+    /// failed at once, still makes it into the summary.
+    pub async fn drain(&mut self, deadline: tokio::time::Instant) -> color_eyre::Result<()> {
+        if let Some(mut output) = self.screen_output.take() {
+            while let Ok(Some(bytes)) = tokio::time::timeout_at(deadline, output.recv()).await {
+                self.receive_bytes(bytes);
+            }
+            self.screen_output = Some(output);
+        }
+        if let Some(mut output) = self.output.take() {
+            while let Ok(Some((pipe, task_output))) =
+                tokio::time::timeout_at(deadline, output.recv()).await
+            {
+                self.receive_output(pipe, task_output);
             }
+            self.output = Some(output);
+        }
+        if let Some(log) = self.log.as_mut() {
+            log.flush()?;
         }
         Ok(())
     }
 
+    fn receive_bytes(&mut self, bytes: Vec<u8>) {
+        self.last_output_at = Some(Instant::now());
+        self.received_bytes += bytes.len() as u64;
+        self.received_lines += bytes.iter().filter(|&&byte| byte == b'\n').count() as u64;
+        if self.frozen {
+            self.held_bytes.extend(bytes);
+        } else if let Some(parser) = self.screen.as_mut() {
+            parser.process(&bytes);
+        }
+    }
+
+    fn receive_output(&mut self, pipe: Pipe, output: TaskOutput) {
+        self.last_output_at = Some(Instant::now());
+        let lines = match output {
+            TaskOutput::Line(line) => {
+                // the line break was stripped
+                self.received_bytes += line.len() as u64 + 1;
+                self.received_lines += 1;
+                vec![line]
+            }
+            TaskOutput::Binary { bytes, preview } => {
+                self.received_bytes += bytes as u64;
+                self.binary_notice(bytes, &preview)
+            }
+            TaskOutput::Notice(text) => vec![annotation(&text)],
+        };
+        for line in lines {
+            self.capture(&line);
+            if let Some(muted) = self.muted.as_mut() {
+                *muted += 1;
+            } else if self.frozen {
+                self.held_lines.push((pipe, line, SystemTime::now()));
+            } else {
+                self.show_line(pipe, line, SystemTime::now());
+            }
+        }
+    }
+
+    /// The lines shown in place of output which is not text.
+    fn binary_notice(&self, bytes: usize, preview: &[u8]) -> Vec<String> {
+        let mut lines = vec![annotation(&format!(
+            "binary output suppressed ({} bytes)",
+            bytes
+        ))];
+        if self.task.on_binary == BinaryMode::Hexdump {
+            lines.extend(
+                hexdump(preview)
+                    .into_iter()
+                    .map(|line| format!("\x1b[2m{}\x1b[0m", line)),
+            );
+        }
+        lines
+    }
+
+    /// The part of the line after the last clear screen sequence, if it clears the screen and
+    /// the task does not ignore that.
+    fn after_clear<'a>(&self, line: &'a str) -> Option<&'a str> {
+        match self.task.on_clear {
+            ClearMode::Ignore => None,
+            _ => ansi::after_clear(line),
+        }
+    }
+
+    /// Feeds a line into everything besides the output itself, which keeps going while the
+    /// console is frozen.
+    fn capture(&mut self, line: &str) {
+        let cleared = self.after_clear(line);
+        let line = cleared.unwrap_or(line);
+        if cleared.is_some() && ansi::strip(line).trim().is_empty() {
+            return;
+        }
+        let mut became_ready = false;
+        if let Some(ReadyProbe::Line(text)) = self.task.ready.as_ref() {
+            if self.ready_at.is_none() && !self.is_restarting() && ansi::strip(line).contains(text)
+            {
+                self.ready_at = Some(SystemTime::now());
+                became_ready = true;
+            }
+        }
+        TestSummary::observe(&mut self.test_summary, line);
+        self.counters.observe(line);
+        if self.echo {
+            println!("[{}] {}", self.task.identifier(), ansi::strip(line));
+        }
+        // after the line is echoed, so that the ready event follows it
+        if became_ready && self.state == TaskState::Running {
+            self.transition(TaskState::Ready);
+        }
+        if let Some(transcript) = self.transcript.as_mut() {
+            transcript.push(ansi::strip(line));
+        }
+        if let Some(Err(e)) = self.log.as_mut().map(|log| log.write_line(line)) {
+            self.message_sender.send_error(e);
+        }
+        if let Some(forwarder) = self.forwarder.as_ref() {
+            let line = ansi::strip(line);
+            if let Err(e) = forwarder.send(self.task.identifier(), self.executor.pid(), &line) {
+                self.message_sender.send_error(e);
+            }
+        }
+        if let Some(plugins) = self.plugins.as_ref() {
+            plugins.send(PluginEvent::Line {
+                task: self.task.key(),
+                line: &ansi::strip(line),
+            });
+        }
+    }
+
+    /// Appends a line to the output, handling a clear screen sequence in it first.
+    fn show_line(&mut self, pipe: Pipe, mut line: String, received_at: SystemTime) {
+        if let Some(rest) = self.after_clear(&line).map(str::to_string) {
+            self.clear_output();
+            if ansi::strip(&rest).trim().is_empty() {
+                return;
+            }
+            line = rest;
+        }
+        self.push_output_line(line.clone(), received_at);
+        match pipe {
+            Pipe::Stdout => self.stdout_pane.push(line.clone()),
+            Pipe::Stderr => self.stderr_pane.push(line.clone()),
+        }
+        self.run_lines.push_back(line);
+        let max_lines = self.task.max_lines.unwrap_or(DEFAULT_RUN_LINES);
+        while self.run_lines.len() > max_lines {
+            self.run_lines.pop_front();
+        }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted.is_some()
+    }
+
+    /// Stops showing the lines of output while muted, e.g. of a task flooding its console, which
+    /// are still logged, forwarded and checked for readiness but only counted. The screen of a
+    /// task with `ui = "term"` keeps being updated.
+    pub fn toggle_muted(&mut self) {
+        match self.muted.take() {
+            Some(0) => {}
+            Some(muted) => self.annotate(&format!("{} lines suppressed while muted", muted)),
+            None => self.muted = Some(0),
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Stops appending to the output while frozen, the output received in the meantime is held
+    /// back and appended after a separator once unfrozen.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        if self.frozen == frozen {
+            return;
+        }
+        self.frozen = frozen;
+        if frozen {
+            return;
+        }
+        if let Some(parser) = self.screen.as_mut() {
+            parser.process(&std::mem::take(&mut self.held_bytes));
+        }
+        let held_lines = std::mem::take(&mut self.held_lines);
+        if !held_lines.is_empty() {
+            self.annotate(&format!("{} lines received while frozen", held_lines.len()));
+        }
+        for (pipe, line, received_at) in held_lines {
+            self.show_line(pipe, line, received_at);
+        }
+    }
+
+    /// Handles the task clearing the screen according to its [`ClearMode`], the line clearing it
+    /// is only kept from the clear sequence on.
+    fn clear_output(&mut self) {
+        match self.task.on_clear {
+            ClearMode::Separator => {
+                self.annotate("cleared");
+            }
+            ClearMode::Reset => self.reset_output(),
+            ClearMode::Ignore => {}
+        }
+    }
+
+    /// Discards the output so far, or the screen and scrollback of a task with `ui = "term"`, and
+    /// frees the memory it took up. The task keeps running and its next output starts at the top.
+    pub fn clear(&mut self) {
+        if let Some(parser) = self.screen.as_mut() {
+            let (rows, cols) = parser.screen().size();
+            *parser = vt100::Parser::new(rows, cols, TERM_SCROLLBACK);
+            return;
+        }
+        self.reset_output();
+        self.output_rows.shrink_to_fit();
+        self.text_cache.clear();
+        self.output_lines.shrink_to_fit();
+        self.output_line_times.shrink_to_fit();
+        self.output_row_lines.shrink_to_fit();
+        self.held_lines = Vec::new();
+        // the lines of the run are freed as well, the diff view starts over from here
+        self.run_lines = VecDeque::new();
+        self.update_diff();
+    }
+
+    fn reset_output(&mut self) {
+        self.output_rows.clear();
+        self.output_lines.clear();
+        self.output_line_times.clear();
+        self.output_row_lines.clear();
+        self.output_line_count = 0;
+        self.output_vertical_scroll = 0;
+        self.scroll_bottom = true;
+        self.bookmarks.clear();
+        self.stdout_pane.clear();
+        self.stderr_pane.clear();
+    }
+
+    /// Wraps and appends a line to the output.
+    fn push_output_line(&mut self, line: String, received_at: SystemTime) {
+        // before the console is first drawn, the lines are wrapped once its width is known
+        if self.wrap_width > 0 {
+            let wrapped_lines = Self::wrap_text(&self.shorten(&line), self.wrap_width);
+            self.output_rows.extend(wrapped_lines);
+        }
+        self.output_line_count += 1;
+        self.output_row_lines
+            .resize(self.output_rows.len(), self.output_line_count);
+        self.output_lines.push_back(line);
+        self.output_line_times.push_back(received_at);
+        if let Some(max_lines) = self.task.max_lines {
+            while self.output_lines.len() > max_lines.max(1) {
+                self.drop_first_line();
+            }
+        }
+    }
+
+    /// Drops the oldest line of the output, the numbers of the remaining lines stay the same.
+    fn drop_first_line(&mut self) {
+        let first_line = self.first_line();
+        self.output_lines.pop_front();
+        self.output_line_times.pop_front();
+        let rows = self.output_row_lines.partition_point(|&l| l <= first_line);
+        self.output_row_lines.drain(..rows);
+        self.output_rows.drain(..rows);
+        self.output_vertical_scroll = self.output_vertical_scroll.saturating_sub(rows);
+        self.bookmarks.remove(&first_line);
+        self.dropped_lines += 1;
+    }
+
+    pub fn dropped_lines(&self) -> usize {
+        self.dropped_lines
+    }
+
+    /// How many lines and bytes of output were received over all runs of the task.
+    /// The sizes of the output buffers, for the [`crate::perf::PerfOverlay`].
+    pub fn buffers(&self) -> ConsoleBuffers {
+        ConsoleBuffers {
+            label: self.task.key().to_string(),
+            lines: self.output_lines.len(),
+            bytes: self.output_lines.iter().map(String::len).sum(),
+            rows: self.output_rows.len(),
+            cached_rows: self.text_cache.len(),
+            held_lines: self.held_lines.len(),
+            last_receive: self.last_receive,
+        }
+    }
+
+    pub fn received(&self) -> (u64, u64) {
+        (self.received_lines, self.received_bytes)
+    }
+
+    /// Wraps the whole output to a new width, keeping the line at the top of the viewport.
+    fn rewrap(&mut self, width_limit: usize) {
+        let top_line = self
+            .output_row_lines
+            .get(self.output_vertical_scroll)
+            .copied();
+        self.wrap_width = width_limit;
+        self.output_rows.clear();
+        self.output_row_lines.clear();
+        self.output_line_count -= self.output_lines.len();
+        let lines = std::mem::take(&mut self.output_lines);
+        let times = std::mem::take(&mut self.output_line_times);
+        for (line, received_at) in lines.into_iter().zip(times) {
+            self.push_output_line(line, received_at);
+        }
+        if let Some(top_line) = top_line {
+            self.output_vertical_scroll = self.output_row_lines.partition_point(|&l| l < top_line);
+        }
+    }
+
+    /// Forces the output to be wrapped again and the scroll position to be clamped on the next
+    /// render, e.g. after the terminal has been resized.
+    pub fn invalidate_layout(&mut self) {
+        self.wrap_width = 0;
+        self.area = None;
+        self.output_rect = None;
+        self.title_rect = None;
+    }
+
+    pub fn set_log(&mut self, log: TaskLog) {
+        self.log = Some(log);
+    }
+
+    pub fn set_forwarder(&mut self, forwarder: Arc<LogForwarder>) {
+        self.forwarder = Some(forwarder);
+    }
+
+    pub fn set_plugins(&mut self, plugins: Arc<Plugins>) {
+        self.executor.set_plugins(plugins.clone());
+        self.plugins = Some(plugins);
+    }
+
+    /// Starts watching the files of [`TaskConfig::watch`], if any.
+    pub fn watch(&mut self) {
+        if self.task.watch.is_empty() {
+            return;
+        }
+        let dir = self.task.cwd.as_deref().unwrap_or(Path::new("."));
+        let paths = self.task.watch.iter().map(|path| dir.join(path)).collect();
+        self.watcher = Some(Watcher::start(
+            self.task.key().to_string(),
+            paths,
+            &self.message_sender,
+        ));
+    }
+
+    /// A changed file relative to the working directory of the task, for annotations.
+    pub fn watched_path<'a>(&self, path: &'a Path) -> &'a Path {
+        let dir = self.task.cwd.as_deref().unwrap_or(Path::new("."));
+        path.strip_prefix(dir).unwrap_or(path)
+    }
+
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    pub fn set_events(&mut self, events: Option<EventFormat>) {
+        self.events = events;
+    }
+
+    /// Keeps the lines of output to be taken with [`Console::take_transcript`].
+    pub fn keep_transcript(&mut self) {
+        self.transcript.get_or_insert_with(Vec::new);
+    }
+
+    /// The lines of output received since the last call, see [`Console::keep_transcript`].
+    pub fn take_transcript(&mut self) -> Vec<String> {
+        self.transcript
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    pub fn is_marked(&self) -> bool {
+        self.marked
+    }
+
+    pub fn set_marked(&mut self, marked: bool) {
+        self.marked = marked;
+    }
+
+    pub fn task(&self) -> &TaskConfig {
+        &self.task
+    }
+
+    /// Replaces the task config. A changed command only takes effect on the next restart, which
+    /// returns whether the command has changed.
+    pub fn update_task(&mut self, task: TaskConfig) -> bool {
+        self.task = task;
+        self.command_changed()
+    }
+
+    pub fn command_changed(&self) -> bool {
+        self.executor.raw_command() != self.task.command.as_str()
+    }
+
+    pub fn test_summary(&self) -> Option<TestSummary> {
+        self.test_summary
+    }
+
+    pub fn started_at(&self) -> Option<SystemTime> {
+        self.executor.started_at()
+    }
+
+    pub fn exited_at(&self) -> Option<SystemTime> {
+        self.executor.exited_at()
+    }
+
+    pub fn peak_rss(&self) -> Option<u64> {
+        self.executor.peak_rss()
+    }
+
+    pub fn io_stats(&self) -> Option<IoStats> {
+        self.executor.io_stats()
+    }
+
+    /// The last `count` lines of output without ANSI escape sequences, or the last non-empty rows
+    /// of the screen of a task with `ui = "term"`.
+    pub fn last_lines(&self, count: usize) -> Vec<String> {
+        let lines = match self.screen.as_ref() {
+            Some(parser) => parser
+                .screen()
+                .contents()
+                .trim_end()
+                .lines()
+                .map(str::to_string)
+                .collect::<Vec<_>>(),
+            None => self
+                .output_lines
+                .iter()
+                .chain(self.held_lines.iter().map(|(_, line, _)| line))
+                .skip((self.output_lines.len() + self.held_lines.len()).saturating_sub(count))
+                .map(|line| ansi::strip(line))
+                .collect(),
+        };
+        lines[lines.len().saturating_sub(count)..].to_vec()
+    }
+
+    /// Whether the task is between two runs: stopping to be started again, or loading its
+    /// environment before it starts.
+    pub fn is_restarting(&self) -> bool {
+        self.restart_requested.is_some() || self.env_loading.is_some()
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Whether the current run was asked to stop, by the user or a plugin.
+    pub fn is_stop_requested(&self) -> bool {
+        self.stop_requested
+    }
+
+    /// Updates the readiness of every task and starts the waiting tasks whose dependencies have
+    /// all become ready. A waiting task is given up on when a dependency has stopped without
+    /// becoming ready.
+    pub fn start_waiting(consoles: &mut [Console]) {
+        let mut stopped = vec![false; consoles.len()];
+        for (console, stopped) in consoles.iter_mut().zip(stopped.iter_mut()) {
+            if let Ok(status) = console.try_wait() {
+                console.poll_ready(&status);
+                console.observe_exit(&status);
+                *stopped = !matches!(status, TaskStatus::Executing { .. })
+                    && !console.is_restarting()
+                    && !console.is_waiting();
+            }
+        }
+        for index in 0..consoles.len() {
+            if !consoles[index].is_waiting() {
+                continue;
+            }
+            let mut ready = true;
+            let mut abandoned = None;
+            for dependency in consoles[index].task().depends_on.iter() {
+                let Some(position) = consoles
+                    .iter()
+                    .position(|console| console.task().key() == dependency)
+                else {
+                    abandoned = Some(format!("`{}` does not exist anymore", dependency));
+                    break;
+                };
+                if !consoles[position].is_ready() {
+                    ready = false;
+                    if stopped[position] {
+                        abandoned = Some(format!("`{}` stopped before becoming ready", dependency));
+                        break;
+                    }
+                }
+            }
+            let console = &mut consoles[index];
+            if let Some(reason) = abandoned {
+                console.abandon_waiting(&reason);
+            } else if ready {
+                if let Err(e) = console.execute() {
+                    console.message_sender.send_error(e);
+                }
+            }
+        }
+    }
+
+    /// Gives up waiting for the dependencies and tells why in the output.
+    pub fn abandon_waiting(&mut self, reason: &str) {
+        self.waiting = false;
+        self.transition(TaskState::Failed);
+        self.annotate(&format!("not started, {}", reason));
+    }
+
+    pub fn ready_at(&self) -> Option<SystemTime> {
+        self.ready_at
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready_at.is_some()
+    }
+
+    /// Marks the task as ready once its probe has passed, see [`Console::start_probing`], or
+    /// once it has exited successfully if it has no probe. A failed task never becomes ready.
+    pub fn poll_ready(&mut self, status: &TaskStatus) {
+        if self.ready_at.is_some() || self.is_restarting() {
+            return;
+        }
+        let ready = match (&self.task.ready, status) {
+            (_, TaskStatus::Exited { success: false, .. }) => {
+                self.probing = None;
+                false
+            }
+            (Some(_), TaskStatus::Executing { .. } | TaskStatus::Exited { .. }) => self
+                .probing
+                .as_mut()
+                .is_some_and(|probing| probing.try_recv().is_ok()),
+            (None, TaskStatus::Exited { .. }) => true,
+            _ => false,
+        };
+        if ready {
+            self.ready_at = Some(SystemTime::now());
+            // a task without a probe is ready once it has exited, which its state shows already
+            if self.state == TaskState::Running && matches!(status, TaskStatus::Executing { .. }) {
+                self.transition(TaskState::Ready);
+            }
+        }
+    }
+
+    pub fn state(&self) -> TaskState {
+        self.state
+    }
+
+    /// Changes the state of the task and announces it, an invalid change is logged and ignored.
+    fn transition(&mut self, to: TaskState) {
+        match self.state.transition(to) {
+            Ok(state) => {
+                let from = std::mem::replace(&mut self.state, state);
+                let transition = Transition {
+                    task: self.task.key().to_string(),
+                    from,
+                    to: state,
+                };
+                // right away, in order with the lines of output
+                if let Some(plugins) = self.plugins.as_ref() {
+                    plugins.send(PluginEvent::from(&transition));
+                }
+                if let Some(format) = self.events {
+                    task_events::print(format, &transition, self);
+                }
+                self.message_sender.send_transition(transition);
+            }
+            Err(e) => tracing::warn!("[Console] `{}`: {}", self.task.key(), e),
+        }
+    }
+
+    /// Moves the task into its final state once its process has been seen exiting.
+    pub fn observe_exit(&mut self, status: &TaskStatus) {
+        // the status is still the one of the last run
+        if !self.state.is_active() || self.env_loading.is_some() {
+            return;
+        }
+        let state = match status {
+            TaskStatus::Killed { .. } => TaskState::Killed,
+            TaskStatus::Exited { success: true, .. } => TaskState::Exited,
+            // terminated by a signal
+            TaskStatus::Exited { status, .. } if status.code().is_none() => TaskState::Killed,
+            TaskStatus::Exited { .. } => TaskState::Failed,
+            _ => return,
+        };
+        self.transition(state);
+    }
+
+    /// Gives up on the task becoming ready, e.g. `parallely up --timeout` has passed.
+    pub fn time_out(&mut self) {
+        if matches!(self.state, TaskState::Pending | TaskState::Running) {
+            self.waiting = false;
+            self.transition(TaskState::TimedOut);
+        }
+    }
+
+    /// Appends a dimmed line to the output, which is not part of the output of the task.
+    pub fn annotate(&mut self, text: &str) {
+        self.stdout_pane.push(annotation(text));
+        self.stderr_pane.push(annotation(text));
+        self.push_output_line(annotation(text), SystemTime::now());
+    }
+
+    /// Sends a signal received by parallely to every running task which accepts forwarded
+    /// signals. Returns how many tasks it was sent to.
+    pub fn forward_signal(consoles: &mut [Console], signal: ChildSignal) -> usize {
+        let mut count = 0;
+        for console in consoles.iter_mut() {
+            let running = matches!(console.try_wait(), Ok(TaskStatus::Executing { .. }));
+            if running && console.task.forward_signals {
+                console.signal_child(signal);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    pub fn signal_child(&mut self, signal: ChildSignal) {
+        if let Err(e) = self.executor.send_signal(signal) {
+            self.message_sender.send_error(e);
+        }
+    }
+
+    pub fn width_offset(&self) -> i32 {
+        self.width_offset
+    }
+
+    pub fn set_width_offset(&mut self, width_offset: i32) {
+        self.width_offset = width_offset;
+    }
+
+    /// How much room the console asks for with `--auto-layout`: more while the task prints or
+    /// once it has failed, less after it has been idle for [`IDLE_AFTER`]. The weight moves
+    /// towards that gradually, so the layout re-balances smoothly.
+    pub fn layout_weight(&mut self) -> u16 {
+        let active = self.failed
+            || self
+                .last_output_at
+                .is_some_and(|at| at.elapsed() < IDLE_AFTER);
+        let target = if active { ACTIVE_LAYOUT_WEIGHT } else { 1.0 };
+        let now = Instant::now();
+        let weight = match self.layout_weight {
+            Some((weight, updated)) => {
+                let elapsed = now.duration_since(updated).as_secs_f64();
+                let progress = 1.0 - (-elapsed / LAYOUT_SMOOTHING.as_secs_f64()).exp();
+                weight + (target - weight) * progress
+            }
+            None => target,
+        };
+        self.layout_weight = Some((weight, now));
+        (weight * 10.0).round() as u16
+    }
+
+    /// Whether stdout and stderr are shown apart, which a task in a pseudo terminal cannot be.
+    pub fn is_split(&self) -> bool {
+        self.split && self.screen.is_none()
+    }
+
+    /// Toggles showing stdout and stderr in panes of their own, returns `false` if the task
+    /// runs in a pseudo terminal, where they cannot be told apart.
+    pub fn toggle_split(&mut self) -> bool {
+        if self.is_term() {
+            return false;
+        }
+        self.split = !self.split;
+        true
+    }
+
+    /// Switches which pane of a split console the keys scroll, returns `false` if it is not
+    /// split.
+    pub fn switch_scrolled_pane(&mut self) -> bool {
+        if !self.is_split() {
+            return false;
+        }
+        self.scroll_stderr = !self.scroll_stderr;
+        true
+    }
+
+    fn scrolled_pane(&mut self) -> &mut StreamPane {
+        match self.scroll_stderr {
+            true => &mut self.stderr_pane,
+            false => &mut self.stdout_pane,
+        }
+    }
+
+    /// Whether the task runs in a pseudo terminal, see [`TaskUi::Term`].
+    pub fn is_term(&self) -> bool {
+        self.screen.is_some()
+    }
+
+    /// Writes typed or pasted text to the standard input of the task. Line breaks are sent as
+    /// `\r` to a pseudo terminal, as a terminal does for <Enter>.
+    pub fn send_input(&mut self, text: &str) -> color_eyre::Result<()> {
+        let mut text = text.replace("\r\n", "\n");
+        if self.is_term() {
+            text = text.replace('\n', "\r");
+        }
+        self.executor.write_stdin(text.as_bytes())
+    }
+
+    pub fn run_action(&mut self, action: &QuickAction) -> color_eyre::Result<()> {
+        match &action.effect {
+            ActionEffect::Send(text) => self.executor.write_stdin(text.as_bytes()),
+            ActionEffect::Signal(signal) => self.executor.send_signal(*signal),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.stop_requested = true;
+        if self.env_loading.take().is_some() {
+            self.annotate("stopped before it started");
+            self.transition(TaskState::Failed);
+            return;
+        }
+        if matches!(self.state, TaskState::Running | TaskState::Ready) {
+            self.transition(TaskState::Stopping);
+        }
+        self.signal_child(self.task.stop_signal());
+    }
+
+    /// Whether the process of a task removed from the config has exited, after it was stopped at
+    /// `since`. It is killed once it takes longer than [`RESTART_KILL_TIMEOUT`] to stop.
+    pub fn poll_retired(&mut self, since: Instant) -> bool {
+        match self.executor.try_wait() {
+            Ok(TaskStatus::Executing { .. }) => {
+                if since.elapsed() > RESTART_KILL_TIMEOUT {
+                    if let Err(e) = self.executor.start_kill() {
+                        self.message_sender.send_error(e);
+                    }
+                }
+                false
+            }
+            Ok(_) => true,
+            Err(e) => {
+                self.message_sender.send_error(e);
+                true
+            }
+        }
+    }
+
+    /// Sends the restart signal of the task if one is configured. Otherwise stops the task if it
+    /// is running and starts it again once it has exited, see [`Console::update_status`].
+    pub fn restart(&mut self) {
+        self.crash_looping = false;
+        self.quick_exits = 0;
+        match self.executor.try_wait() {
+            Ok(TaskStatus::Executing { .. }) => {
+                if let (Some(signal), false) = (self.task.restart_signal, self.command_changed()) {
+                    self.signal_child(signal);
+                    return;
+                }
+                self.stop();
+                self.restart_requested = Some(Instant::now());
+            }
+            Ok(status) => {
+                self.observe_exit(&status);
+                self.respawn();
+            }
+            Err(e) => self.message_sender.send_error(e),
+        }
+    }
+
+    /// Restarts the task with another command, for this session only. A reload of the config
+    /// file offers to restart it with the configured command again.
+    pub fn restart_with(&mut self, command: String) {
+        if self.edited_commands.is_empty() {
+            self.edited_commands.push(self.task.command.to_string());
+        }
+        self.edited_commands.retain(|edited| *edited != command);
+        self.edited_commands.push(command.clone());
+        self.task.command.set_line(command);
+        self.restart();
+    }
+
+    /// The other commands the task ran with, to recall while editing its command.
+    pub fn command_history(&self) -> Vec<String> {
+        let mut history = self.edited_commands.clone();
+        history.retain(|command| command != self.task.command.as_str());
+        history
+    }
+
+    /// Toggles between the output and its diff against the previous run, returns `false` if the
+    /// task has not been restarted yet.
+    pub fn toggle_diff_view(&mut self) -> bool {
+        if self.previous_run_lines.is_none() {
+            return false;
+        }
+        self.diff_view = !self.diff_view;
+        self.update_diff();
+        self.scroll_bottom = !self.diff_view;
+        self.output_vertical_scroll = 0;
+        true
+    }
+
+    /// Computes the diff of the current run against the previous one while the diff view is
+    /// shown, which takes too long to do on every render.
+    fn update_diff(&mut self) {
+        self.diff_cache = None;
+        if !self.diff_view {
+            return;
+        }
+        if let Some(previous) = self.previous_run_lines.as_mut() {
+            let diff = RunDiff::new(previous.make_contiguous(), self.run_lines.make_contiguous());
+            self.diff_cache = Some(diff);
+        }
+    }
+
+    fn respawn(&mut self) {
+        self.restart_requested = None;
+        self.previous_run_lines = Some(std::mem::take(&mut self.run_lines));
+        self.update_diff();
+        self.test_summary = None;
+        self.counters.reset();
+        self.annotate("restarted");
+        if self.command_changed() {
+            let rate = self.executor.output_rate();
+            self.executor = Self::new_executor(&self.task, &self.message_sender);
+            self.executor.set_output_rate(rate);
+            if let Some(plugins) = self.plugins.clone() {
+                self.executor.set_plugins(plugins);
+            }
+        }
+        if let Err(e) = self.execute() {
+            self.message_sender.send_error(e);
+        }
+    }
+
+    fn configured_rate(task: &TaskConfig) -> OutputRate {
+        task.rate_limit
+            .map_or(OutputRate::Unlimited, OutputRate::LinesPerSecond)
+    }
+
+    /// Stops reading the output of the task, or resumes reading it at the configured rate.
+    pub fn toggle_output_paused(&mut self) {
+        let rate = match self.executor.output_rate() {
+            OutputRate::Paused => Self::configured_rate(&self.task),
+            _ => OutputRate::Paused,
+        };
+        self.executor.set_output_rate(rate);
+    }
+
+    /// Toggles between reading the output as fast as possible and at the rate limit of the task,
+    /// [`DEFAULT_RATE_LIMIT`] lines per second if it has none.
+    pub fn toggle_rate_limit(&mut self) {
+        let rate = match self.executor.output_rate() {
+            OutputRate::LinesPerSecond(_) => OutputRate::Unlimited,
+            _ => OutputRate::LinesPerSecond(self.task.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT)),
+        };
+        self.executor.set_output_rate(rate);
+    }
+
+    /// How many bytes of a line are shown, 0 for all of them.
+    fn max_line_length(&self) -> usize {
+        match self.task.max_line_length {
+            _ if self.long_lines_expanded => 0,
+            Some(max_length) => max_length,
+            None => DEFAULT_MAX_LINE_LENGTH,
+        }
+    }
+
+    /// The line as it is shown, cut after [`TaskConfig::max_line_length`] bytes unless long lines
+    /// are expanded.
+    fn shorten<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        Console::shorten_to(line, self.max_line_length())
+    }
+
+    /// The line cut after `max_length` bytes, or all of it if `max_length` is 0. The cut never
+    /// falls inside a character or an escape sequence.
+    fn shorten_to(line: &str, max_length: usize) -> Cow<'_, str> {
+        if max_length == 0 || line.len() <= max_length {
+            return Cow::Borrowed(line);
+        }
+        let mut end = max_length;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(start) = ansi::open_escape(&line[..end]) {
+            end = start;
+        }
+        let cut = (line.len() - end).div_ceil(1024);
+        Cow::Owned(format!(
+            "{}\x1b[0m\x1b[2m … +{} KB\x1b[0m",
+            &line[..end],
+            cut
+        ))
+    }
+
+    /// Shows lines longer than [`TaskConfig::max_line_length`] in full, or cuts them again.
+    pub fn toggle_long_lines(&mut self) {
+        self.long_lines_expanded = !self.long_lines_expanded;
+        // wrapped again on the next render
+        self.wrap_width = 0;
+    }
+
+    pub fn toggle_line_numbers(&mut self) {
+        self.line_numbers = !self.line_numbers;
+    }
+
+    /// Scrolls so that the first row of the given 1-based line is at the top of the viewport.
+    pub fn go_to_line(&mut self, line: usize) {
+        let row = self.output_row_lines.partition_point(|&l| l < line);
+        let scroll_max = self.output_vertical_scroll_max.unwrap_or(0);
+        self.output_vertical_scroll = min(row, scroll_max);
+        self.scroll_bottom = self.output_vertical_scroll == scroll_max;
+    }
+
+    /// Scrolls to the first line received at or after `time`, returns `false` if there is none.
+    pub fn go_to_time(&mut self, time: SystemTime) -> bool {
+        if self.screen.is_some() || self.diff_view {
+            return false;
+        }
+        let Some(index) = self.output_line_times.iter().position(|&at| at >= time) else {
+            return false;
+        };
+        self.go_to_line(self.first_line() + index);
+        true
+    }
+
+    /// Where the output is scrolled to.
+    pub fn scroll_position(&self) -> ScrollPosition {
+        let top_line = self.top_line();
+        ScrollPosition {
+            top_line,
+            top_line_time: top_line
+                .checked_sub(self.first_line())
+                .and_then(|index| self.output_line_times.get(index))
+                .copied(),
+            follows: self.scroll_bottom,
+        }
+    }
+
+    /// The number of the first line still kept, see [`TaskConfig::max_lines`].
+    fn first_line(&self) -> usize {
+        self.output_line_count + 1 - self.output_lines.len()
+    }
+
+    /// The line at the top of the viewport, 0 without any output.
+    fn top_line(&self) -> usize {
+        self.output_row_lines
+            .get(self.output_vertical_scroll)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Bookmarks the line at the top of the viewport, or removes its bookmark.
+    pub fn toggle_bookmark(&mut self) {
+        if self.screen.is_some() || self.diff_view {
+            return;
+        }
+        let line = self.top_line();
+        if line > 0 && !self.bookmarks.remove(&line) {
+            self.bookmarks.insert(line);
+        }
+    }
+
+    /// Scrolls to the next bookmark below the top of the viewport, or the previous one above it.
+    pub fn jump_to_bookmark(&mut self, forward: bool) {
+        if self.screen.is_some() || self.diff_view {
+            return;
+        }
+        let top_line = self.top_line();
+        let bookmark = match forward {
+            true => self.bookmarks.range(top_line + 1..).next(),
+            false => self.bookmarks.range(..top_line).next_back(),
+        };
+        if let Some(&line) = bookmark {
+            self.go_to_line(line);
+        }
+    }
+
+    /// Polls the task status and starts flashing the border when the task has just failed.
+    /// A pending restart is carried out here once the task has exited, or the task is killed if
+    /// it takes too long to stop.
+    pub fn update_status(&mut self) -> Option<TaskStatus> {
+        let status = match self.executor.try_wait() {
+            Ok(status) => status,
+            Err(e) => {
+                self.message_sender.send_error(e);
+                return None;
+            }
+        };
+        if self.env_loading.is_some() {
+            self.status = Some(TaskStatus::Ready(self.raw_command().to_string()));
+            return self.status.clone();
+        }
+        if let Some(requested) = self.restart_requested {
+            if !matches!(status, TaskStatus::Executing { .. }) {
+                // ends the stopped run first, a task cannot start again while it is stopping
+                self.observe_exit(&status);
+                self.respawn();
+            } else if requested.elapsed() > RESTART_KILL_TIMEOUT {
+                if let Err(e) = self.executor.start_kill() {
+                    self.message_sender.send_error(e);
+                }
+            }
+            self.status = Some(TaskStatus::Ready(self.raw_command().to_string()));
+            return self.status.clone();
+        }
+        self.status = Some(status.clone());
+        let failed = status.is_failed();
+        if failed && !self.failed {
+            self.flash_until = Some(Instant::now() + FAILURE_FLASH_DURATION);
+        }
+        self.failed = failed;
+        self.poll_exit(&status);
+        Some(status)
+    }
+
+    /// Runs the exit hook once the current run has exited, and starts the task again according
+    /// to its [`RestartPolicy`].
+    pub fn poll_exit(&mut self, status: &TaskStatus) {
+        if let (
+            TaskStatus::Exited {
+                status: exit,
+                success,
+                ..
+            },
+            false,
+        ) = (status, self.exit_handled)
+        {
+            self.exit_handled = true;
+            self.run_exit_hook(*exit, *success);
+            if self.restarts_on(*success) {
+                self.observe_exit(status);
+                self.respawn();
+            }
+        }
+    }
+
+    /// Whether the task is restarted after exiting successfully or not: not if it was asked to
+    /// stop, nor once it is crash-looping, see [`crate::config::StartLimit`].
+    fn restarts_on(&mut self, success: bool) -> bool {
+        let restart = match self.task.restart {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !success,
+            RestartPolicy::Always => true,
+        };
+        if !restart || self.stop_requested || self.is_restarting() || self.crash_looping {
+            return false;
+        }
+        let limit = self.task.start_limit;
+        if self.run_duration() < Duration::from_secs(limit.interval) {
+            self.quick_exits += 1;
+        } else {
+            self.quick_exits = 0;
+        }
+        if self.quick_exits > limit.burst {
+            self.crash_looping = true;
+            self.annotate(&format!(
+                "crash-looping: exited {} times within {}s of starting, restart it with `r`",
+                self.quick_exits, limit.interval
+            ));
+            return false;
+        }
+        true
+    }
+
+    /// How long the last run took, once it has exited.
+    fn run_duration(&self) -> Duration {
+        self.exited_at()
+            .zip(self.started_at())
+            .and_then(|(exited_at, started_at)| exited_at.duration_since(started_at).ok())
+            .unwrap_or_default()
+    }
+
+    /// Starts `after_success` or `after_failure` in the background, with what is known about
+    /// the run in environment variables so that the hook can be a simple script.
+    fn run_exit_hook(&mut self, status: ExitStatus, success: bool) {
+        let hook = match success {
+            true => self.task.after_success.clone(),
+            false => self.task.after_failure.clone(),
+        };
+        let Some(hook) = hook else {
+            return;
+        };
+        let mut args = hook.split_whitespace();
+        let Some(program) = args.next() else {
+            return;
+        };
+        let duration = self.run_duration();
+        let mut command = tokio::process::Command::new(program);
+        command
+            .args(args)
+            .env("PARALLELY_TASK", self.task.key())
+            .env(
+                "PARALLELY_EXIT_CODE",
+                status
+                    .code()
+                    .map(|code| code.to_string())
+                    .unwrap_or_default(),
+            )
+            .env(
+                "PARALLELY_DURATION",
+                format!("{:.3}", duration.as_secs_f64()),
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(log) = self.log.as_ref() {
+            command.env("PARALLELY_LOG_FILE", log.path());
+        }
+        match command.spawn() {
+            Ok(mut child) => {
+                self.annotate(&format!("running hook {}", hook));
+                let message_sender = self.message_sender.clone();
+                self.message_sender.supervisor().spawn(async move {
+                    match child.wait().await {
+                        Ok(status) if status.success() => {}
+                        Ok(status) => {
+                            message_sender.send_error(eyre!("Hook {} exited with {}", hook, status))
+                        }
+                        Err(e) => message_sender.send_error(e),
+                    }
+                });
+            }
+            Err(e) => self.annotate(&format!("failed to run hook {}: {}", hook, e)),
+        }
+    }
+
+    fn flashing(&mut self) -> bool {
+        match self.flash_until {
+            Some(until) => {
+                let now = Instant::now();
+                if now >= until {
+                    self.flash_until = None;
+                    false
+                } else {
+                    let remaining = (until - now).as_millis();
+                    (remaining / FAILURE_FLASH_INTERVAL_MS).is_multiple_of(2)
+                }
+            }
+            None => false,
+        }
+    }
+
+    /// Marks the bookmarks on the track of the scrollbar, between its arrows, at the position of
+    /// their line within the whole output.
+    fn render_bookmarks(&self, scrollbar_rect: Rect, buf: &mut Buffer) {
+        let track_height = scrollbar_rect.height.saturating_sub(2) as usize;
+        let rows = self.output_row_lines.len();
+        if track_height == 0 || rows == 0 {
+            return;
+        }
+        let x = scrollbar_rect.right().saturating_sub(1);
+        for line in &self.bookmarks {
+            let row = self.output_row_lines.partition_point(|&l| l < *line);
+            let y = scrollbar_rect.y + 1 + (row * track_height / rows) as u16;
+            if let Some(cell) = buf.cell_mut((x, y)) {
+                cell.set_symbol("◆").set_style(Style::new().yellow());
+            }
+        }
+    }
+
+    /// Cuts the text to fit into `width` columns, ending it with `…` if anything was cut off.
+    pub fn ellipsize(text: &str, width: usize) -> String {
+        if textwrap::core::display_width(text) <= width {
+            return text.to_string();
+        }
+        let mut result = String::new();
+        let mut result_width = 0;
+        for c in text.chars() {
+            let char_width = textwrap::core::display_width(c.encode_utf8(&mut [0; 4]));
+            if result_width + char_width + 1 > width {
+                break;
+            }
+            result.push(c);
+            result_width += char_width;
+        }
+        result.push('…');
+        result
+    }
+
     fn wrap_text(text: &str, width_limit: usize) -> Vec<String> {
+        if text.len() > CHUNK_WRAP_LENGTH {
+            return Self::chunk_text(text, width_limit);
+        }
         textwrap::wrap(text, width_limit)
             .into_iter()
             .map(|part| match part {
@@ -101,18 +1809,46 @@ impl Console {
             .collect::<Vec<_>>()
     }
 
-    fn append_text(text: &mut Text<'static>, lines: Vec<String>) {
-        lines.into_iter().for_each(|line| match line.into_text() {
-            Ok(t) => text.extend(t),
-            Err(_) => text.push_line(line),
-        });
+    /// Cuts the text into rows of `width_limit` columns, regardless of words. ANSI escape
+    /// sequences take up no room.
+    fn chunk_text(text: &str, width_limit: usize) -> Vec<String> {
+        let mut rows = Vec::new();
+        let mut row = String::new();
+        let mut row_width = 0;
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                row.push(c);
+                if let Some(c) = chars.next() {
+                    row.push(c);
+                    if c == '[' {
+                        for c in chars.by_ref() {
+                            row.push(c);
+                            if ('@'..='~').contains(&c) {
+                                break;
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+            let char_width = textwrap::core::display_width(c.encode_utf8(&mut [0; 4]));
+            if row_width + char_width > width_limit && row_width > 0 {
+                rows.push(std::mem::take(&mut row));
+                row_width = 0;
+            }
+            row.push(c);
+            row_width += char_width;
+        }
+        rows.push(row);
+        rows
     }
 }
 
 impl StatefulWidget for &mut Console {
     type State = Context;
 
-    fn render(self, area: Rect, buf: &mut Buffer, _context: &mut Context)
+    fn render(self, area: Rect, buf: &mut Buffer, context: &mut Context)
     where
         Self: Sized,
     {
@@ -120,65 +1856,357 @@ impl StatefulWidget for &mut Console {
         let inner_area = container.inner(area);
         container.render(area, buf);
 
-        let width_limit = inner_area.width as usize - 2;
-        if let Err(e) = self.receive(width_limit) {
-            self.message_sender.send_error(e);
-        }
+        let width_limit = (inner_area.width as usize).saturating_sub(2).max(1);
+        // the sidebar of the counters is left out where it would crowd the output
+        let sidebar_width = match self.counters.is_empty()
+            || context.compact
+            || inner_area.width < counters::SIDEBAR_WIDTH * 3
+        {
+            true => 0,
+            false => counters::SIDEBAR_WIDTH,
+        };
+        let output_width = width_limit.saturating_sub(sidebar_width as usize).max(1);
+        self.fit_width(output_width);
 
-        let title_str = format!("[{}] - ({})", self.raw_command(), self.pid().unwrap_or(0));
-        let title_text = Text::from(
-            Console::wrap_text(&title_str, width_limit)
-                .into_iter()
-                .map(Line::from)
-                .collect::<Vec<_>>(),
-        );
-        let [title_rect, output_rect] = Layout::vertical([
-            Constraint::Max(title_text.lines.len() as u16 + 2),
-            Constraint::Min(1),
-        ])
-        .areas(inner_area);
-
-        let title_block = Block::bordered()
-            .title(" Command - PID ".magenta().bold())
-            .border_type(BorderType::Rounded);
-        let title = Paragraph::new(title_text.blue()).block(title_block);
-        title.render(title_rect, buf);
+        let title_str = match &self.task.name {
+            Some(name) => format!(
+                "{}: [{}] - ({})",
+                name,
+                self.raw_command(),
+                self.pid().unwrap_or(0)
+            ),
+            None => format!("[{}] - ({})", self.raw_command(), self.pid().unwrap_or(0)),
+        };
+        let title_width = if context.compact {
+            // leave room for the status glyph and the corners of the border
+            width_limit.saturating_sub(4)
+        } else {
+            width_limit
+        };
+        let title_text = Console::ellipsize(&title_str, title_width);
+        let title_truncated = title_text != title_str;
+        let title_height = if context.compact { 0 } else { 3 };
+        let [title_rect, output_rect] =
+            Layout::vertical([Constraint::Max(title_height), Constraint::Min(1)]).areas(inner_area);
+        let [output_rect, sidebar_rect] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Length(sidebar_width)])
+                .areas(output_rect);
+
+        let status = self.status.clone();
+        let border_style = if self.flashing() {
+            Style::new().red().reversed()
+        } else if self.focused {
+            Style::new().yellow()
+        } else {
+            Style::new()
+        };
+        let probing = self.task.ready.is_some() && !self.is_ready();
+        let status_glyph = match &status {
+            Some(status @ TaskStatus::Executing { .. }) if probing => status.glyph().yellow(),
+            Some(status @ TaskStatus::Executing { .. }) => status.glyph().green(),
+            Some(status @ TaskStatus::Ready(_)) => status.glyph().yellow(),
+            Some(status) if status.is_failed() => status.glyph().red(),
+            Some(status) => status.glyph().green(),
+            None => "?".red(),
+        };
 
+        let text = |phrase| context.lang.text(phrase);
+        let mut title_line = Line::from(vec![" ".into(), status_glyph.bold()]);
+        if context.compact {
+            title_line.push_span(format!(" {} ", title_text).blue());
+        } else {
+            title_line.push_span(format!(" {} ", text(Phrase::CommandPid)).magenta().bold());
+        }
+        if self.waiting {
+            let dependencies = self.task.depends_on.join(", ");
+            title_line
+                .push_span(format!("{} {} ", text(Phrase::WaitingFor), dependencies).yellow());
+        } else if let (Some(probe), true, Some(TaskStatus::Executing { .. })) =
+            (self.task.ready.as_ref(), probing, &status)
+        {
+            title_line.push_span(format!("{} {} ", text(Phrase::WaitingFor), probe).yellow());
+        }
+        match self.executor.output_rate() {
+            OutputRate::Unlimited => {}
+            OutputRate::Paused => {
+                title_line.push_span(format!("⏸ {} ", text(Phrase::Paused)).yellow().bold())
+            }
+            rate => title_line.push_span(format!("⏱ {} ", rate).yellow()),
+        }
+        if let Some(muted) = self.muted {
+            title_line.push_span(
+                format!("⊘ {} {} ", text(Phrase::Muted), muted)
+                    .yellow()
+                    .bold(),
+            );
+        }
+        if self.received_bytes > 0 && !context.compact {
+            title_line.push_span(
+                format!(
+                    "≡ {} {}, {} ",
+                    self.received_lines,
+                    text(Phrase::Lines),
+                    report::format_bytes(self.received_bytes)
+                )
+                .dark_gray(),
+            );
+        }
+        if let Some(io_stats) = self.executor.io_stats().filter(|_| !context.compact) {
+            let mut io = format!(
+                "⇅ R {} W {} ",
+                report::format_bytes(io_stats.read_bytes),
+                report::format_bytes(io_stats.write_bytes)
+            );
+            if let Some(net) = io_stats.net {
+                io.push_str(&format!(
+                    "↓ {} ↑ {} ",
+                    report::format_bytes(net.received_bytes),
+                    report::format_bytes(net.sent_bytes)
+                ));
+            }
+            title_line.push_span(io.dark_gray());
+        }
+        if self.dropped_lines > 0 {
+            title_line.push_span(
+                format!("✂ {} {} ", self.dropped_lines, text(Phrase::Dropped)).dark_gray(),
+            );
+        }
+        if let Some(summary) = self.test_summary {
+            title_line.push_span(format!("✔ {} ", summary.passed).green().bold());
+            if summary.failed > 0 {
+                title_line.push_span(format!("✖ {} ", summary.failed).red().bold());
+            }
+            if summary.skipped > 0 {
+                title_line.push_span(format!("○ {} ", summary.skipped).dark_gray().bold());
+            }
+        }
+        if self.crash_looping {
+            title_line.push_span(
+                format!(" {} ", text(Phrase::CrashLooping))
+                    .red()
+                    .bold()
+                    .reversed(),
+            );
+            title_line.push_span(" ");
+        }
+        if self.marked {
+            title_line.push_span(
+                format!(" ◆ {} ", text(Phrase::Marked))
+                    .cyan()
+                    .bold()
+                    .reversed(),
+            );
+            title_line.push_span(" ");
+        }
+        let output_title = if context.compact {
+            Some(title_line)
+        } else {
+            let title_block = Block::bordered()
+                .title(title_line)
+                .border_type(BorderType::Rounded)
+                .border_style(border_style);
+            let title = Paragraph::new(title_text.blue()).block(title_block);
+            title.render(title_rect, buf);
+            None
+        };
+
+        let diff = self
+            .diff_cache
+            .as_ref()
+            .map(|diff| (diff.text.lines.len(), diff.added, diff.removed));
+        let output_title = match (output_title, diff.is_some()) {
+            (Some(mut title_line), true) => {
+                title_line.push_span(format!(" [{}] ", text(Phrase::Diff)).yellow().bold());
+                title_line
+            }
+            (Some(title_line), false) => title_line,
+            (None, true) => Line::from(
+                format!(" [{}] ", text(Phrase::DiffVsPrevious))
+                    .yellow()
+                    .bold(),
+            ),
+            (None, false) => Line::from(format!(" [{}] ", text(Phrase::Output)).green().bold()),
+        };
+        let pane_title = match context.compact {
+            true => output_title.clone(),
+            false => Line::default(),
+        };
         let output_block = Block::bordered()
-            .title(Title::from(" [output] ".green().bold()).alignment(Alignment::Left))
-            .border_type(BorderType::Rounded);
-        let output_scroll_max = self
-            .output_text
-            .lines
-            .len()
-            .saturating_sub(output_block.inner(output_rect).height as usize);
-        if self.scroll_bottom {
-            self.output_vertical_scroll = output_scroll_max;
-        }
-        let output = Paragraph::new(self.output_text.clone())
-            .scroll((self.output_vertical_scroll as u16, 0))
-            .block(output_block);
-        output.render(output_rect, buf);
-
-        let output_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(Some("↑"))
-            .end_symbol(Some("↓"));
-        let mut scrollbar_state =
-            ScrollbarState::new(output_scroll_max).position(self.output_vertical_scroll);
-        output_scrollbar.render(
-            output_rect.inner(Margin {
+            .title(Title::from(output_title).alignment(Alignment::Left))
+            .border_type(BorderType::Rounded)
+            .border_style(border_style);
+        let output_inner_rect = output_block.inner(output_rect);
+        if sidebar_width > 0 {
+            let title = Line::from(format!(" {} ", text(Phrase::Counters)).green().bold());
+            self.counters.render(sidebar_rect, buf, title, border_style);
+        }
+        let output_scroll_max = if let Some(parser) = self.screen.as_mut() {
+            let size = (
+                output_inner_rect.height.max(1),
+                output_inner_rect.width.max(1),
+            );
+            if parser.screen().size() != size {
+                parser.screen_mut().set_size(size.0, size.1);
+                #[cfg(unix)]
+                if let Err(e) = self.executor.resize_pty(size.0, size.1) {
+                    self.message_sender.send_error(e);
+                }
+            }
+            let position = match parser.screen().scrollback() {
+                0 => " term ".to_string(),
+                scrollback => format!(" ↑ {} ", scrollback),
+            };
+            output_block
+                .title_bottom(Line::from(position).right_aligned())
+                .render(output_rect, buf);
+            term::render(parser.screen(), output_inner_rect, buf);
+            0
+        } else if self.split {
+            let max_length = self.max_line_length();
+            let [stdout_rect, stderr_rect] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(output_rect);
+            self.stdout_pane
+                .render(stdout_rect, buf, pane_title, border_style, max_length);
+            self.stderr_pane
+                .render(stderr_rect, buf, Line::default(), border_style, max_length);
+            0
+        } else {
+            let output_scroll_max = diff
+                .as_ref()
+                .map(|(lines, _, _)| *lines)
+                .unwrap_or(self.output_rows.len())
+                .saturating_sub(output_inner_rect.height as usize);
+            if self.scroll_bottom {
+                self.output_vertical_scroll = output_scroll_max;
+            } else {
+                self.output_vertical_scroll = min(self.output_vertical_scroll, output_scroll_max);
+            }
+            let position = match &diff {
+                Some((_, added, removed)) => format!(" +{} -{} ", added, removed),
+                None => format!(
+                    " {} {}/{} ",
+                    text(Phrase::Line),
+                    self.top_line(),
+                    self.output_line_count
+                ),
+            };
+            let output_block = output_block.title_bottom(Line::from(position).right_aligned());
+            output_block.render(output_rect, buf);
+
+            let text_rect = if self.line_numbers && diff.is_none() {
+                let gutter_width = self.output_line_count.max(1).to_string().len() as u16;
+                let [gutter_rect, text_rect] =
+                    Layout::horizontal([Constraint::Length(gutter_width), Constraint::Fill(1)])
+                        .spacing(1)
+                        .areas(output_inner_rect);
+                let gutter = self
+                    .output_row_lines
+                    .iter()
+                    .enumerate()
+                    .skip(self.output_vertical_scroll)
+                    .take(gutter_rect.height as usize)
+                    .map(|(row, &line)| {
+                        let first_row = row == 0 || self.output_row_lines[row - 1] != line;
+                        if first_row && self.bookmarks.contains(&line) {
+                            Line::from(line.to_string().yellow())
+                        } else if first_row {
+                            Line::from(line.to_string().dark_gray())
+                        } else {
+                            Line::default()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                Paragraph::new(gutter)
+                    .alignment(Alignment::Right)
+                    .render(gutter_rect, buf);
+                text_rect
+            } else {
+                output_inner_rect
+            };
+            let diffing = diff.is_some();
+            // only the lines in view are copied, and only the rows in view are parsed
+            let output = match self.diff_cache.as_ref() {
+                Some(diff) => Paragraph::new(
+                    diff.text
+                        .lines
+                        .iter()
+                        .skip(self.output_vertical_scroll)
+                        .take(text_rect.height as usize)
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                ),
+                None => Paragraph::new(
+                    self.output_rows
+                        .iter()
+                        .skip(self.output_vertical_scroll)
+                        .take(text_rect.height as usize)
+                        .map(|row| self.text_cache.get(row))
+                        .collect::<Vec<_>>(),
+                ),
+            };
+            output.render(text_rect, buf);
+
+            let output_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(Some("↑"))
+                .end_symbol(Some("↓"));
+            let mut scrollbar_state =
+                ScrollbarState::new(output_scroll_max).position(self.output_vertical_scroll);
+            let scrollbar_rect = output_rect.inner(Margin {
                 horizontal: 0,
                 vertical: 1,
-            }),
-            buf,
-            &mut scrollbar_state,
-        );
+            });
+            output_scrollbar.render(scrollbar_rect, buf, &mut scrollbar_state);
+            if !diffing {
+                self.render_bookmarks(scrollbar_rect, buf);
+            }
+            output_scroll_max
+        };
 
+        let title_rect = if context.compact {
+            Rect {
+                height: 1,
+                ..output_rect
+            }
+        } else {
+            title_rect
+        };
+        if self.title_hovered && (title_truncated || self.resolved.is_some()) {
+            let mut lines = Console::wrap_text(&title_str, width_limit)
+                .into_iter()
+                .map(Line::from)
+                .collect::<Vec<_>>();
+            if let Some(resolved) = self.resolved.as_ref() {
+                let resolved = format!("→ {}", resolved);
+                let wrapped = Console::wrap_text(&resolved, width_limit).into_iter();
+                lines.extend(wrapped.map(|line| Line::from(line).dark_gray()));
+            }
+            let overlay_rect = Rect {
+                height: min(lines.len() as u16 + 2, area.bottom() - title_rect.y),
+                ..title_rect
+            };
+            Clear.render(overlay_rect, buf);
+            Paragraph::new(lines)
+                .blue()
+                .block(
+                    Block::bordered()
+                        .border_type(BorderType::Rounded)
+                        .border_style(border_style),
+                )
+                .render(overlay_rect, buf);
+        }
+
+        self.area = Some(area);
+        self.title_rect = Some(title_rect);
         self.output_rect = Some(output_rect);
         self.output_vertical_scroll_max = Some(output_scroll_max);
     }
 }
 
+/// A dimmed note between the lines of output, e.g. where the task was restarted.
+fn annotation(text: &str) -> String {
+    format!("\x1b[2m─── {} ───\x1b[0m", text)
+}
+
 impl Deref for Console {
     type Target = TaskExecutor;
 
@@ -0,0 +1,112 @@
+use crate::console::text_cache::TextCache;
+use crate::console::Console;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, BorderType, Paragraph, Widget};
+use std::collections::VecDeque;
+
+/// How many lines each pane keeps at most.
+const PANE_LINES: usize = 5_000;
+
+/// The output of one pipe of a task, shown when its console is split into stdout and stderr,
+/// with a scroll position of its own. Only the rows in view are wrapped, from the bottom up.
+pub struct StreamPane {
+    name: &'static str,
+    lines: VecDeque<String>,
+    /// How many rows the pane is scrolled up from the bottom.
+    scroll_back: usize,
+    rect: Option<Rect>,
+    text_cache: TextCache,
+}
+
+impl StreamPane {
+    pub fn new(name: &'static str) -> Self {
+        StreamPane {
+            name,
+            lines: VecDeque::new(),
+            scroll_back: 0,
+            rect: None,
+            text_cache: TextCache::default(),
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() == PANE_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn clear(&mut self) {
+        self.lines = VecDeque::new();
+        self.scroll_back = 0;
+        self.text_cache.clear();
+    }
+
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        self.rect
+            .is_some_and(|rect| rect.contains((column, row).into()))
+    }
+
+    /// The height of the pane inside its border, e.g. for scrolling by pages.
+    pub fn page(&self) -> usize {
+        self.rect
+            .map_or(1, |rect| rect.height.saturating_sub(2).max(1) as usize)
+    }
+
+    /// Scrolls up or down by rows, clamped on the next render.
+    pub fn scroll(&mut self, up: usize, down: usize) {
+        self.scroll_back = self.scroll_back.saturating_add(up).saturating_sub(down);
+    }
+
+    /// Renders the lines in view, each cut after `max_length` bytes unless it is 0.
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        title: Line<'static>,
+        style: Style,
+        max_length: usize,
+    ) {
+        let mut title = title;
+        title.push_span(format!(" {} ", self.name).green().bold());
+        let mut block = Block::bordered()
+            .title(title)
+            .border_type(BorderType::Rounded)
+            .border_style(style);
+        let inner = block.inner(area);
+        let width = (inner.width as usize).max(1);
+        let height = inner.height as usize;
+
+        // wrap from the last line up until the rows in view are known
+        let mut rows = Vec::new();
+        for line in self.lines.iter().rev() {
+            if rows.len() >= self.scroll_back.saturating_add(height) {
+                break;
+            }
+            let line = Console::shorten_to(line, max_length);
+            rows.extend(Console::wrap_text(&line, width).into_iter().rev());
+        }
+        self.scroll_back = self.scroll_back.min(rows.len().saturating_sub(height));
+        let mut text = Text::default();
+        for row in rows
+            .into_iter()
+            .skip(self.scroll_back)
+            .take(height)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            text.push_line(self.text_cache.get(&row));
+        }
+        if self.scroll_back > 0 {
+            block =
+                block.title_bottom(Line::from(format!(" ↑ {} ", self.scroll_back)).right_aligned());
+        }
+        block.render(area, buf);
+        Paragraph::new(text).render(inner, buf);
+        self.rect = Some(area);
+    }
+}
@@ -0,0 +1,56 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+
+/// Draws the visible part of a terminal screen into the area, cell by cell.
+pub fn render(screen: &vt100::Screen, area: Rect, buf: &mut Buffer) {
+    for row in 0..area.height {
+        for col in 0..area.width {
+            let Some(cell) = screen.cell(row, col) else {
+                continue;
+            };
+            if cell.is_wide_continuation() {
+                continue;
+            }
+            let mut modifier = Modifier::empty();
+            for (enabled, flag) in [
+                (cell.bold(), Modifier::BOLD),
+                (cell.dim(), Modifier::DIM),
+                (cell.italic(), Modifier::ITALIC),
+                (cell.underline(), Modifier::UNDERLINED),
+                (cell.inverse(), Modifier::REVERSED),
+            ] {
+                if enabled {
+                    modifier |= flag;
+                }
+            }
+            let style = Style::new()
+                .fg(color(cell.fgcolor()))
+                .bg(color(cell.bgcolor()))
+                .add_modifier(modifier);
+            let symbol = if cell.has_contents() {
+                cell.contents()
+            } else {
+                " "
+            };
+            buf[(area.x + col, area.y + row)]
+                .set_symbol(symbol)
+                .set_style(style);
+        }
+    }
+
+    let (row, col) = screen.cursor_position();
+    if !screen.hide_cursor() && screen.scrollback() == 0 && row < area.height && col < area.width {
+        buf[(area.x + col, area.y + row)]
+            .modifier
+            .toggle(Modifier::REVERSED);
+    }
+}
+
+fn color(color: vt100::Color) -> Color {
+    match color {
+        vt100::Color::Default => Color::Reset,
+        vt100::Color::Idx(index) => Color::Indexed(index),
+        vt100::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
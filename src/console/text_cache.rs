@@ -0,0 +1,65 @@
+use ansi_to_tui::IntoText;
+use ratatui::text::Line;
+use std::collections::HashMap;
+
+/// How many rows a [`TextCache`] keeps, a few screens full.
+const CAPACITY: usize = 2048;
+
+/// The styled text of rows of output with ANSI escape sequences, parsed only once they are shown
+/// and kept by their content, so that scrolling over rows shown before or redrawing the same
+/// screen does not parse them again. The rows which have been shown the longest time ago are
+/// dropped beyond [`CAPACITY`].
+#[derive(Default)]
+pub struct TextCache {
+    rows: HashMap<String, (Line<'static>, u64)>,
+    /// Counts up with every lookup, for when each row was last shown.
+    clock: u64,
+}
+
+impl TextCache {
+    /// The styled text of a row, the row itself if it cannot be parsed.
+    pub fn get(&mut self, row: &str) -> Line<'static> {
+        self.clock += 1;
+        if let Some((line, shown_at)) = self.rows.get_mut(row) {
+            *shown_at = self.clock;
+            return line.clone();
+        }
+        if self.rows.len() >= CAPACITY {
+            self.evict();
+        }
+        let line = parse(row);
+        self.rows
+            .insert(row.to_string(), (line.clone(), self.clock));
+        line
+    }
+
+    /// Drops the half of the rows which have been shown the longest time ago.
+    fn evict(&mut self) {
+        let mut shown_at = self.rows.values().map(|(_, at)| *at).collect::<Vec<_>>();
+        let (_, median, _) = shown_at.select_nth_unstable(self.rows.len() / 2);
+        let median = *median;
+        self.rows.retain(|_, (_, at)| *at > median);
+    }
+
+    /// How many rows are parsed.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.rows = HashMap::new();
+    }
+}
+
+/// A row as a single line, even if it holds e.g. a carriage return.
+fn parse(row: &str) -> Line<'static> {
+    match row.into_text() {
+        Ok(text) => Line::from(
+            text.lines
+                .into_iter()
+                .flat_map(|line| line.spans)
+                .collect::<Vec<_>>(),
+        ),
+        Err(_) => Line::from(row.to_string()),
+    }
+}
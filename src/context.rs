@@ -1,4 +1,11 @@
+use crate::i18n::Lang;
+
+/// Render options shared by the app and every console.
 #[derive(Default)]
-pub struct Context {}
+pub struct Context {
+    /// Show the command and PID in the border of the output block instead of a separate block.
+    pub compact: bool,
+    pub lang: Lang,
+}
 
 impl Context {}
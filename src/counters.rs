@@ -0,0 +1,146 @@
+use crate::ansi;
+use crate::console::Console;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, BorderType, Paragraph, Widget};
+use regex_automata::meta::Regex;
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// How wide the sidebar of the counters is, borders included.
+pub const SIDEBAR_WIDTH: u16 = 32;
+
+/// A regular expression whose matches in the output of a task are counted, e.g.
+/// `"requests? served"` or `"ERROR|FATAL"`, see [`crate::config::TaskConfig::counters`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct CounterPattern {
+    pattern: String,
+    regex: Regex,
+}
+
+#[derive(Debug, Error)]
+pub enum CounterPatternError {
+    #[error("Invalid counter `{pattern}`: {message}")]
+    Invalid { pattern: String, message: String },
+}
+
+impl FromStr for CounterPattern {
+    type Err = CounterPatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let regex = Regex::new(pattern).map_err(|e| CounterPatternError::Invalid {
+            pattern: pattern.to_string(),
+            message: e
+                .syntax_error()
+                .map_or_else(|| e.to_string(), ToString::to_string),
+        })?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+}
+
+impl TryFrom<String> for CounterPattern {
+    type Error = CounterPatternError;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        pattern.parse()
+    }
+}
+
+impl Display for CounterPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// How many lines of the current run matched a pattern, and the last one which did.
+#[derive(Debug, Clone)]
+struct Counter {
+    pattern: CounterPattern,
+    count: u64,
+    last_match: Option<String>,
+}
+
+/// The counters of a console, updated as the lines of output arrive, whether they are shown
+/// or not, and shown in a sidebar next to the output.
+#[derive(Debug, Clone, Default)]
+pub struct Counters(Vec<Counter>);
+
+impl Counters {
+    pub fn new(patterns: &[CounterPattern]) -> Self {
+        Self(
+            patterns
+                .iter()
+                .map(|pattern| Counter {
+                    pattern: pattern.clone(),
+                    count: 0,
+                    last_match: None,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Counts the line once for every pattern it matches, without its ANSI escape sequences.
+    pub fn observe(&mut self, line: &str) {
+        if self.0.is_empty() {
+            return;
+        }
+        let line = ansi::strip(line);
+        for counter in self.0.iter_mut() {
+            if counter.pattern.regex.is_match(&line) {
+                counter.count += 1;
+                counter.last_match = Some(line.trim().to_string());
+            }
+        }
+    }
+
+    /// Starts counting from zero, e.g. when the task is restarted.
+    pub fn reset(&mut self) {
+        for counter in self.0.iter_mut() {
+            counter.count = 0;
+            counter.last_match = None;
+        }
+    }
+
+    /// Renders the sidebar, one counter after another with its count next to its pattern and
+    /// its last match below, as much of it as fits.
+    pub fn render(&self, area: Rect, buf: &mut Buffer, title: Line<'static>, style: Style) {
+        let block = Block::bordered()
+            .title(title)
+            .border_type(BorderType::Rounded)
+            .border_style(style);
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let width = inner.width as usize;
+        let mut text = Text::default();
+        for counter in self.0.iter() {
+            let count = counter.count.to_string();
+            let pattern = Console::ellipsize(
+                &counter.pattern.pattern,
+                width.saturating_sub(count.len() + 1),
+            );
+            let count = match counter.count {
+                0 => count.dark_gray().bold(),
+                _ => count.cyan().bold(),
+            };
+            text.push_line(Line::from(vec![count, " ".into(), pattern.into()]));
+            let last_match = match counter.last_match.as_deref() {
+                Some(line) => Console::ellipsize(line, width.saturating_sub(2)).dark_gray(),
+                None => "-".dark_gray(),
+            };
+            text.push_line(Line::from(vec!["  ".into(), last_match]));
+        }
+        Paragraph::new(text).render(inner, buf);
+    }
+}
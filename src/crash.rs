@@ -0,0 +1,69 @@
+use crate::history;
+use crate::report;
+use crate::report::TaskReport;
+use color_eyre::eyre::WrapErr;
+use std::any::Any;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many lines of output of each task are kept in a crash dump at most.
+pub const DUMP_LINES: usize = 10_000;
+
+/// Writes what parallely crashed with, the state of every task as `tasks.json` and the output of
+/// each task as `<index>-<task>.log` into a new directory next to the history. Returns the
+/// directory.
+pub fn dump(
+    reports: &[TaskReport],
+    started_at: SystemTime,
+    reason: &str,
+) -> color_eyre::Result<PathBuf> {
+    let base = history::path()
+        .and_then(|path| path.parent().map(|dir| dir.join("crashes")))
+        .unwrap_or_else(|| std::env::temp_dir().join("parallely-crashes"));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let dir = base.join(format!("{}-{}", now, std::process::id()));
+    std::fs::create_dir_all(&dir)
+        .wrap_err_with(|| format!("Failed to create directory {}", dir.display()))?;
+
+    std::fs::write(dir.join("error.txt"), reason)?;
+    report::write_json(reports, started_at, &dir.join("tasks.json"))?;
+    for (index, report) in reports.iter().enumerate() {
+        let name = report
+            .command
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(40)
+            .collect::<String>();
+        let status = match &report.status {
+            Ok(status) => status.to_string(),
+            Err(error) => error.to_string(),
+        };
+        let mut content = format!("# {}\n# {}\n", report.command, status);
+        for line in report.last_lines.iter() {
+            content.push_str(line);
+            content.push('\n');
+        }
+        std::fs::write(dir.join(format!("{}-{}.log", index + 1, name)), content)?;
+    }
+    Ok(dir)
+}
+
+/// The message a panic was started with.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => format!("panicked: {}", message),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => format!("panicked: {}", message),
+            None => "panicked".to_string(),
+        },
+    }
+}
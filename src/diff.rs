@@ -0,0 +1,59 @@
+use crate::ansi;
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Text};
+use similar::{Algorithm, DiffTag};
+use std::time::{Duration, Instant};
+
+/// Gives up on finding the minimal diff after this long, so huge outputs can't stall the UI.
+const DIFF_DEADLINE: Duration = Duration::from_millis(200);
+
+/// The line diff between the output of the previous and the current run of a task.
+pub struct RunDiff {
+    pub text: Text<'static>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl RunDiff {
+    pub fn new(previous: &[String], current: &[String]) -> Self {
+        let ops = similar::capture_diff_slices_deadline(
+            Algorithm::Myers,
+            previous,
+            current,
+            Some(Instant::now() + DIFF_DEADLINE),
+        );
+        let mut lines = Vec::new();
+        let (mut added, mut removed) = (0, 0);
+        for op in ops {
+            let (tag, previous_range, current_range) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => {
+                    lines.extend(
+                        current[current_range]
+                            .iter()
+                            .map(|line| Line::from(format!("  {}", ansi::strip(line)))),
+                    );
+                }
+                DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                    removed += previous_range.len();
+                    added += current_range.len();
+                    lines.extend(
+                        previous[previous_range]
+                            .iter()
+                            .map(|line| Line::from(format!("- {}", ansi::strip(line)).red())),
+                    );
+                    lines.extend(
+                        current[current_range]
+                            .iter()
+                            .map(|line| Line::from(format!("+ {}", ansi::strip(line)).green())),
+                    );
+                }
+            }
+        }
+        Self {
+            text: Text::from(lines),
+            added,
+            removed,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Stdio;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DirenvError {
+    #[error("Failed to read {0}: {1}")]
+    Read(String, std::io::Error),
+    #[error("Failed to run direnv for {0}, is it installed? {1}")]
+    Spawn(String, std::io::Error),
+    #[error("direnv failed for {0}, run `direnv allow` there: {1}")]
+    Failed(String, String),
+    #[error("Unexpected output of `direnv export json`: {0}")]
+    Output(#[from] serde_json::Error),
+}
+
+/// The environment a shell gets in `dir`: the variables of its `.env` and then the ones exported
+/// by its `.envrc` with direnv, limited to the names which match one of `allow`. A name ending
+/// with `*` allows every variable starting with the rest, `*` allows all.
+pub fn load(dir: &Path, allow: &[String]) -> Result<BTreeMap<String, String>, DirenvError> {
+    let mut env = BTreeMap::new();
+    let dotenv = dir.join(".env");
+    if dotenv.is_file() {
+        let content = std::fs::read_to_string(&dotenv)
+            .map_err(|e| DirenvError::Read(dotenv.display().to_string(), e))?;
+        env.extend(parse_dotenv(&content));
+    }
+    if dir.join(".envrc").is_file() {
+        env.extend(export(dir)?);
+    }
+    env.retain(|name, _| allow.iter().any(|pattern| matches(pattern, name)));
+    Ok(env)
+}
+
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
+/// The variables `.envrc` in `dir` sets, variables it unsets are left out.
+fn export(dir: &Path) -> Result<BTreeMap<String, String>, DirenvError> {
+    let output = std::process::Command::new("direnv")
+        .args(["export", "json"])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| DirenvError::Spawn(dir.display().to_string(), e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(DirenvError::Failed(
+            dir.display().to_string(),
+            stderr.trim().to_string(),
+        ));
+    }
+    if output.stdout.iter().all(u8::is_ascii_whitespace) {
+        return Ok(BTreeMap::new());
+    }
+    let exported: BTreeMap<String, Option<String>> = serde_json::from_slice(&output.stdout)?;
+    Ok(exported
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect())
+}
+
+/// Parses `NAME=value` lines, optionally prefixed with `export`, with `#` comments and values in
+/// single (taken as is) or double quotes.
+fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = if let Some(quoted) = value.strip_prefix('\'') {
+                quoted.strip_suffix('\'').unwrap_or(quoted).to_string()
+            } else if let Some(quoted) = value.strip_prefix('"') {
+                quoted
+                    .strip_suffix('"')
+                    .unwrap_or(quoted)
+                    .replace("\\n", "\n")
+                    .replace("\\\"", "\"")
+            } else {
+                match value.split_once(" #") {
+                    Some((value, _)) => value.trim_end().to_string(),
+                    None => value.to_string(),
+                }
+            };
+            Some((name.trim().to_string(), value))
+        })
+        .collect()
+}
@@ -0,0 +1,39 @@
+pub mod plain;
+pub mod terminal;
+
+use crate::app::App;
+use crate::message::MessageSender;
+use ratatui::crossterm::event;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+/// Shows the state of the [`App`] and feeds the input of the user back into it, so that other
+/// ways to present the tasks share the state machine of the app instead of forking it. The
+/// ratatui UI is [`terminal::TerminalFrontend`], the one for screen readers
+/// [`plain::PlainFrontend`].
+pub trait Frontend {
+    /// Starts delivering the input of the user to the app as event chunks through
+    /// `message_sender`, e.g. from a background task.
+    fn handle_input(&mut self, message_sender: MessageSender);
+
+    /// Shows the current state of the app, called whenever it may have changed.
+    fn render(&mut self, app: &mut App) -> color_eyre::Result<()>;
+}
+
+/// Reads the events of the terminal in a background task and sends them to the app in chunks,
+/// the input of both frontends.
+pub fn read_terminal_input(message_sender: MessageSender) {
+    let supervisor = message_sender.supervisor().clone();
+    supervisor.spawn(async move {
+        let event_stream = event::EventStream::new().chunks_timeout(100, Duration::from_millis(2));
+        tokio::pin!(event_stream);
+        while let Some(maybe_event) = event_stream.next().await {
+            let events = maybe_event
+                .into_iter()
+                .flatten()
+                .map(Into::into)
+                .collect::<Vec<_>>();
+            message_sender.send_event_chunk(events);
+        }
+    });
+}
@@ -0,0 +1,105 @@
+use crate::app::App;
+use crate::frontend::{self, Frontend};
+use crate::i18n::Phrase;
+use crate::message::MessageSender;
+use crate::task_state::TaskState;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Prints what happens as lines of plain text instead of drawing the screen, for screen readers
+/// which read out what is appended to the terminal: the output of each task labeled with its
+/// name, its state when it changes, the focused task, notices and the question, prompt or view
+/// which is open. The keys are the ones of the full screen UI.
+pub struct PlainFrontend {
+    states: HashMap<String, TaskState>,
+    focused: Option<String>,
+    notice: Option<String>,
+    overlay: Option<String>,
+}
+
+impl PlainFrontend {
+    pub fn new() -> Self {
+        PlainFrontend {
+            states: HashMap::new(),
+            focused: None,
+            notice: None,
+            overlay: None,
+        }
+    }
+
+    /// The lines which tell what changed since the last call.
+    fn changes(&mut self, app: &mut App) -> color_eyre::Result<Vec<String>> {
+        let lang = app.lang();
+        let mut lines = Vec::new();
+        // the output is still wrapped to the terminal, which the search and scroll keys go by
+        let width = crossterm::terminal::size().map_or(80, |(width, _)| width as usize);
+        for console in app.consoles_mut() {
+            console.fit_width(width);
+            let label = console.task().key().to_string();
+            lines.extend(
+                console
+                    .take_transcript()
+                    .into_iter()
+                    .map(|line| format!("{}: {}", label, line)),
+            );
+            let state = console.state();
+            if self.states.insert(label.clone(), state) != Some(state) {
+                lines.push(format!(
+                    "{} {} {}",
+                    label,
+                    lang.text(Phrase::IsNow),
+                    lang.state(state)
+                ));
+            }
+        }
+        let focused = app
+            .focused_console()
+            .map(|console| console.task().key().to_string());
+        if focused != self.focused {
+            if let Some(label) = focused.as_ref() {
+                lines.push(format!("{} {}", lang.text(Phrase::Focused), label));
+            }
+            self.focused = focused;
+        }
+        let notice = app.notice().map(str::to_string);
+        if notice != self.notice {
+            if let Some(notice) = notice.as_ref() {
+                lines.push(format!("parallely: {}", notice));
+            }
+            self.notice = notice;
+        }
+        let overlay = app.describe_overlay();
+        if overlay != self.overlay {
+            match overlay.as_ref() {
+                Some(overlay) => lines.extend(overlay.lines().map(str::to_string)),
+                None if self.overlay.is_some() => lines.push("Closed".to_string()),
+                None => {}
+            }
+            self.overlay = overlay;
+        }
+        Ok(lines)
+    }
+}
+
+impl Frontend for PlainFrontend {
+    fn handle_input(&mut self, message_sender: MessageSender) {
+        frontend::read_terminal_input(message_sender);
+    }
+
+    fn render(&mut self, app: &mut App) -> color_eyre::Result<()> {
+        if app.take_snapshot().is_some() {
+            app.notify("Snapshots need the full screen UI".to_string());
+        }
+        let lines = self.changes(app)?;
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let mut stdout = std::io::stdout().lock();
+        for line in lines {
+            // the terminal is in raw mode, which does not return the carriage on a line feed
+            write!(stdout, "{}\r\n", line)?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+}
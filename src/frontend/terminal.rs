@@ -0,0 +1,85 @@
+use crate::app::App;
+use crate::context::Context;
+use crate::frontend::{self, Frontend};
+use crate::message::MessageSender;
+use crate::snapshot;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+use ratatui::DefaultTerminal;
+
+/// The full screen UI drawn with ratatui, taking input from the terminal.
+pub struct TerminalFrontend {
+    terminal: DefaultTerminal,
+    context: Context,
+    high_contrast: bool,
+}
+
+impl TerminalFrontend {
+    pub fn new(terminal: DefaultTerminal) -> Self {
+        TerminalFrontend {
+            terminal,
+            context: Context::default(),
+            high_contrast: false,
+        }
+    }
+
+    /// Draws with bright colors on black and without dimmed text, see
+    /// [`Parallely::high_contrast`](crate::parallely::Parallely::high_contrast).
+    pub fn with_high_contrast(mut self, high_contrast: bool) -> Self {
+        self.high_contrast = high_contrast;
+        self
+    }
+}
+
+impl Frontend for TerminalFrontend {
+    fn handle_input(&mut self, message_sender: MessageSender) {
+        frontend::read_terminal_input(message_sender);
+    }
+
+    fn render(&mut self, app: &mut App) -> color_eyre::Result<()> {
+        let context = &mut self.context;
+        let high_contrast = self.high_contrast;
+        let frame = self.terminal.draw(|frame| {
+            frame.render_stateful_widget(&mut *app, frame.area(), context);
+            if high_contrast {
+                raise_contrast(frame.buffer_mut());
+            }
+        })?;
+        if let Some(format) = app.take_snapshot() {
+            match snapshot::write(frame.buffer, format) {
+                Ok(path) => app.notify(format!("Snapshot saved to {}", path.display())),
+                Err(e) => app.notify(format!("Failed to save snapshot: {}", e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the dim and dark colors the widgets are drawn with by bright ones on black, for
+/// people who cannot make them out. The colors of the output of the tasks are raised as well.
+fn raise_contrast(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        let fg = match cell.fg {
+            Color::Reset | Color::Gray | Color::DarkGray => Color::White,
+            Color::Black => Color::Black,
+            Color::Red => Color::LightRed,
+            Color::Green => Color::LightGreen,
+            Color::Yellow => Color::LightYellow,
+            Color::Blue | Color::Cyan => Color::LightCyan,
+            Color::Magenta => Color::LightMagenta,
+            color => color,
+        };
+        let bg = match cell.bg {
+            Color::Reset => Color::Black,
+            Color::DarkGray | Color::Gray => Color::White,
+            color => color,
+        };
+        // text on a background which became white must stay readable
+        let fg = match (bg, fg) {
+            (Color::White, Color::White) => Color::Black,
+            (_, fg) => fg,
+        };
+        cell.set_fg(fg).set_bg(bg);
+        cell.modifier.remove(Modifier::DIM);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::parallely::Parallely;
+use crate::report::TaskReport;
+use clap::Parser;
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// How many sessions are kept in the history file.
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("There is no session {0} in the history, run `parallely history` to list them")]
+    Unknown(usize),
+}
+
+/// One invocation of parallely: the arguments it was started with and how its tasks ended.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub started_ms: u64,
+    /// The working directory, which relative paths in the arguments are resolved against.
+    pub cwd: PathBuf,
+    /// The arguments without the program name, commands typed in at startup are added as
+    /// COMMANDS.
+    pub args: Vec<String>,
+    pub tasks: Vec<TaskOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub command: String,
+    pub status: String,
+    pub failed: bool,
+}
+
+impl HistoryEntry {
+    pub fn new(started_at: SystemTime, args: Vec<String>, reports: &[TaskReport]) -> Self {
+        let tasks = reports
+            .iter()
+            .map(|report| TaskOutcome {
+                command: report.command.clone(),
+                status: match &report.status {
+                    Ok(status) => status.to_string(),
+                    Err(error) => error.to_string(),
+                },
+                failed: report
+                    .status
+                    .as_ref()
+                    .map_or(true, |status| status.is_failed()),
+            })
+            .collect();
+        Self {
+            started_ms: started_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            cwd: std::env::current_dir().unwrap_or_default(),
+            args,
+            tasks,
+        }
+    }
+
+    /// Parses the arguments again and changes into the directory of the session, so that it
+    /// runs as it did back then.
+    pub fn restore(&self) -> color_eyre::Result<Parallely> {
+        std::env::set_current_dir(&self.cwd)
+            .wrap_err_with(|| format!("Failed to change into {}", self.cwd.display()))?;
+        let args = std::iter::once("parallely".to_string()).chain(self.args.iter().cloned());
+        Ok(Parallely::try_parse_from(args)?)
+    }
+
+    /// The arguments as they would be typed into a shell.
+    pub fn command_line(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| {
+                if arg.is_empty() || arg.contains(char::is_whitespace) {
+                    format!("{:?}", arg)
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// How long ago the session was started, e.g. `5m ago`.
+    pub fn age(&self) -> String {
+        let started_at = UNIX_EPOCH + Duration::from_millis(self.started_ms);
+        let seconds = SystemTime::now()
+            .duration_since(started_at)
+            .unwrap_or_default()
+            .as_secs();
+        match seconds {
+            0..60 => "just now".to_string(),
+            60..3600 => format!("{}m ago", seconds / 60),
+            3600..86400 => format!("{}h ago", seconds / 3600),
+            _ => format!("{}d ago", seconds / 86400),
+        }
+    }
+
+    fn outcome(&self) -> String {
+        let failed = self.tasks.iter().filter(|task| task.failed).count();
+        format!("{} ok, {} failed", self.tasks.len() - failed, failed)
+    }
+}
+
+impl Display for HistoryEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>9}  parallely {}  ({})",
+            self.age(),
+            self.command_line(),
+            self.outcome()
+        )
+    }
+}
+
+/// `history.jsonl` in the data directory of parallely, one session per line.
+pub fn path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let data_dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        });
+    data_dir.map(|dir| dir.join("parallely").join("history.jsonl"))
+}
+
+/// The sessions from the most recent to the oldest, lines which cannot be parsed are skipped.
+pub fn load() -> color_eyre::Result<Vec<HistoryEntry>> {
+    let Some(path) = path().filter(|path| path.is_file()) else {
+        return Ok(Vec::new());
+    };
+    let content = std::fs::read_to_string(&path)
+        .wrap_err_with(|| format!("Failed to read history {}", path.display()))?;
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// The session with the 1-based number of `parallely history`.
+pub fn get(number: usize) -> color_eyre::Result<HistoryEntry> {
+    let entries = load()?;
+    let entry = number
+        .checked_sub(1)
+        .and_then(|index| entries.into_iter().nth(index));
+    Ok(entry.ok_or(HistoryError::Unknown(number))?)
+}
+
+/// Adds the session to the end of the history, dropping the oldest ones beyond [`MAX_ENTRIES`].
+/// Sessions ending at the same time take turns, see [`lock`].
+pub fn append(entry: &HistoryEntry) -> color_eyre::Result<()> {
+    let Some(path) = path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _lock = lock(&path)?;
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .wrap_err_with(|| format!("Failed to write history {}", path.display()))?;
+    trim(&path).wrap_err_with(|| format!("Failed to write history {}", path.display()))
+}
+
+/// Locks `history.jsonl.lock` next to the history until the file returned is dropped. The
+/// history itself cannot be locked, since [`trim`] replaces it.
+fn lock(path: &Path) -> color_eyre::Result<File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    file.lock()
+        .wrap_err_with(|| format!("Failed to lock history {}", path.display()))?;
+    Ok(file)
+}
+
+/// Drops the oldest sessions once there are more than [`MAX_ENTRIES`], by writing the rest into
+/// a temporary file which is renamed over the history, so that it is never left half written.
+fn trim(path: &Path) -> std::io::Result<()> {
+    let content = std::fs::read_to_string(path)?;
+    let count = content.lines().count();
+    if count <= MAX_ENTRIES {
+        return Ok(());
+    }
+    let mut kept = String::new();
+    for line in content.lines().skip(count - MAX_ENTRIES) {
+        kept.push_str(line);
+        kept.push('\n');
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    std::fs::write(&temporary, kept)?;
+    std::fs::rename(&temporary, path)
+}
+
+/// Prints the sessions for `parallely history`.
+pub fn print() -> color_eyre::Result<()> {
+    let entries = load()?;
+    if entries.is_empty() {
+        println!("No sessions yet");
+        return Ok(());
+    }
+    let width = entries.len().to_string().len();
+    for (index, entry) in entries.iter().enumerate() {
+        println!("{:>width$}  {}  {}", index + 1, entry, entry.cwd.display());
+    }
+    println!("\nRun one again with `parallely rerun <N>`");
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use crate::task_state::TaskState;
+use clap::ValueEnum;
+
+/// The language of the UI. Notices, prompts and the output of the subcommands are in English.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    /// Simplified Chinese.
+    Zh,
+}
+
+/// The texts of the UI which are translated, see [`Lang::text`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Phrase {
+    Focus,
+    Hide,
+    Unhide,
+    Snapshot,
+    Quit,
+    Paste,
+    Leave,
+    Close,
+    Frozen,
+    ReadOnly,
+    Typing,
+    CommandPid,
+    WaitingFor,
+    Paused,
+    Muted,
+    Counters,
+    Lines,
+    Dropped,
+    CrashLooping,
+    Marked,
+    Output,
+    Diff,
+    DiffVsPrevious,
+    Line,
+    StartupTimeline,
+    Booting,
+    ReadyRunning,
+    Waiting,
+    NotStarted,
+    ReadyAfter,
+    Ran,
+    Failed,
+    Focused,
+    IsNow,
+    Running,
+    Done,
+    Restart,
+    Stop,
+    Unmark,
+    Submit,
+    Zoomed,
+    EndInput,
+}
+
+impl Lang {
+    /// The language of the locale set in `LC_ALL`, `LC_MESSAGES` or `LANG`, the first of them
+    /// which is set, English unless it is Chinese.
+    pub fn detect() -> Lang {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|locale| !locale.is_empty());
+        match locale {
+            Some(locale) if locale.to_ascii_lowercase().starts_with("zh") => Lang::Zh,
+            _ => Lang::En,
+        }
+    }
+
+    pub fn text(self, phrase: Phrase) -> &'static str {
+        match self {
+            Lang::En => match phrase {
+                Phrase::Focus => "Focus",
+                Phrase::Hide => "Hide",
+                Phrase::Unhide => "Unhide",
+                Phrase::Snapshot => "Snapshot",
+                Phrase::Quit => "Quit",
+                Phrase::Paste => "Paste",
+                Phrase::Leave => "Leave",
+                Phrase::Close => "Close",
+                Phrase::Frozen => "❄ frozen, <F> to resume",
+                Phrase::ReadOnly => "read-only",
+                Phrase::Typing => "⌨ typing into the focused console",
+                Phrase::CommandPid => "Command - PID",
+                Phrase::WaitingFor => "waiting for",
+                Phrase::Paused => "paused",
+                Phrase::Muted => "muted",
+                Phrase::Counters => "counters",
+                Phrase::Lines => "lines",
+                Phrase::Dropped => "dropped",
+                Phrase::CrashLooping => "crash-looping",
+                Phrase::Marked => "marked",
+                Phrase::Output => "output",
+                Phrase::Diff => "diff",
+                Phrase::DiffVsPrevious => "diff vs previous run",
+                Phrase::Line => "line",
+                Phrase::StartupTimeline => "Startup timeline",
+                Phrase::Booting => "booting",
+                Phrase::ReadyRunning => "ready/running",
+                Phrase::Waiting => "waiting",
+                Phrase::NotStarted => "not started",
+                Phrase::ReadyAfter => "ready",
+                Phrase::Ran => "ran",
+                Phrase::Failed => "failed",
+                Phrase::Focused => "Focused",
+                Phrase::IsNow => "is now",
+                Phrase::Running => "running",
+                Phrase::Done => "done",
+                Phrase::Restart => "Restart",
+                Phrase::Stop => "Stop",
+                Phrase::Unmark => "Unmark",
+                Phrase::Submit => "Submit",
+                Phrase::Zoomed => "zoomed, <Z> to show all",
+                Phrase::EndInput => "End input",
+            },
+            Lang::Zh => match phrase {
+                Phrase::Focus => "切换焦点",
+                Phrase::Hide => "隐藏",
+                Phrase::Unhide => "取消隐藏",
+                Phrase::Snapshot => "截图",
+                Phrase::Quit => "退出",
+                Phrase::Paste => "粘贴",
+                Phrase::Leave => "离开",
+                Phrase::Close => "关闭",
+                Phrase::Frozen => "❄ 已冻结，按 <F> 继续",
+                Phrase::ReadOnly => "只读",
+                Phrase::Typing => "⌨ 正在向当前终端输入",
+                Phrase::CommandPid => "命令 - PID",
+                Phrase::WaitingFor => "等待",
+                Phrase::Paused => "已暂停",
+                Phrase::Muted => "已静音",
+                Phrase::Counters => "计数",
+                Phrase::Lines => "行",
+                Phrase::Dropped => "已丢弃",
+                Phrase::CrashLooping => "反复崩溃",
+                Phrase::Marked => "已标记",
+                Phrase::Output => "输出",
+                Phrase::Diff => "差异",
+                Phrase::DiffVsPrevious => "与上次运行的差异",
+                Phrase::Line => "行",
+                Phrase::StartupTimeline => "启动时间线",
+                Phrase::Booting => "启动中",
+                Phrase::ReadyRunning => "就绪/运行中",
+                Phrase::Waiting => "等待中",
+                Phrase::NotStarted => "未启动",
+                Phrase::ReadyAfter => "就绪",
+                Phrase::Ran => "运行了",
+                Phrase::Failed => "失败",
+                Phrase::Focused => "焦点",
+                Phrase::IsNow => "现在",
+                Phrase::Running => "运行中",
+                Phrase::Done => "已完成",
+                Phrase::Restart => "重启",
+                Phrase::Stop => "停止",
+                Phrase::Unmark => "取消标记",
+                Phrase::Submit => "提交",
+                Phrase::Zoomed => "已放大，按 <Z> 显示全部",
+                Phrase::EndInput => "结束输入",
+            },
+        }
+    }
+
+    /// The name of the state, the English one is its [`Display`](std::fmt::Display).
+    pub fn state(self, state: TaskState) -> String {
+        match self {
+            Lang::En => state.to_string(),
+            Lang::Zh => match state {
+                TaskState::Pending => "等待中",
+                TaskState::Starting => "启动中",
+                TaskState::Running => "运行中",
+                TaskState::Ready => "就绪",
+                TaskState::Stopping => "停止中",
+                TaskState::Exited => "已退出",
+                TaskState::Killed => "已被终止",
+                TaskState::Failed => "失败",
+                TaskState::TimedOut => "超时",
+            }
+            .to_string(),
+        }
+    }
+}
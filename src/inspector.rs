@@ -0,0 +1,290 @@
+use crate::event::ParallelyEvent;
+use crate::process_tree;
+use crate::resolve::{self, ResolvedCommand};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Clear, Paragraph, Widget};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+/// Parts of variable names whose values are masked, compared case-insensitively.
+const SECRET_NAMES: [&str; 8] = [
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "KEY",
+    "CREDENTIAL",
+    "AUTH",
+    "PRIVATE",
+];
+
+/// A process started by a task, see [`Inspector`].
+struct Descendant {
+    /// How many parents it has below the task.
+    depth: usize,
+    pid: u32,
+    command: String,
+}
+
+/// A popup showing what the focused task runs with: its command as it is spawned, the executable
+/// it resolves to, working directory, environment (with the values of secrets masked), PID, process group and the
+/// processes it started. The environment is the one of the running process where it can be
+/// read, else the one the task is started with.
+pub struct Inspector {
+    label: String,
+    program: Vec<String>,
+    resolved: ResolvedCommand,
+    cwd: Option<PathBuf>,
+    env: BTreeMap<String, String>,
+    /// Whether `env` was read from the running process.
+    live_env: bool,
+    pid: Option<u32>,
+    process_group: Option<i32>,
+    descendants: Vec<Descendant>,
+    scroll: u16,
+}
+
+pub enum InspectorAction {
+    None,
+    Close,
+}
+
+impl Inspector {
+    /// Inspects the task which is spawned with `command`, and runs as `pid` if it is running.
+    pub fn new(label: impl Into<String>, command: &Command, pid: Option<u32>) -> Self {
+        let program = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let mut cwd = command
+            .get_current_dir()
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok());
+        let mut env = std::env::vars().collect::<BTreeMap<_, _>>();
+        for (name, value) in command.get_envs() {
+            let name = name.to_string_lossy().into_owned();
+            match value {
+                Some(value) => env.insert(name, value.to_string_lossy().into_owned()),
+                None => env.remove(&name),
+            };
+        }
+        let mut live_env = false;
+        let mut descendants = Vec::new();
+        if let Some(pid) = pid {
+            let mut system = System::new();
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing()
+                    .with_cmd(UpdateKind::Always)
+                    .with_cwd(UpdateKind::Always)
+                    .with_environ(UpdateKind::Always),
+            );
+            if let Some(process) = system.process(Pid::from_u32(pid)) {
+                if !process.environ().is_empty() {
+                    env = process
+                        .environ()
+                        .iter()
+                        .filter_map(|variable| {
+                            let (name, value) = variable.to_str()?.split_once('=')?;
+                            Some((name.to_string(), value.to_string()))
+                        })
+                        .collect();
+                    live_env = true;
+                }
+                if let Some(dir) = process.cwd() {
+                    cwd = Some(dir.to_path_buf());
+                }
+            }
+            descendants = process_tree::descendants(&system, Pid::from_u32(pid))
+                .into_iter()
+                .map(|(depth, process)| Descendant {
+                    depth,
+                    pid: process.pid().as_u32(),
+                    command: process_tree::command_line(process),
+                })
+                .collect();
+        }
+        Inspector {
+            label: label.into(),
+            program,
+            resolved: resolve::resolve(command),
+            cwd,
+            env,
+            live_env,
+            pid,
+            process_group: pid.and_then(process_group),
+            descendants,
+            scroll: 0,
+        }
+    }
+
+    /// `Up`/`Down` and `PgUp`/`PgDn` scroll, `Esc`, `q` or `I` close. Consumes every key event
+    /// without a `Control` modifier, so that global shortcuts such as `Ctrl+C` keep working.
+    pub fn handle_event(&mut self, event: &mut ParallelyEvent) -> InspectorAction {
+        if let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                return InspectorAction::None;
+            }
+            let action = match code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.scroll = self.scroll.saturating_sub(1);
+                    InspectorAction::None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.scroll = self.scroll.saturating_add(1);
+                    InspectorAction::None
+                }
+                KeyCode::PageUp => {
+                    self.scroll = self.scroll.saturating_sub(10);
+                    InspectorAction::None
+                }
+                KeyCode::PageDown => {
+                    self.scroll = self.scroll.saturating_add(10);
+                    InspectorAction::None
+                }
+                KeyCode::Esc | KeyCode::Char('q' | 'I') => InspectorAction::Close,
+                _ => InspectorAction::None,
+            };
+            event.stop_propagation();
+            return action;
+        }
+        InspectorAction::None
+    }
+
+    /// Everything shown as plain text, for the screen reader frontend.
+    pub fn describe(&self) -> String {
+        self.lines()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let field = |name: &str, value: String| {
+            Line::from(vec![format!("{:<14}", name).magenta().bold(), value.into()])
+        };
+        let mut lines = vec![
+            field("command", process_tree::shell_words(&self.program)),
+            field("executable", self.resolved.to_string()),
+            field(
+                "cwd",
+                self.cwd
+                    .as_ref()
+                    .map_or_else(|| "?".to_string(), |cwd| cwd.display().to_string()),
+            ),
+            field(
+                "pid",
+                self.pid
+                    .map_or_else(|| "not running".to_string(), |pid| pid.to_string()),
+            ),
+        ];
+        if let Some(group) = self.process_group {
+            lines.push(field("process group", group.to_string()));
+        }
+        lines.push(Line::default());
+        lines.push(Line::from(
+            format!("processes ({})", self.descendants.len() + 1)
+                .magenta()
+                .bold(),
+        ));
+        if let Some(pid) = self.pid {
+            lines.push(Line::from(vec![
+                format!("{:>8} ", pid).dark_gray(),
+                self.program.first().cloned().unwrap_or_default().into(),
+            ]));
+        }
+        for descendant in self.descendants.iter() {
+            lines.push(Line::from(vec![
+                format!("{:>8} ", descendant.pid).dark_gray(),
+                Span::raw(format!(
+                    "{}└ {}",
+                    "  ".repeat(descendant.depth),
+                    descendant.command
+                )),
+            ]));
+        }
+        lines.push(Line::default());
+        let source = match self.live_env {
+            true => "of the running process",
+            false => "it is started with",
+        };
+        lines.push(Line::from(vec![
+            format!("environment ({}) ", self.env.len())
+                .magenta()
+                .bold(),
+            source.dark_gray(),
+        ]));
+        for (name, value) in self.env.iter() {
+            let value = match is_secret(name) {
+                true => "••••••".dark_gray(),
+                false => Span::raw(value.clone()),
+            };
+            lines.push(Line::from(vec![format!("{}=", name).blue(), value]));
+        }
+        lines
+    }
+}
+
+impl Widget for &mut Inspector {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let [popup_area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::bordered()
+            .title(format!(" Inspect {} ", self.label).magenta().bold())
+            .title_bottom(Line::from(vec![
+                " Scroll ".into(),
+                "<Up/Down> ".blue().bold(),
+                " Close ".into(),
+                "<Esc> ".blue().bold(),
+            ]))
+            .border_type(BorderType::Rounded);
+        let lines = self.lines();
+        let height = block.inner(popup_area).height;
+        let max_scroll = (lines.len() as u16).saturating_sub(height);
+        self.scroll = self.scroll.min(max_scroll);
+
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((self.scroll, 0))
+            .render(popup_area, buf);
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_NAMES.iter().any(|part| name.contains(part))
+}
+
+#[cfg(unix)]
+fn process_group(pid: u32) -> Option<i32> {
+    let group = unsafe { libc::getpgid(pid as libc::pid_t) };
+    (group >= 0).then_some(group)
+}
+
+#[cfg(not(unix))]
+fn process_group(_pid: u32) -> Option<i32> {
+    None
+}
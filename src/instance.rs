@@ -0,0 +1,215 @@
+use clap::ValueEnum;
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Set for the tasks of a session running in its own namespace, see [`InstanceMode::Namespace`].
+pub const INSTANCE_VAR: &str = "PARALLELY_INSTANCE";
+
+/// How many times the lock file of a running session is read until it has been written.
+const READ_ATTEMPTS: usize = 20;
+const READ_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What happens when parallely is started for a project which already has a running session,
+/// e.g. so that the dev servers are not started twice.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InstanceMode {
+    /// Start another session, nothing is checked.
+    #[default]
+    Allow,
+    /// Exit with an error naming the running session.
+    Error,
+    /// Print the state of the tasks of the running session from its `--status-file` and exit.
+    Attach,
+    /// Start another session with `PARALLELY_INSTANCE` set to its number, which also names the
+    /// service containers apart.
+    Namespace,
+}
+
+#[derive(Debug, Error)]
+pub enum InstanceError {
+    #[error("parallely is already running for {key} as PID {pid}, see `--instance`")]
+    Running { key: String, pid: u32 },
+    #[error("parallely is running for {key} as PID {pid} without `--status-file`, there is nothing to attach to")]
+    NoStatusFile { key: String, pid: u32 },
+    #[error("{0} is locked by a running session, but does not tell which one")]
+    Unreadable(PathBuf),
+}
+
+/// What the lock file of a running session contains.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunningInstance {
+    pub pid: u32,
+    /// The config file or preset the session was started for.
+    pub key: String,
+    pub status_file: Option<PathBuf>,
+}
+
+pub enum Acquired {
+    Lock(InstanceLock),
+    Running(RunningInstance),
+}
+
+/// Marks a session as running until it is dropped, by holding an exclusive lock on its lock file.
+/// The system releases the lock however the session ends, so a lock file left behind by a
+/// session which did not exit cleanly is simply locked again.
+pub struct InstanceLock {
+    path: PathBuf,
+    /// Held open for the lock, which is released once it is closed.
+    _file: File,
+    namespace: Option<usize>,
+}
+
+impl InstanceLock {
+    /// Locks the project identified by `key`. With `namespace`, a project which is already
+    /// locked gets the next free number instead of the running session being returned.
+    pub fn acquire(
+        key: &str,
+        namespace: bool,
+        status_file: Option<&Path>,
+    ) -> color_eyre::Result<Acquired> {
+        let dir = lock_dir();
+        std::fs::create_dir_all(&dir)
+            .wrap_err_with(|| format!("Failed to create directory {}", dir.display()))?;
+        let hash = fnv1a(key.as_bytes());
+        let info = RunningInstance {
+            pid: std::process::id(),
+            key: key.to_string(),
+            status_file: status_file.map(Path::to_path_buf),
+        };
+        let mut number = 1;
+        loop {
+            let path = match number {
+                1 => dir.join(format!("{:016x}.lock", hash)),
+                number => dir.join(format!("{:016x}-{}.lock", hash, number)),
+            };
+            let mut file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .wrap_err_with(|| format!("Failed to open {}", path.display()))?;
+            match file.try_lock() {
+                // the session which held the lock before may have removed the file meanwhile
+                Ok(()) if !is_linked(&file, &path) => continue,
+                Ok(()) => {
+                    file.set_len(0)
+                        .and_then(|()| file.write_all(serde_json::to_string(&info)?.as_bytes()))
+                        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+                    return Ok(Acquired::Lock(InstanceLock {
+                        path,
+                        _file: file,
+                        namespace: (number > 1).then_some(number),
+                    }));
+                }
+                Err(TryLockError::WouldBlock) if namespace => number += 1,
+                Err(TryLockError::WouldBlock) => {
+                    return Ok(Acquired::Running(read_running(&path)?))
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).wrap_err_with(|| format!("Failed to lock {}", path.display()))
+                }
+            }
+        }
+    }
+
+    /// The number of the session if another one of the project was already running.
+    pub fn namespace(&self) -> Option<usize> {
+        self.namespace
+    }
+}
+
+impl Drop for InstanceLock {
+    /// Removes the lock file while it is still locked, the lock is released once it is closed.
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+/// What the running session which holds the lock file wrote into it, which it may still be
+/// writing.
+fn read_running(path: &Path) -> color_eyre::Result<RunningInstance> {
+    for _ in 0..READ_ATTEMPTS {
+        let running = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        if let Some(running) = running {
+            return Ok(running);
+        }
+        std::thread::sleep(READ_INTERVAL);
+    }
+    Err(InstanceError::Unreadable(path.to_path_buf()).into())
+}
+
+/// Whether the open file is still the one at `path`.
+#[cfg(unix)]
+fn is_linked(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(linked)) => (open.dev(), open.ino()) == (linked.dev(), linked.ino()),
+        _ => false,
+    }
+}
+
+/// A file which is open cannot be replaced on Windows, only removed once it is closed.
+#[cfg(not(unix))]
+fn is_linked(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+/// The 64-bit FNV-1a hash, which names files such as the lock file of a key the same in every
+/// version of parallely, unlike the hasher of the standard library.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Prints the state and the last lines of output of every task of the running session.
+pub fn attach(running: &RunningInstance) -> color_eyre::Result<()> {
+    let Some(path) = running.status_file.as_ref() else {
+        return Err(InstanceError::NoStatusFile {
+            key: running.key.clone(),
+            pid: running.pid,
+        }
+        .into());
+    };
+    let content = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read status file {}", path.display()))?;
+    let status: serde_json::Value = serde_json::from_str(&content)
+        .wrap_err_with(|| format!("Failed to parse status file {}", path.display()))?;
+    println!(
+        "parallely is running for {} as PID {}",
+        running.key, running.pid
+    );
+    let tasks = status["tasks"].as_array().cloned().unwrap_or_default();
+    for task in tasks {
+        let name = task["name"].as_str().unwrap_or_default();
+        let state = task["state"].as_str().unwrap_or_default();
+        match task["pid"].as_u64() {
+            Some(pid) => println!("\n{}  {} (PID {})", name, state, pid),
+            None => println!("\n{}  {}", name, state),
+        }
+        for line in task["last_lines"].as_array().into_iter().flatten() {
+            println!("  │ {}", line.as_str().unwrap_or_default());
+        }
+    }
+    Ok(())
+}
+
+/// The runtime directory of the user if there is one, as the locks do not survive a reboot.
+fn lock_dir() -> PathBuf {
+    #[cfg(not(windows))]
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    #[cfg(windows)]
+    let runtime_dir = None::<PathBuf>;
+    runtime_dir
+        .unwrap_or_else(std::env::temp_dir)
+        .join("parallely")
+}
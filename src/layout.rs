@@ -0,0 +1,35 @@
+use std::cmp::Reverse;
+
+/// The fewest cells a console is shrunk to by resizing, enough for its borders and a line.
+pub const MIN_SIZE: u16 = 3;
+
+/// The sizes of panes laid out side by side (or one above the other) after resizing some of
+/// them by `offsets` cells, e.g. with `Ctrl+→`. A pane grows by taking cells from the others,
+/// the largest first, and shrinks by giving cells to them, the smallest first, so that the
+/// total stays the same. Panes which are not resized themselves are preferred, and no pane
+/// gets smaller than [`MIN_SIZE`].
+pub fn resize(base: &[u16], offsets: &[i32]) -> Vec<u16> {
+    let mut sizes = base.to_vec();
+    for (index, offset) in offsets.iter().copied().enumerate() {
+        for _ in 0..offset.unsigned_abs() {
+            let others = (0..sizes.len()).filter(|&other| other != index);
+            let (from, to) = if offset > 0 {
+                let from = others
+                    .filter(|&other| sizes[other] > MIN_SIZE)
+                    .min_by_key(|&other| (offsets[other] != 0, Reverse(sizes[other])));
+                (from, Some(index))
+            } else if sizes[index] > MIN_SIZE {
+                let to = others.min_by_key(|&other| (offsets[other] != 0, sizes[other]));
+                (Some(index), to)
+            } else {
+                (None, None)
+            };
+            let (Some(from), Some(to)) = (from, to) else {
+                break;
+            };
+            sizes[from] -= 1;
+            sizes[to] += 1;
+        }
+    }
+    sizes
+}
@@ -0,0 +1,82 @@
+use clap::ValueEnum;
+use std::io;
+use thiserror::Error;
+
+/// Where `--forward-logs` sends the output of the tasks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ForwardTarget {
+    /// The local syslog daemon via `/dev/log`.
+    Syslog,
+    /// The systemd journal via its native protocol.
+    Journald,
+}
+
+#[derive(Debug, Error)]
+pub enum ForwardError {
+    #[error("Forwarding logs to {0:?} is only supported on unix")]
+    Unsupported(ForwardTarget),
+    #[error("Failed to connect to {0:?} at {1}: {2}")]
+    Connect(ForwardTarget, &'static str, io::Error),
+}
+
+/// Sends every line of output as a separate log entry, identified by the task.
+pub struct LogForwarder {
+    target: ForwardTarget,
+    #[cfg(unix)]
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+impl LogForwarder {
+    #[cfg(unix)]
+    pub fn connect(target: ForwardTarget) -> Result<Self, ForwardError> {
+        let paths: &[&'static str] = match target {
+            ForwardTarget::Syslog => &["/dev/log", "/var/run/syslog"],
+            ForwardTarget::Journald => &["/run/systemd/journal/socket"],
+        };
+        let socket = std::os::unix::net::UnixDatagram::unbound()
+            .map_err(|e| ForwardError::Connect(target, paths[0], e))?;
+        let mut result = Ok(());
+        for path in paths {
+            result = socket
+                .connect(path)
+                .map_err(|e| ForwardError::Connect(target, path, e));
+            if result.is_ok() {
+                break;
+            }
+        }
+        result.map(|_| Self { target, socket })
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect(target: ForwardTarget) -> Result<Self, ForwardError> {
+        Err(ForwardError::Unsupported(target))
+    }
+
+    /// Sends a line with the user facility and informational severity.
+    #[cfg(unix)]
+    pub fn send(&self, identifier: &str, pid: Option<u32>, line: &str) -> io::Result<()> {
+        let message = match self.target {
+            ForwardTarget::Syslog => match pid {
+                Some(pid) => format!("<14>{}[{}]: {}", identifier, pid, line),
+                None => format!("<14>{}: {}", identifier, line),
+            },
+            ForwardTarget::Journald => {
+                let mut message = format!(
+                    "PRIORITY=6\nSYSLOG_FACILITY=1\nSYSLOG_IDENTIFIER={}\nMESSAGE={}\n",
+                    identifier, line
+                );
+                if let Some(pid) = pid {
+                    message.push_str(&format!("SYSLOG_PID={}\n", pid));
+                }
+                message
+            }
+        };
+        self.socket.send(message.as_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn send(&self, _identifier: &str, _pid: Option<u32>, _line: &str) -> io::Result<()> {
+        Err(io::Error::other(ForwardError::Unsupported(self.target)))
+    }
+}
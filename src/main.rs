@@ -1,26 +1,83 @@
+mod ansi;
 pub mod app;
+mod bell;
+mod bench;
+mod builtin;
+mod config;
+mod confirm;
 pub mod console;
 mod context;
+mod counters;
+mod crash;
+mod diff;
+mod direnv;
 mod event;
+mod frontend;
+mod history;
+mod i18n;
+mod inspector;
+mod instance;
+mod layout;
+mod log_forwarder;
 mod message;
+mod multiplexer;
 mod parallely;
+mod perf;
+mod picker;
+mod plugin;
+mod probe;
+mod process_tree;
+mod project_path;
+mod prompt;
+mod report;
+mod resolve;
+mod selection;
 mod shutdown_handler;
+mod snapshot;
+mod startup;
+mod status_bar;
+mod status_file;
+mod summary;
+mod supervisor;
+mod task_events;
 mod task_executor;
+mod task_log;
+mod task_state;
+mod test_summary;
+mod timeline;
+mod timestamp;
+mod up;
+mod watchdog;
+mod watcher;
 
 use crate::app::App;
-use crate::parallely::Parallely;
-use clap::Parser;
+use crate::builtin::Builtin;
+use crate::config::TaskConfig;
+use crate::frontend::plain::PlainFrontend;
+use crate::frontend::terminal::TerminalFrontend;
+use crate::history::HistoryEntry;
+use crate::instance::{Acquired, InstanceError, InstanceLock, InstanceMode};
+use crate::parallely::{Parallely, ParallelySubcommand};
+use crate::shutdown_handler::ShutdownReason;
+use crate::startup::StartupChoice;
+use clap::{CommandFactory, Parser};
+use color_eyre::eyre::WrapErr;
 use color_eyre::Help;
+use futures::FutureExt;
 use ratatui::crossterm::ExecutableCommand;
+use std::io::IsTerminal;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
 use std::process::exit;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
-#[tokio::main]
-async fn main() -> color_eyre::Result<()> {
-    let parallely = match Parallely::try_parse() {
+/// Everything up to locking the instance runs before the runtime is started, so that the
+/// environment can still be changed safely, see [`lock_instance`].
+fn main() -> color_eyre::Result<()> {
+    let mut parallely = match Parallely::try_parse() {
         Ok(p) => p,
         Err(e) => {
             restore();
@@ -30,32 +87,319 @@ async fn main() -> color_eyre::Result<()> {
     };
 
     // self init
-    let _guard = try_init(&parallely)?;
+    let guard = try_init(&parallely)?;
+    // recorded in the history, so that the session can be run again
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    match parallely.subcommand.take() {
+        Some(ParallelySubcommand::Presets) => return config::preset::print(),
+        Some(ParallelySubcommand::History) => return history::print(),
+        Some(ParallelySubcommand::Bench {
+            runs,
+            parallel,
+            json,
+            command,
+        }) => return runtime()?.block_on(bench::run(&command, runs, parallel, json.as_deref())),
+        Some(ParallelySubcommand::Builtin { name, argument }) => {
+            let builtin = Builtin::parse(&name, &argument)?;
+            return runtime()?.block_on(builtin.run());
+        }
+        Some(ParallelySubcommand::Rerun { number }) => {
+            let entry = history::get(number)?;
+            eprintln!(
+                "Running parallely {} in {}",
+                entry.command_line(),
+                entry.cwd.display()
+            );
+            parallely = entry.restore()?;
+            args = entry.args;
+        }
+        Some(ParallelySubcommand::Up {
+            wait,
+            timeout,
+            forward_signals,
+            on_hangup,
+            events,
+            instance,
+            preset,
+            config,
+            params,
+            commands,
+        }) => {
+            parallely.preset = preset;
+            parallely.config = config;
+            parallely.params = params;
+            parallely.commands = commands;
+            parallely.instance = instance;
+            discover_config(&mut parallely)?;
+            parallely.prompt_params()?;
+            let lock = lock_instance(&parallely)?;
+            let tasks = parallely.tasks()?;
+            let result = runtime()?.block_on(up::run(
+                tasks,
+                wait,
+                timeout,
+                forward_signals,
+                on_hangup,
+                events,
+            ));
+            drop(lock);
+            return result;
+        }
+        None => {}
+    }
+    discover_config(&mut parallely)?;
+    for (name, value) in parallely.prompt_params()? {
+        args.push("--param".to_string());
+        args.push(format!("{}={}", name, value));
+    }
+    if parallely.dry_run {
+        config::print(&parallely.tasks()?);
+        return Ok(());
+    }
+    let lock = lock_instance(&parallely)?;
+    runtime()?.block_on(run(parallely, args, lock, guard))
+}
 
-    // ratatui init
-    let mut terminal = ratatui::try_init()?;
-    terminal.clear()?;
+fn runtime() -> std::io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+}
 
-    let mut app = App::new(parallely);
-    let result = app.run(terminal).await?;
+/// Runs the session, from picking the tasks if none are given to printing the summary.
+async fn run(
+    mut parallely: Parallely,
+    mut args: Vec<String>,
+    lock: Option<InstanceLock>,
+    _guard: Option<WorkerGuard>,
+) -> color_eyre::Result<()> {
+    let mut tasks = parallely.tasks()?;
+    if tasks.is_empty() {
+        if !std::io::stdin().is_terminal() {
+            Parallely::command()
+                .error(
+                    clap::error::ErrorKind::MissingRequiredArgument,
+                    "no COMMANDS given and the standard input is not a terminal",
+                )
+                .exit();
+        }
+        match startup::run().await? {
+            Some(StartupChoice::Tasks(startup_tasks)) => {
+                args.extend(startup_tasks.iter().map(|task| task.command.to_string()));
+                tasks = startup_tasks;
+            }
+            Some(StartupChoice::Rerun(entry)) => {
+                parallely = entry.restore()?;
+                args = entry.args;
+                tasks = discover_tasks(&mut parallely)?;
+            }
+            None => return Ok(()),
+        }
+    }
+
+    let many = tasks.len() >= selection::SELECT_THRESHOLD;
+    if many && !parallely.all && std::io::stdin().is_terminal() {
+        match selection::run(tasks, &parallely.instance_key()?).await? {
+            Some(selected) => tasks = selected,
+            None => return Ok(()),
+        }
+    }
 
-    // ratatui restore
-    ratatui::try_restore()
-        .with_suggestion(|| "Failed to restore terminal. Run [reset] to recover")?;
+    if let Some(multiplexer) = parallely.multiplexer {
+        return multiplexer::run(multiplexer, &tasks, parallely.exit_on_complete);
+    }
+    if let Some(dir) = parallely.log_dir.as_ref() {
+        std::fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("Failed to create log directory {}", dir.display()))?;
+    }
+    let timings = parallely.timings;
+    let color = parallely.color;
+    let tail_on_exit = parallely.tail_on_exit;
+    let timings_json = parallely.timings_json.clone();
+    let screen_reader = parallely.screen_reader;
+    let high_contrast = parallely.high_contrast;
+    let mouse = !parallely.no_mouse;
+    let mut app = App::new(parallely, tasks)?;
 
-    // self restore
-    try_restore()?;
+    let run = if screen_reader {
+        // the keys are read as they are pressed, the output is appended as plain lines
+        crossterm::terminal::enable_raw_mode()?;
+        let mut frontend = PlainFrontend::new();
+        AssertUnwindSafe(app.run(&mut frontend))
+            .catch_unwind()
+            .await
+    } else {
+        enable_capture(mouse)?;
 
-    for result in result.tasks_status {
-        match result {
-            Ok(task_status) => println!("{}", task_status),
-            Err(error) => eprintln!("{}", error),
+        // ratatui init
+        let mut terminal = ratatui::try_init()?;
+        terminal.clear()?;
+        let mut frontend = TerminalFrontend::new(terminal).with_high_contrast(high_contrast);
+        AssertUnwindSafe(app.run(&mut frontend))
+            .catch_unwind()
+            .await
+    };
+    let result = match run {
+        Ok(Ok(result)) => result,
+        Ok(Err(error)) => {
+            dump_crash(&mut app, &format!("{:?}", error)).await;
+            return Err(error);
+        }
+        Err(payload) => {
+            dump_crash(&mut app, &crash::panic_message(payload.as_ref())).await;
+            exit(101);
+        }
+    };
+
+    if let ShutdownReason::Hangup(_) = result.shutdown_reason {
+        // there is nothing to restore
+        shutdown_handler::discard_output();
+    } else if screen_reader {
+        crossterm::terminal::disable_raw_mode()
+            .with_suggestion(|| "Failed to restore terminal. Run [reset] to recover")?;
+    } else {
+        // ratatui restore
+        ratatui::try_restore()
+            .with_suggestion(|| "Failed to restore terminal. Run [reset] to recover")?;
+
+        // self restore
+        try_restore()?;
+    }
+
+    let cancelled_by = match result.shutdown_reason {
+        ShutdownReason::Failure(index) => result.tasks.get(index).map(|task| match &task.status {
+            Ok(status) => status.to_string(),
+            Err(error) => error.to_string(),
+        }),
+        _ => None,
+    };
+
+    match tail_on_exit {
+        Some(count) => {
+            summary::print(&result.tasks, color, 0);
+            summary::print_failed_output(&result.tasks, count);
         }
+        None => summary::print(&result.tasks, color, summary::FAILED_TAIL_LINES),
+    }
+
+    if let Err(e) = history::append(&HistoryEntry::new(result.started_at, args, &result.tasks)) {
+        eprintln!("Failed to update the history: {:#}", e);
+    }
+
+    if timings {
+        report::print_timings(&result.tasks, result.started_at);
+    }
+    if let Some(path) = timings_json {
+        report::write_json(&result.tasks, result.started_at, &path)?;
     }
 
+    if let Some(cancelled_by) = cancelled_by {
+        eprintln!("Cancelled on failure of: {}", cancelled_by);
+        drop(lock);
+        drop(_guard);
+        exit(1);
+    }
+
+    if let Some(task) = result.ejected {
+        drop(lock);
+        let code = run_ejected(&task)?;
+        drop(_guard);
+        exit(code);
+    }
+
+    Ok(())
+}
+
+/// Saves the output and state of the tasks once the main loop failed, so that they are not lost
+/// with the UI, and stops the tasks.
+async fn dump_crash(app: &mut App, reason: &str) {
+    restore();
+    ratatui::restore();
+    let result = app.crash_result();
+    match crash::dump(&result.tasks, result.started_at, reason) {
+        Ok(dir) => eprintln!(
+            "parallely crashed, the output and state of the tasks were saved to {}",
+            dir.display()
+        ),
+        Err(e) => eprintln!(
+            "parallely crashed and failed to save the output of the tasks: {:#}",
+            e
+        ),
+    }
+    app.stop().await;
+}
+
+/// Runs the command of an ejected task with the standard streams of parallely, so that it is
+/// attached to the terminal. Returns its exit code.
+fn run_ejected(task: &TaskConfig) -> color_eyre::Result<i32> {
+    let command = &task.command;
+    eprintln!("\nRunning {} attached to the terminal", command);
+    let (program, args) = builtin::command_line(command, task.shell);
+    let mut child = std::process::Command::new(program);
+    child.args(args);
+    if let Some(cwd) = task.cwd.as_ref() {
+        child.current_dir(cwd);
+    }
+    if !task.load_env.is_empty() {
+        let dir = task.cwd.as_deref().unwrap_or(Path::new("."));
+        child.envs(direnv::load(dir, &task.load_env)?);
+    }
+    let status = child
+        .status()
+        .wrap_err_with(|| format!("Failed to run {}", command))?;
+    // killed by a signal
+    Ok(status.code().unwrap_or(1))
+}
+
+/// The tasks to run, from the closest parallely.toml if none are given.
+fn discover_tasks(parallely: &mut Parallely) -> color_eyre::Result<Vec<TaskConfig>> {
+    discover_config(parallely)?;
+    parallely.tasks()
+}
+
+/// Uses the closest parallely.toml if no tasks are given.
+fn discover_config(parallely: &mut Parallely) -> color_eyre::Result<()> {
+    if parallely.commands.is_empty() && parallely.config.is_none() && parallely.preset.is_none() {
+        if let Some(path) = config::discover(&std::env::current_dir()?) {
+            eprintln!("Using config file {}", path.display());
+            parallely.config = Some(path);
+        }
+    }
     Ok(())
 }
 
+/// Marks the session as running according to [`Parallely::instance_mode`], exits once the
+/// running session has been attached to.
+fn lock_instance(parallely: &Parallely) -> color_eyre::Result<Option<InstanceLock>> {
+    let mode = parallely.instance_mode();
+    if mode == InstanceMode::Allow {
+        return Ok(None);
+    }
+    let key = parallely.instance_key()?;
+    let namespace = mode == InstanceMode::Namespace;
+    match InstanceLock::acquire(&key, namespace, parallely.status_file.as_deref())? {
+        Acquired::Lock(lock) => {
+            if let Some(number) = lock.namespace() {
+                eprintln!(
+                    "parallely is already running for {}, this is instance {}",
+                    key, number
+                );
+                std::env::set_var(instance::INSTANCE_VAR, number.to_string());
+            }
+            Ok(Some(lock))
+        }
+        Acquired::Running(running) if mode == InstanceMode::Attach => {
+            instance::attach(&running)?;
+            exit(0);
+        }
+        Acquired::Running(running) => Err(InstanceError::Running {
+            key: running.key,
+            pid: running.pid,
+        }
+        .into()),
+    }
+}
+
 fn try_init(parallely: &Parallely) -> color_eyre::Result<Option<WorkerGuard>> {
     color_eyre::install()?;
 
@@ -87,12 +431,17 @@ fn try_init(parallely: &Parallely) -> color_eyre::Result<Option<WorkerGuard>> {
         None
     };
 
-    std::io::stdout().execute(crossterm::event::EnableMouseCapture)?;
-    std::io::stdout().execute(crossterm::event::EnableFocusChange)?;
-
     Ok(guard)
 }
 
+fn enable_capture(mouse: bool) -> color_eyre::Result<()> {
+    if mouse {
+        std::io::stdout().execute(crossterm::event::EnableMouseCapture)?;
+    }
+    std::io::stdout().execute(crossterm::event::EnableFocusChange)?;
+    Ok(())
+}
+
 fn restore() {
     if let Err(err) = try_restore() {
         eprintln!("Failed to restore terminal: {:#?}", err);
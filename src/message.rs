@@ -1,6 +1,11 @@
 use crate::event::ParallelyEvent;
+use crate::plugin::PluginCommand;
 use crate::shutdown_handler::ShutdownReason;
+use crate::supervisor::Supervisor;
+use crate::task_executor::child_ext::ChildSignal;
+use crate::task_state::Transition;
 use futures::Stream;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tokio::sync::mpsc::error::SendError;
@@ -8,7 +13,7 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 
 pub fn message_queue() -> (MessageSender, MessageStream) {
     let (message_sender, message_receiver) = tokio::sync::mpsc::unbounded_channel();
-    let message_sender = MessageSender::new(message_sender);
+    let message_sender = MessageSender::new(message_sender, Supervisor::default());
     let message_stream = MessageStream::new(message_receiver);
     (message_sender, message_stream)
 }
@@ -18,6 +23,15 @@ pub enum Message {
     Error(color_eyre::Report),
     Shutdown(ShutdownReason),
     EventChunk(Vec<ParallelyEvent>),
+    Reload,
+    /// A signal received by parallely which is forwarded to the tasks, see `--forward-signals`.
+    Signal(ChildSignal),
+    /// A task changed its state.
+    Transition(Transition),
+    /// A command sent by the plugin of the name.
+    Plugin(String, PluginCommand),
+    /// A file watched for the task of the key changed, see [`crate::config::TaskConfig::watch`].
+    Changed(String, PathBuf),
     Update,
 }
 
@@ -33,20 +47,36 @@ impl From<color_eyre::Report> for Message {
     }
 }
 
+impl From<Transition> for Message {
+    fn from(value: Transition) -> Self {
+        Self::Transition(value)
+    }
+}
+
 impl From<Vec<ParallelyEvent>> for Message {
     fn from(value: Vec<ParallelyEvent>) -> Self {
         Self::EventChunk(value)
     }
 }
 
+/// Sends messages to the main loop of the app. The `send_*` methods never fail: once the main
+/// loop has stopped and its queue is closed, e.g. while background tasks race the shutdown,
+/// there is nobody left to act on a message, so it is dropped and logged instead. Use
+/// [`MessageSender::send`] to notice that the queue is closed, e.g. to stop a background loop.
 #[derive(Clone)]
 pub struct MessageSender {
     inner: tokio::sync::mpsc::UnboundedSender<Message>,
+    supervisor: Supervisor,
 }
 
 impl MessageSender {
-    fn new(inner: tokio::sync::mpsc::UnboundedSender<Message>) -> Self {
-        Self { inner }
+    fn new(inner: tokio::sync::mpsc::UnboundedSender<Message>, supervisor: Supervisor) -> Self {
+        Self { inner, supervisor }
+    }
+
+    /// Owns the background tasks which send messages to this queue.
+    pub fn supervisor(&self) -> &Supervisor {
+        &self.supervisor
     }
 
     pub fn send<T>(&self, message: T) -> color_eyre::Result<(), SendError<Message>>
@@ -56,31 +86,61 @@ impl MessageSender {
         self.inner.send(message.into())
     }
 
+    /// Sends the message, or drops it if the queue is closed.
+    fn deliver<T>(&self, message: T)
+    where
+        T: Into<Message>,
+    {
+        match self.send(message) {
+            Ok(()) => {}
+            Err(SendError(Message::Error(error))) => {
+                tracing::warn!(
+                    "[Message] Dropped error after the main loop stopped: {:?}",
+                    error
+                );
+            }
+            Err(SendError(message)) => {
+                tracing::debug!(
+                    "[Message] Dropped after the main loop stopped: {:?}",
+                    message
+                );
+            }
+        }
+    }
+
     pub fn send_error<E>(&self, error: E)
     where
         E: Into<color_eyre::Report>,
     {
-        if let Err(e) = self.send(error.into()) {
-            panic!("Failed to send error: {}", e);
-        }
+        self.deliver(error.into());
     }
 
     pub fn send_shutdown(&self, reason: ShutdownReason) {
-        if let Err(e) = self.send(reason) {
-            self.send_error(e);
-        }
+        self.deliver(reason);
     }
 
     pub fn send_event_chunk(&self, events: Vec<ParallelyEvent>) {
-        if let Err(e) = self.send(events) {
-            self.send_error(e);
-        }
+        self.deliver(events);
+    }
+
+    pub fn send_reload(&self) {
+        self.deliver(Message::Reload);
+    }
+
+    pub fn send_signal(&self, signal: ChildSignal) {
+        self.deliver(Message::Signal(signal));
+    }
+
+    pub fn send_transition(&self, transition: Transition) {
+        self.deliver(transition);
+    }
+
+    pub fn send_plugin_command(&self, plugin: String, command: PluginCommand) {
+        self.deliver(Message::Plugin(plugin, command));
     }
 
     pub fn need_update(&self) {
-        if let Err(e) = self.send(Message::Update) {
-            self.send_error(e);
-        }
+        self.deliver(Message::Update);
     }
 }
 
@@ -93,6 +153,11 @@ impl MessageStream {
         let message_stream = UnboundedReceiverStream::new(message_stream);
         Self { message_stream }
     }
+
+    /// How many messages are waiting to be handled.
+    pub fn len(&self) -> usize {
+        self.message_stream.as_ref().len()
+    }
 }
 
 impl Stream for MessageStream {
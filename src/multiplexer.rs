@@ -0,0 +1,58 @@
+pub mod zellij;
+
+use crate::config::TaskConfig;
+use crate::multiplexer::zellij::Zellij;
+use clap::ValueEnum;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MultiplexerError {
+    #[error("Not running inside a {0} session")]
+    NotInSession(&'static str),
+    #[error("Failed to spawn `{command}` in {multiplexer}: {status}")]
+    SpawnFailed {
+        multiplexer: &'static str,
+        command: String,
+        status: std::process::ExitStatus,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum MultiplexerKind {
+    /// Open every command in its own zellij pane.
+    Zellij,
+}
+
+impl MultiplexerKind {
+    pub fn backend(&self) -> Box<dyn Multiplexer> {
+        match self {
+            MultiplexerKind::Zellij => Box::new(Zellij),
+        }
+    }
+}
+
+/// An external terminal multiplexer which hosts the commands in its own panes, instead of the
+/// built-in renderer. The multiplexer then owns the lifecycle of the commands.
+pub trait Multiplexer {
+    fn name(&self) -> &'static str;
+
+    /// Checks that the multiplexer can be driven from the current environment.
+    fn check(&self) -> color_eyre::Result<(), MultiplexerError>;
+
+    /// Opens a pane running the command of the task in its working directory, with its shell.
+    fn spawn(&self, task: &TaskConfig, close_on_exit: bool) -> color_eyre::Result<()>;
+}
+
+pub fn run(
+    kind: MultiplexerKind,
+    tasks: &[TaskConfig],
+    close_on_exit: bool,
+) -> color_eyre::Result<()> {
+    let backend = kind.backend();
+    backend.check()?;
+    for task in tasks {
+        backend.spawn(task, close_on_exit)?;
+        println!("Spawned: {} (in {})", task.command, backend.name());
+    }
+    Ok(())
+}
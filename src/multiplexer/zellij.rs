@@ -0,0 +1,42 @@
+use crate::builtin;
+use crate::config::TaskConfig;
+use crate::multiplexer::{Multiplexer, MultiplexerError};
+use std::process::Command;
+
+pub struct Zellij;
+
+impl Multiplexer for Zellij {
+    fn name(&self) -> &'static str {
+        "zellij"
+    }
+
+    fn check(&self) -> color_eyre::Result<(), MultiplexerError> {
+        match std::env::var_os("ZELLIJ") {
+            Some(_) => Ok(()),
+            None => Err(MultiplexerError::NotInSession(self.name())),
+        }
+    }
+
+    fn spawn(&self, task: &TaskConfig, close_on_exit: bool) -> color_eyre::Result<()> {
+        let mut zellij = Command::new("zellij");
+        zellij.args(["run", "--name", task.key()]);
+        if let Some(cwd) = task.cwd.as_ref() {
+            // the pane is started by the zellij server, which runs in a directory of its own
+            zellij.arg("--cwd").arg(std::path::absolute(cwd)?);
+        }
+        if close_on_exit {
+            zellij.arg("--close-on-exit");
+        }
+        let (program, args) = builtin::command_line(&task.command, task.shell);
+        let status = zellij.arg("--").arg(program).args(args).status()?;
+        if !status.success() {
+            return Err(MultiplexerError::SpawnFailed {
+                multiplexer: self.name(),
+                command: task.command.to_string(),
+                status,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
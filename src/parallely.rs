@@ -1,18 +1,383 @@
-use clap::Parser;
+use crate::bell::BellPolicy;
+use crate::config;
+use crate::config::params;
+use crate::config::tags::TagFilter;
+use crate::config::{OutputEncoding, TaskConfig};
+use crate::console;
+use crate::i18n::Lang;
+use crate::instance::InstanceMode;
+use crate::log_forwarder::ForwardTarget;
+use crate::multiplexer::MultiplexerKind;
+use crate::shutdown_handler::HangupAction;
+use crate::summary::ColorChoice;
+use crate::task_events::EventFormat;
+use crate::task_log;
+use crate::task_log::LogOptions;
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, Subcommand};
 use std::fmt::Debug;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Default, Debug, Parser)]
-#[command(version, about, author)]
+#[command(version, about, author, args_conflicts_with_subcommands = true)]
 pub struct Parallely {
-    /// The commands to run in parallel. e.g. `parallely "echo hello" "echo world"`
-    #[arg(value_name = "COMMANDS", required = true)]
+    #[command(subcommand)]
+    pub subcommand: Option<ParallelySubcommand>,
+
+    /// The commands to run in parallel. e.g. `parallely "echo hello" "echo world"`. Without any,
+    /// they can be typed or pasted in at startup.
+    #[arg(value_name = "COMMANDS")]
     pub commands: Vec<String>,
 
+    /// Start the tasks of a preset (see `parallely presets`) before the ones of the config file.
+    #[arg(short, long, value_name = "PRESET")]
+    pub preset: Option<String>,
+
+    /// Load tasks from a TOML config file, they run before the COMMANDS. Without any tasks given,
+    /// the closest parallely.toml in the current directory or its parents is used.
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Give a value to a parameter of the tasks (see `params` in the config file), which is
+    /// asked for at startup otherwise. Can be repeated.
+    #[arg(long = "param", value_name = "NAME=VALUE", value_parser = params::parse_assignment)]
+    pub params: Vec<(String, String)>,
+
     /// Exit on all sub-processes complete.
     #[arg(long = "eoc")]
     pub exit_on_complete: bool,
 
+    /// Kill all remaining sub-processes as soon as one of them exits with a failure.
+    #[arg(long)]
+    pub cancel_on_failure: bool,
+
+    /// Forward SIGHUP, SIGUSR1 and SIGUSR2 received by parallely to the tasks instead of
+    /// reloading the config on SIGHUP, e.g. for tools which reload on a signal.
+    #[arg(long)]
+    pub forward_signals: bool,
+
+    /// What to do with the tasks when the terminal parallely runs in is closed: stop them as on
+    /// quitting, or leave them running without reading their output anymore.
+    #[arg(long, value_enum, value_name = "ACTION", default_value_t = HangupAction::Stop)]
+    pub on_hangup: HangupAction,
+
+    /// Whether to color the summary printed at exit.
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Print the last N lines of output of every failed task to stderr at exit, instead of the
+    /// last few beneath it in the summary.
+    #[arg(long, value_name = "N")]
+    pub tail_on_exit: Option<usize>,
+
+    /// Print when each task started and ended, and how long it took, at exit.
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Write the timings of every task as JSON into FILE at exit.
+    #[arg(long, value_name = "FILE")]
+    pub timings_json: Option<PathBuf>,
+
+    /// Write the output of every task into DIR/<task>.log.
+    #[arg(long, value_name = "DIR")]
+    pub log_dir: Option<PathBuf>,
+
+    /// Rotate a task log once it reaches SIZE, e.g. 512K, 10M or 1G.
+    #[arg(long, value_name = "SIZE", default_value = "10M", value_parser = task_log::parse_size)]
+    pub log_max_size: u64,
+
+    /// Also rotate a task log after DURATION, e.g. 30m, 12h or 1d.
+    #[arg(long, value_name = "DURATION", value_parser = task_log::parse_duration)]
+    pub log_rotate_every: Option<Duration>,
+
+    /// How many rotated, gzip compressed logs to keep per task.
+    #[arg(long, value_name = "COUNT", default_value_t = 5)]
+    pub log_keep: usize,
+
+    /// Forward every line of output to the system log, identified by the task name.
+    #[arg(long, value_enum, value_name = "TARGET")]
+    pub forward_logs: Option<ForwardTarget>,
+
+    /// Start the plugins in DIR instead of ~/.config/parallely/plugins: executables which
+    /// receive what happens as JSON lines on stdin and send commands back on stdout.
+    #[arg(long, value_name = "DIR")]
+    pub plugin_dir: Option<PathBuf>,
+
+    /// Do not start any plugins.
+    #[arg(long, conflicts_with = "plugin_dir")]
+    pub no_plugins: bool,
+
+    /// Run the automation rules of a Lua script, e.g. to start a task once another one prints a
+    /// line. Overrides `script` of the config file.
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+
+    /// Keep FILE up to date with the state, PID and last lines of output of every task as JSON.
+    #[arg(long, value_name = "FILE")]
+    pub status_file: Option<PathBuf>,
+
+    /// How many lines of output of each task to include in the status file.
+    #[arg(long, value_name = "COUNT", default_value_t = 10)]
+    pub status_lines: usize,
+
+    /// Show the command and PID of each task in the border of its output instead of a separate
+    /// block, toggle with <c> at runtime.
+    #[arg(long)]
+    pub compact: bool,
+
+    /// The encoding of the output of the tasks which do not set `encoding`, e.g. gbk, shift_jis or
+    /// latin1. UTF-8 by default.
+    #[arg(long, value_name = "ENCODING")]
+    pub encoding: Option<OutputEncoding>,
+
+    /// The language of the UI, by default the one of the locale (LC_ALL, LC_MESSAGES or LANG).
+    #[arg(long, value_name = "LANG")]
+    pub lang: Option<Lang>,
+
+    /// Focus the console of this task, by its name or command, at startup.
+    #[arg(long, value_name = "TASK")]
+    pub focus: Option<String>,
+
+    /// Show only the console of this task at startup, as if zoomed with <z>.
+    #[arg(long, value_name = "TASK", conflicts_with = "focus")]
+    pub zoom: Option<String>,
+
+    /// Give more room to the consoles of tasks which recently printed or failed and less to
+    /// idle ones, instead of splitting the screen evenly.
+    #[arg(long)]
+    pub auto_layout: bool,
+
+    /// Do not ask for confirmation before stopping or killing tasks, or quitting while they run.
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Only allow looking at the output, e.g. on a shared dashboard: the keys which stop,
+    /// restart, signal or type into tasks and the ones which quit are disabled. parallely can
+    /// still be stopped with SIGINT or SIGTERM.
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Print the output as plain lines labeled with their task, followed by what changed, e.g.
+    /// a task exiting or the focus moving, instead of drawing the screen, for screen readers.
+    /// The keys are the same.
+    #[arg(long)]
+    pub screen_reader: bool,
+
+    /// Draw the screen with bright colors on black, without dimmed text.
+    #[arg(long, conflicts_with = "screen_reader")]
+    pub high_contrast: bool,
+
+    /// Leave the mouse to the terminal, so that text can be selected and copied as usual.
+    /// Everything the mouse does is done with the keys as well.
+    #[arg(long, conflicts_with = "screen_reader")]
+    pub no_mouse: bool,
+
+    /// What to do when a session of the same config file or preset is already running, `allow`
+    /// unless the config file sets `instance`.
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub instance: Option<InstanceMode>,
+
+    /// Print the tasks as they would be run, with the command picked for this platform and the
+    /// executable it resolves to, and exit.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Run every task, instead of picking the ones to run at startup when there are 10 or more.
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Run only the tasks with these tags and the tasks they depend on, e.g. `backend,!slow` for
+    /// the tasks tagged `backend` which are not tagged `slow`.
+    #[arg(long, value_name = "TAGS")]
+    pub tags: Option<TagFilter>,
+
+    /// How many lines the mouse wheel scrolls the output at a time.
+    #[arg(long, value_name = "LINES", default_value_t = console::DEFAULT_SCROLL_STEP)]
+    pub scroll_step: usize,
+
+    /// When to ring the terminal bell.
+    #[arg(long, value_enum, default_value_t = BellPolicy::Never)]
+    pub bell: BellPolicy,
+
+    /// Run the commands in panes of an external terminal multiplexer instead of the built-in UI.
+    #[arg(long, value_enum, value_name = "MULTIPLEXER")]
+    pub multiplexer: Option<MultiplexerKind>,
+
     /// Write log into $(PWD)/logs.
     #[arg(short, long)]
     pub debug: bool,
 }
+
+impl Parallely {
+    /// The tasks from the preset and the config file followed by the ones given on the command
+    /// line.
+    pub fn tasks(&self) -> color_eyre::Result<Vec<TaskConfig>> {
+        let mut tasks = config::load_tasks(
+            self.preset.as_deref(),
+            self.config.as_deref(),
+            &self.commands,
+            &self.params,
+        )?;
+        for task in tasks.iter_mut() {
+            task.encoding = task.encoding.or(self.encoding);
+            // left running when the terminal is closed, so they have to outlive parallely
+            task.detached |= self.on_hangup == HangupAction::Detach;
+        }
+        if let Some(filter) = self.tags.as_ref() {
+            tasks = filter.apply(tasks);
+        }
+        Ok(tasks)
+    }
+
+    /// Asks for the parameters of the tasks which were not given with `--param`, returns the
+    /// values typed in after adding them to [`Parallely::params`].
+    pub fn prompt_params(&mut self) -> color_eyre::Result<Vec<(String, String)>> {
+        let templates = config::load_templates(
+            self.preset.as_deref(),
+            self.config.as_deref(),
+            &self.commands,
+        )?;
+        let answers = params::prompt(&templates, &self.params)?;
+        self.params.extend(answers.iter().cloned());
+        Ok(answers)
+    }
+
+    /// Whether destructive actions need to be confirmed, which `--yes` or `confirm = false` in
+    /// the config file turn off.
+    pub fn confirmations(&self) -> bool {
+        let configured = self
+            .config
+            .as_deref()
+            .and_then(|path| config::Config::load(path).ok())
+            .and_then(|config| config.confirm);
+        !self.yes && configured.unwrap_or(true)
+    }
+
+    /// `--instance`, or else `instance` of the config file.
+    pub fn instance_mode(&self) -> InstanceMode {
+        self.instance
+            .or_else(|| {
+                self.config
+                    .as_deref()
+                    .and_then(|path| config::Config::load(path).ok())
+                    .and_then(|config| config.instance)
+            })
+            .unwrap_or_default()
+    }
+
+    /// `--script`, or else `script` of the config file.
+    pub fn script(&self) -> Option<PathBuf> {
+        self.script.clone().or_else(|| {
+            self.config
+                .as_deref()
+                .and_then(|path| config::Config::load(path).ok())
+                .and_then(|config| config.script)
+        })
+    }
+
+    /// Identifies the project for [`Parallely::instance_mode`]: the config file, the preset or
+    /// else the current directory.
+    pub fn instance_key(&self) -> color_eyre::Result<String> {
+        let mut parts = Vec::new();
+        if let Some(path) = self.config.as_deref() {
+            parts.push(std::fs::canonicalize(path)?.display().to_string());
+        }
+        if let Some(preset) = self.preset.as_deref() {
+            parts.push(format!("preset {}", preset));
+        }
+        if parts.is_empty() {
+            parts.push(std::env::current_dir()?.display().to_string());
+        }
+        Ok(parts.join(" + "))
+    }
+
+    pub fn log_options(&self) -> Option<LogOptions> {
+        self.log_dir.as_ref().map(|dir| LogOptions {
+            dir: dir.clone(),
+            max_size: self.log_max_size,
+            rotate_every: self.log_rotate_every,
+            keep: self.log_keep,
+        })
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ParallelySubcommand {
+    /// List the presets which can be started with --preset.
+    Presets,
+    /// Run the tasks without the UI, printing their output prefixed by the task, e.g. in CI.
+    Up {
+        /// Print a line once every task is ready, or running if it has no ready probe, exit with
+        /// an error if a task stops before that.
+        #[arg(long)]
+        wait: bool,
+
+        /// With --wait, exit with an error if the tasks are not ready after DURATION, e.g. 90s or
+        /// 5m.
+        #[arg(long, value_name = "DURATION", value_parser = task_log::parse_duration, requires = "wait")]
+        timeout: Option<Duration>,
+
+        /// Forward SIGHUP, SIGUSR1 and SIGUSR2 received by parallely to the tasks.
+        #[arg(long)]
+        forward_signals: bool,
+
+        /// What to do with the tasks on SIGHUP or when the terminal is closed.
+        #[arg(long, value_enum, value_name = "ACTION", default_value_t = HangupAction::Stop)]
+        on_hangup: HangupAction,
+
+        /// Print a line of JSON whenever a task starts, becomes ready or exits, with the time, among
+        /// the output of the tasks, so that the session can be followed by log processors.
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        events: Option<EventFormat>,
+
+        /// What to do when a session of the same config file or preset is already running.
+        #[arg(long, value_enum, value_name = "MODE")]
+        instance: Option<InstanceMode>,
+
+        /// Start the tasks of a preset before the ones of the config file.
+        #[arg(short, long, value_name = "PRESET")]
+        preset: Option<String>,
+
+        /// Load tasks from a TOML config file, the closest parallely.toml without any tasks given.
+        #[arg(short, long, value_name = "FILE")]
+        config: Option<PathBuf>,
+
+        /// Give a value to a parameter of the tasks, see `parallely --help`.
+        #[arg(long = "param", value_name = "NAME=VALUE", value_parser = params::parse_assignment)]
+        params: Vec<(String, String)>,
+
+        /// More commands to run after the tasks of the config file.
+        #[arg(value_name = "COMMANDS")]
+        commands: Vec<String>,
+    },
+    /// List the past sessions, the most recent first.
+    History,
+    /// Run a past session again with the same arguments in the same directory.
+    Rerun {
+        /// The number of the session in `parallely history`.
+        #[arg(default_value_t = 1)]
+        number: usize,
+    },
+    /// Run a command repeatedly and print the minimum, mean, maximum and 95th percentile of how
+    /// long it took, like hyperfine. The output of the command is discarded.
+    Bench {
+        /// How many times to run the command.
+        #[arg(short = 'n', long, default_value_t = 10, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        runs: usize,
+
+        /// How many runs to start at a time, in batches.
+        #[arg(short = 'j', long, value_name = "COUNT", default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        parallel: usize,
+
+        /// Write the durations and their statistics as JSON into FILE.
+        #[arg(long, value_name = "FILE")]
+        json: Option<PathBuf>,
+
+        /// The command to run, split on whitespace like COMMANDS.
+        command: String,
+    },
+    /// Run a helper built into parallely, which tasks do with `builtin:<name> <argument>`.
+    #[command(hide = true)]
+    Builtin { name: String, argument: String },
+}
@@ -0,0 +1,133 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, BorderType, Clear, Paragraph, Widget};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Over how long the frames per second are counted.
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+const LABEL_WIDTH: usize = 20;
+
+/// How long the main loop takes to draw frames and handle messages, for the [`PerfOverlay`].
+pub struct PerfStats {
+    /// When the frames of the last [`FPS_WINDOW`] were drawn.
+    frames: VecDeque<Instant>,
+    last_render: Duration,
+    last_message: Duration,
+}
+
+impl PerfStats {
+    pub fn new() -> Self {
+        PerfStats {
+            frames: VecDeque::new(),
+            last_render: Duration::ZERO,
+            last_message: Duration::ZERO,
+        }
+    }
+
+    pub fn record_frame(&mut self, duration: Duration) {
+        let now = Instant::now();
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|frame| now.duration_since(*frame) > FPS_WINDOW)
+        {
+            self.frames.pop_front();
+        }
+        self.last_render = duration;
+    }
+
+    /// Records how long the main loop took to handle a message.
+    pub fn record_message(&mut self, duration: Duration) {
+        self.last_message = duration;
+    }
+}
+
+/// The buffers of the output of one console, see [`crate::console::Console::buffers`].
+pub struct ConsoleBuffers {
+    pub label: String,
+    /// The received lines which are kept and their size in bytes.
+    pub lines: usize,
+    pub bytes: usize,
+    /// The rows the lines are wrapped to and how many of them are parsed into styled text.
+    pub rows: usize,
+    pub cached_rows: usize,
+    /// The lines received while frozen.
+    pub held_lines: usize,
+    /// How long the console took to take in its pending output the last time it was drawn.
+    pub last_receive: Duration,
+}
+
+/// A debug popup in the top right corner, toggled with `F12`, with the frames per second, how
+/// many messages are waiting for the main loop, how long the last frame and message took and the
+/// output buffers of every console.
+pub struct PerfOverlay<'a> {
+    pub stats: &'a PerfStats,
+    pub queued_messages: usize,
+    pub consoles: Vec<ConsoleBuffers>,
+}
+
+impl Widget for &PerfOverlay<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let stats = self.stats;
+        let mut lines = vec![
+            Line::from(format!(
+                "{} fps · {} queued · render {} · message {}",
+                stats.frames.len(),
+                self.queued_messages,
+                format_duration(stats.last_render),
+                format_duration(stats.last_message),
+            )),
+            Line::from(format!(
+                "{:<LABEL_WIDTH$} {:>7} {:>9} {:>7} {:>7} {:>5} {:>8}",
+                "console", "lines", "bytes", "rows", "cached", "held", "receive"
+            ))
+            .dark_gray(),
+        ];
+        lines.extend(self.consoles.iter().map(|console| {
+            let label = console.label.chars().take(LABEL_WIDTH).collect::<String>();
+            Line::from(format!(
+                "{:<LABEL_WIDTH$} {:>7} {:>9} {:>7} {:>7} {:>5} {:>8}",
+                label,
+                console.lines,
+                console.bytes,
+                console.rows,
+                console.cached_rows,
+                console.held_lines,
+                format_duration(console.last_receive),
+            ))
+        }));
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+        let height = lines.len() as u16 + 2;
+        let popup_area = Rect {
+            x: area.right().saturating_sub(width).max(area.x),
+            width: width.min(area.width),
+            height: height.min(area.height),
+            ..area
+        };
+        let block = Block::bordered()
+            .title(" Performance ".magenta().bold())
+            .title_bottom(Line::from(vec![" Close ".into(), "<F12> ".blue().bold()]))
+            .border_type(BorderType::Rounded);
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines).block(block).render(popup_area, buf);
+    }
+}
+
+/// Formats a short duration in milliseconds, e.g. `0.42ms`, `12.5ms` or `250ms`.
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_secs_f64() * 1000.0;
+    if millis < 1.0 {
+        format!("{:.2}ms", millis)
+    } else if millis < 100.0 {
+        format!("{:.1}ms", millis)
+    } else {
+        format!("{:.0}ms", millis)
+    }
+}
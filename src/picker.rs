@@ -0,0 +1,134 @@
+use crate::event::ParallelyEvent;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::widgets::{
+    Block, BorderType, Clear, HighlightSpacing, List, ListState, StatefulWidget, Widget,
+};
+
+/// A popup list which lets the user pick one of several items with the keyboard.
+pub struct Picker<T> {
+    title: String,
+    items: Vec<(String, T)>,
+    state: ListState,
+}
+
+pub enum PickerAction<T> {
+    None,
+    Picked(T),
+    Cancelled,
+}
+
+impl<T> Picker<T>
+where
+    T: Clone,
+{
+    pub fn new(title: impl Into<String>, items: Vec<(String, T)>) -> Self {
+        let state = ListState::default().with_selected(Some(0));
+        Self {
+            title: title.into(),
+            items,
+            state,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The title and the selected item as plain text, for the screen reader frontend.
+    pub fn describe(&self) -> String {
+        let selected = self.state.selected().unwrap_or(0);
+        match self.items.get(selected) {
+            Some((label, _)) => format!(
+                "{}: {} ({} of {})",
+                self.title,
+                label,
+                selected + 1,
+                self.items.len()
+            ),
+            None => self.title.clone(),
+        }
+    }
+
+    /// Consumes every key event without a `Control` modifier, so that global shortcuts such as
+    /// `Ctrl+C` keep working while the picker is open.
+    pub fn handle_event(&mut self, event: &mut ParallelyEvent) -> PickerAction<T> {
+        if let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                return PickerAction::None;
+            }
+            let action = match code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.state.select_previous();
+                    PickerAction::None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.state.select_next();
+                    PickerAction::None
+                }
+                KeyCode::Enter => match self.selected() {
+                    Some(item) => PickerAction::Picked(item),
+                    None => PickerAction::Cancelled,
+                },
+                KeyCode::Esc | KeyCode::Char('q') => PickerAction::Cancelled,
+                _ => PickerAction::None,
+            };
+            event.stop_propagation();
+            return action;
+        }
+        PickerAction::None
+    }
+
+    fn selected(&self) -> Option<T> {
+        self.state
+            .selected()
+            .and_then(|index| {
+                self.items
+                    .get(index.min(self.items.len().saturating_sub(1)))
+            })
+            .map(|(_, item)| item.clone())
+    }
+}
+
+impl<T> Widget for &mut Picker<T> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let width = self
+            .items
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .chain(std::iter::once(self.title.chars().count()))
+            .max()
+            .unwrap_or(0) as u16
+            + 6;
+        let height = self.items.len() as u16 + 2;
+        let [popup_area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::bordered()
+            .title(format!(" {} ", self.title).magenta().bold())
+            .border_type(BorderType::Rounded);
+        let list = List::new(self.items.iter().map(|(label, _)| label.clone()))
+            .block(block)
+            .highlight_symbol("> ")
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_style(Style::new().reversed());
+
+        Clear.render(popup_area, buf);
+        StatefulWidget::render(list, popup_area, buf, &mut self.state);
+    }
+}
@@ -0,0 +1,365 @@
+pub mod script;
+pub mod wasm;
+
+use crate::config;
+use crate::message::MessageSender;
+use crate::plugin::script::Script;
+use crate::plugin::wasm::{WasmModule, WasmProcessor};
+use crate::task_state::Transition;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// How many events are queued for a plugin at most, the ones beyond are dropped.
+const PLUGIN_QUEUE: usize = 1024;
+/// How long the plugins get to exit on their own once their standard input has been closed.
+const PLUGIN_EXIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("Failed to start plugin {0}: {1}")]
+    Spawn(String, std::io::Error),
+    #[error("Plugin {0} sent an invalid command `{1}`: {2}")]
+    InvalidCommand(String, String, serde_json::Error),
+    #[error("Failed to load output processor {0}: {1}")]
+    Wasm(String, wasmi::Error),
+    #[error("Output processor {0} failed and was disabled: {1}")]
+    Processor(String, wasmi::Error),
+}
+
+/// What happened, sent to every plugin as a line of JSON on its standard input.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PluginEvent<'a> {
+    /// A task changed its state, e.g. `{"event":"transition","task":"api","from":"running",
+    /// "to":"ready"}`.
+    Transition {
+        task: &'a str,
+        from: String,
+        to: String,
+    },
+    /// A task printed a line, without its colors.
+    Line { task: &'a str, line: &'a str },
+}
+
+impl<'a> From<&'a Transition> for PluginEvent<'a> {
+    fn from(transition: &'a Transition) -> Self {
+        PluginEvent::Transition {
+            task: &transition.task,
+            from: transition.from.to_string(),
+            to: transition.to.to_string(),
+        }
+    }
+}
+
+/// What a plugin asks parallely to do, a line of JSON on its standard output, e.g.
+/// `{"command":"restart","task":"api"}`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum PluginCommand {
+    /// Starts the task unless it is running.
+    Start {
+        task: String,
+    },
+    Restart {
+        task: String,
+    },
+    Stop {
+        task: String,
+    },
+    /// Appends a dimmed line to the output of the task.
+    Annotate {
+        task: String,
+        text: String,
+    },
+    /// Shows a notice in the bottom border.
+    Notify {
+        text: String,
+    },
+}
+
+struct Plugin {
+    name: String,
+    /// Dropped by [`Plugins::close`], which closes the standard input of the plugin.
+    events: Mutex<Option<mpsc::Sender<String>>>,
+    /// Taken by [`Plugins::close`] to wait for the plugin to exit, which kills it otherwise.
+    child: Mutex<Option<Child>>,
+}
+
+/// The external executables which extend parallely, every executable file in the plugins
+/// directory (see [`user_dir`]). Each one is started with parallely, receives every
+/// [`PluginEvent`] and sends [`PluginCommand`]s back, which arrive as
+/// [`crate::message::Message::Plugin`]. What it prints to stderr goes to the debug log. A plugin
+/// which reads the events slower than they happen misses some, see [`PLUGIN_QUEUE`]. The
+/// `.wasm` files in there are loaded as [`WasmModule`]s, which every line of output passes
+/// through in the order of their names, see [`Processors`]. The [`Script`] given with
+/// `--script` sees the events as well.
+#[derive(Default)]
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+    processors: Vec<WasmModule>,
+    script: Option<Mutex<Script>>,
+}
+
+/// The output processors of a run of a task, which its reader passes every line through, so
+/// that the tasks process their output in parallel and apart from drawing.
+pub struct Processors(Vec<WasmProcessor>);
+
+impl Processors {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Passes the line through every output processor, `None` if one of them dropped it. A
+    /// processor which fails, e.g. runs out of fuel, is disabled and reported to `on_error`.
+    pub fn process(
+        &mut self,
+        line: String,
+        mut on_error: impl FnMut(PluginError),
+    ) -> Option<String> {
+        let mut line = Some(line);
+        self.0.retain_mut(|processor| {
+            let Some(current) = line.as_deref() else {
+                return true;
+            };
+            match processor.process(current) {
+                Ok(processed) => {
+                    line = processed;
+                    true
+                }
+                Err(e) => {
+                    on_error(PluginError::Processor(processor.name().to_string(), e));
+                    false
+                }
+            }
+        });
+        line
+    }
+}
+
+/// The default plugins directory, `plugins` in the [`config::user_dir`].
+pub fn user_dir() -> Option<PathBuf> {
+    config::user_dir().map(|dir| dir.join("plugins"))
+}
+
+impl Plugins {
+    /// Starts the plugins in `dir`, a missing directory has none. Returns the plugins which
+    /// could be started and why the others could not.
+    pub fn start(dir: &Path, message_sender: &MessageSender) -> (Plugins, Vec<PluginError>) {
+        let mut plugins = Vec::new();
+        let mut processors = Vec::new();
+        let mut errors = Vec::new();
+        for path in discover(dir) {
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "wasm")
+            {
+                match WasmModule::load(&path) {
+                    Ok(processor) => processors.push(processor),
+                    Err(e) => errors.push(e),
+                }
+            } else if is_executable(&path) {
+                match Plugin::start(&path, message_sender) {
+                    Ok(plugin) => plugins.push(plugin),
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+        (
+            Plugins {
+                plugins,
+                processors,
+                script: None,
+            },
+            errors,
+        )
+    }
+
+    pub fn set_script(&mut self, script: Script) {
+        self.script = Some(Mutex::new(script));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty() && self.processors.is_empty() && self.script.is_none()
+    }
+
+    /// Instantiates the output processors for a run of a task, reporting those which fail to
+    /// `on_error`.
+    pub fn processors(&self, mut on_error: impl FnMut(PluginError)) -> Processors {
+        let processors = self
+            .processors
+            .iter()
+            .filter_map(|module| {
+                module
+                    .instantiate()
+                    .map_err(|e| on_error(PluginError::Wasm(module.name().to_string(), e)))
+                    .ok()
+            })
+            .collect();
+        Processors(processors)
+    }
+
+    pub fn send(&self, event: PluginEvent) {
+        if let Some(script) = self.script.as_ref() {
+            script
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .handle(&event);
+        }
+        if self.plugins.is_empty() {
+            return;
+        }
+        let line = match serde_json::to_string(&event) {
+            Ok(json) => json + "\n",
+            Err(e) => {
+                tracing::warn!("[Plugin] Failed to serialize {:?}: {}", event, e);
+                return;
+            }
+        };
+        for plugin in self.plugins.iter() {
+            let events = plugin.events.lock().unwrap_or_else(|e| e.into_inner());
+            match events.as_ref().map(|events| events.try_send(line.clone())) {
+                None | Some(Ok(())) => {}
+                Some(Err(TrySendError::Full(_))) => {
+                    tracing::debug!("[Plugin] {} is behind, dropped {:?}", plugin.name, event);
+                }
+                // a plugin which has exited does not receive anything anymore
+                Some(Err(TrySendError::Closed(_))) => {
+                    tracing::trace!("[Plugin] {} is gone", plugin.name);
+                }
+            }
+        }
+    }
+
+    /// Closes the standard input of the plugins, so that they can finish up and exit, and
+    /// waits for them to, at most for [`PLUGIN_EXIT_TIMEOUT`]. The ones which are still running
+    /// then are killed.
+    pub async fn close(&self) {
+        let mut children = Vec::new();
+        for plugin in self.plugins.iter() {
+            plugin
+                .events
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take();
+            children.extend(
+                plugin
+                    .child
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .take(),
+            );
+        }
+        let exited = futures::future::join_all(children.iter_mut().map(Child::wait));
+        if tokio::time::timeout(PLUGIN_EXIT_TIMEOUT, exited)
+            .await
+            .is_err()
+        {
+            tracing::info!("[Plugin] Killing the plugins which did not exit");
+        }
+    }
+}
+
+impl Plugin {
+    fn start(path: &Path, message_sender: &MessageSender) -> Result<Self, PluginError> {
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| PluginError::Spawn(name.clone(), e))?;
+        let supervisor = message_sender.supervisor();
+
+        let (events, mut receiver) = mpsc::channel::<String>(PLUGIN_QUEUE);
+        let mut stdin = child.stdin.take().unwrap();
+        supervisor.spawn(async move {
+            while let Some(line) = receiver.recv().await {
+                if stdin.write_all(line.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+        let plugin = name.clone();
+        let sender = message_sender.clone();
+        supervisor.spawn(async move {
+            while let Ok(Some(line)) = stdout.next_line().await {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<PluginCommand>(&line) {
+                    Ok(command) => sender.send_plugin_command(plugin.clone(), command),
+                    Err(e) => {
+                        sender.send_error(PluginError::InvalidCommand(plugin.clone(), line, e))
+                    }
+                }
+            }
+            tracing::info!("[Plugin] {} closed its output", plugin);
+        });
+
+        let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
+        let plugin = name.clone();
+        supervisor.spawn(async move {
+            while let Ok(Some(line)) = stderr.next_line().await {
+                tracing::warn!("[Plugin] {}: {}", plugin, line);
+            }
+        });
+
+        tracing::info!("[Plugin] Started {}", path.display());
+        Ok(Plugin {
+            name,
+            events: Mutex::new(Some(events)),
+            child: Mutex::new(Some(child)),
+        })
+    }
+}
+
+fn plugin_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().to_string(),
+    )
+}
+
+/// The files in `dir` sorted by name, leaving out hidden ones.
+fn discover(dir: &Path) -> Vec<PathBuf> {
+    let mut paths = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            !hidden && path.is_file()
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| ["exe", "bat", "cmd", "com"].iter().any(|e| extension == *e))
+}
@@ -0,0 +1,237 @@
+use crate::message::MessageSender;
+use crate::plugin::{PluginCommand, PluginEvent};
+use mlua::{Function, HookTriggers, Lua, RegistryKey, Table, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// How many Lua instructions run between two checks of the budget.
+const HOOK_INTERVAL: u32 = 10_000;
+/// How many times [`HOOK_INTERVAL`] instructions the script may run for loading and for every
+/// event, so that a rule which loops forever fails instead of hanging parallely.
+const HOOKS_PER_EVENT: u32 = 1_000;
+
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("Failed to read script {0}: {1}")]
+    Read(String, std::io::Error),
+    #[error("Failed to run script {0}: {1}")]
+    Lua(String, mlua::Error),
+}
+
+/// What a rule of the script waits for.
+enum Trigger {
+    /// The task prints a line matching the Lua pattern.
+    Line(String),
+    /// The task changes to the state, named as in [`crate::task_state::TaskState`]'s `Display`.
+    State(String),
+    /// The task fails that many times in a row.
+    Failures(usize),
+}
+
+/// Makes the trigger of a rule from the second argument of e.g. `parallely.on_line`.
+type ParseTrigger = fn(Value) -> mlua::Result<Trigger>;
+/// Makes the command of an action from its arguments, e.g. of `parallely.annotate`.
+type Action = fn(String, Option<String>) -> PluginCommand;
+
+struct Rule {
+    /// The task, `*` for any.
+    task: String,
+    trigger: Trigger,
+    function: RegistryKey,
+}
+
+/// A Lua script of automation rules, e.g.
+///
+/// ```lua
+/// parallely.on_line("api", "listening", function(task, line)
+///   parallely.start("e2e")
+/// end)
+/// parallely.on_failures("api", 3, function(task, count)
+///   parallely.notify(task .. " failed " .. count .. " times in a row")
+/// end)
+/// ```
+///
+/// It sees the same [`PluginEvent`]s as the plugins and its actions are sent as
+/// [`PluginCommand`]s likewise.
+pub struct Script {
+    name: String,
+    lua: Lua,
+    rules: Arc<Mutex<Vec<Rule>>>,
+    message_sender: MessageSender,
+    /// How many times in a row each task has failed.
+    failures: HashMap<String, usize>,
+    /// How many times the instruction hook has run since the budget was last reset.
+    hooks: Arc<AtomicU32>,
+}
+
+impl Script {
+    /// Runs the script, which registers its rules.
+    pub fn load(path: &Path, message_sender: MessageSender) -> Result<Self, ScriptError> {
+        let name = path.display().to_string();
+        let source =
+            std::fs::read_to_string(path).map_err(|e| ScriptError::Read(name.clone(), e))?;
+        let lua = Lua::new();
+        let hooks = Arc::new(AtomicU32::new(0));
+        let counter = hooks.clone();
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INTERVAL),
+            move |_, _| match counter.fetch_add(1, Ordering::Relaxed) < HOOKS_PER_EVENT {
+                true => Ok(()),
+                false => Err(mlua::Error::runtime("the instruction limit was exceeded")),
+            },
+        );
+        let rules = Arc::new(Mutex::new(Vec::new()));
+        register(&lua, &rules, &message_sender, &name)
+            .and_then(|()| lua.load(source).set_name(name.as_str()).exec())
+            .map_err(|e| ScriptError::Lua(name.clone(), e))?;
+        Ok(Script {
+            name,
+            lua,
+            rules,
+            message_sender,
+            failures: HashMap::new(),
+            hooks,
+        })
+    }
+
+    /// Calls the functions of the rules which the event triggers. An error in one of them is
+    /// shown as a notice, as is running out of the budget of instructions for the event.
+    pub fn handle(&mut self, event: &PluginEvent) {
+        self.hooks.store(0, Ordering::Relaxed);
+        if let Err(e) = self.try_handle(event) {
+            let text = format!("Error in script {}: {}", self.name, e);
+            let first_line = text.lines().next().unwrap_or_default().to_string();
+            tracing::warn!("[Script] {}", text);
+            self.message_sender.send_plugin_command(
+                self.name.clone(),
+                PluginCommand::Notify { text: first_line },
+            );
+        }
+    }
+
+    fn try_handle(&mut self, event: &PluginEvent) -> mlua::Result<()> {
+        match event {
+            PluginEvent::Line { task, line } => {
+                let find: Function = self.lua.globals().get::<_, Table>("string")?.get("find")?;
+                let functions = self.functions(task, |trigger| match trigger {
+                    Trigger::Line(pattern) => find
+                        .call::<_, Value>((*line, pattern.as_str()))
+                        .map(|found| !found.is_nil()),
+                    _ => Ok(false),
+                })?;
+                for function in functions {
+                    function.call::<_, ()>((*task, *line))?;
+                }
+            }
+            PluginEvent::Transition { task, from, to } => {
+                let failures = self.failures.entry(task.to_string()).or_default();
+                // a run which timed out has failed as well
+                let failed = matches!(to.as_str(), "failed" | "timed out");
+                if failed {
+                    *failures += 1;
+                } else if matches!(to.as_str(), "exited" | "ready") {
+                    *failures = 0;
+                }
+                let failures = *failures;
+                let entered = self.functions(task, |trigger| {
+                    Ok(matches!(trigger, Trigger::State(state) if state == to))
+                })?;
+                for function in entered {
+                    function.call::<_, ()>((*task, from.as_str()))?;
+                }
+                if failed {
+                    let failed = self.functions(task, |trigger| {
+                        Ok(matches!(trigger, Trigger::Failures(count) if *count == failures))
+                    })?;
+                    for function in failed {
+                        function.call::<_, ()>((*task, failures))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The functions of the rules for the task whose trigger matches. They are called once the
+    /// rules are unlocked again, so that they can add rules.
+    fn functions(
+        &self,
+        task: &str,
+        mut matches: impl FnMut(&Trigger) -> mlua::Result<bool>,
+    ) -> mlua::Result<Vec<Function<'_>>> {
+        let rules = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        let mut functions = Vec::new();
+        for rule in rules.iter() {
+            if (rule.task == "*" || rule.task == task) && matches(&rule.trigger)? {
+                functions.push(self.lua.registry_value(&rule.function)?);
+            }
+        }
+        Ok(functions)
+    }
+}
+
+/// Sets up the `parallely` table of the script with the functions which register rules and the
+/// actions.
+fn register(
+    lua: &Lua,
+    rules: &Arc<Mutex<Vec<Rule>>>,
+    message_sender: &MessageSender,
+    name: &str,
+) -> mlua::Result<()> {
+    let parallely = lua.create_table()?;
+    let triggers: [(&str, ParseTrigger); 3] = [
+        ("on_line", |value| string(value).map(Trigger::Line)),
+        ("on_state", |value| string(value).map(Trigger::State)),
+        ("on_failures", |value| match value {
+            Value::Integer(count) if count > 0 => Ok(Trigger::Failures(count as usize)),
+            _ => Err(mlua::Error::runtime("the count must be a positive integer")),
+        }),
+    ];
+    for (function_name, trigger) in triggers {
+        let rules = rules.clone();
+        let function = lua.create_function(
+            move |lua, (task, value, function): (String, Value, Function)| {
+                let rule = Rule {
+                    task,
+                    trigger: trigger(value)?,
+                    function: lua.create_registry_value(function)?,
+                };
+                rules.lock().unwrap_or_else(|e| e.into_inner()).push(rule);
+                Ok(())
+            },
+        )?;
+        parallely.set(function_name, function)?;
+    }
+
+    let actions: [(&str, Action); 5] = [
+        ("start", |task, _| PluginCommand::Start { task }),
+        ("restart", |task, _| PluginCommand::Restart { task }),
+        ("stop", |task, _| PluginCommand::Stop { task }),
+        ("annotate", |task, text| PluginCommand::Annotate {
+            task,
+            text: text.unwrap_or_default(),
+        }),
+        ("notify", |text, _| PluginCommand::Notify { text }),
+    ];
+    for (function_name, action) in actions {
+        let message_sender = message_sender.clone();
+        let name = name.to_string();
+        let function =
+            lua.create_function(move |_, (first, second): (String, Option<String>)| {
+                message_sender.send_plugin_command(name.clone(), action(first, second));
+                Ok(())
+            })?;
+        parallely.set(function_name, function)?;
+    }
+    lua.globals().set("parallely", parallely)
+}
+
+fn string(value: Value) -> mlua::Result<String> {
+    match value {
+        Value::String(string) => Ok(string.to_str()?.to_string()),
+        _ => Err(mlua::Error::runtime("expected a string")),
+    }
+}
@@ -0,0 +1,105 @@
+use crate::plugin::PluginError;
+use std::path::Path;
+use wasmi::{Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+/// How many instructions a processor may execute per line, so that a module which loops forever
+/// fails within a millisecond or so instead of holding up the output of the task.
+const FUEL_PER_LINE: u64 = 1_000_000;
+
+/// A compiled output processor, which is instantiated for every run of a task, see
+/// [`WasmModule::instantiate`].
+pub struct WasmModule {
+    name: String,
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmModule {
+    /// Compiles the module and instantiates it once, so that a module which does not implement
+    /// the interface of [`WasmProcessor`] is reported right away.
+    pub fn load(path: &Path) -> Result<Self, PluginError> {
+        let name = super::plugin_name(path);
+        let wasm = std::fs::read(path).map_err(|e| PluginError::Spawn(name.clone(), e))?;
+        let compile = || -> Result<WasmModule, wasmi::Error> {
+            let mut config = Config::default();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config);
+            let module = Module::new(&engine, &wasm)?;
+            Ok(WasmModule {
+                name: name.clone(),
+                engine,
+                module,
+            })
+        };
+        let module = compile().map_err(|e| PluginError::Wasm(name.clone(), e))?;
+        module
+            .instantiate()
+            .map_err(|e| PluginError::Wasm(name, e))?;
+        Ok(module)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// A processor of its own, so that the output of every task is processed on its own reader.
+    pub fn instantiate(&self) -> Result<WasmProcessor, wasmi::Error> {
+        let mut store = Store::new(&self.engine, ());
+        store.set_fuel(FUEL_PER_LINE)?;
+        let instance = Linker::new(&self.engine)
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| wasmi::Error::new("the module does not export `memory`"))?;
+        Ok(WasmProcessor {
+            name: self.name.clone(),
+            alloc: instance.get_typed_func(&store, "alloc")?,
+            process: instance.get_typed_func(&store, "process")?,
+            store,
+            memory,
+        })
+    }
+}
+
+/// A WebAssembly module which transforms every line of output, e.g. to redact secrets or
+/// reformat a log format, loaded from a `.wasm` file in the plugins directory. It imports
+/// nothing, so it can do nothing but compute, and implements the output processor interface by
+/// exporting
+///
+/// - `memory`,
+/// - `alloc(len: i32) -> i32`, which returns where parallely may write a line of `len` bytes,
+/// - `process(ptr: i32, len: i32) -> i64`, which is called with the UTF-8 line written there and
+///   returns where the line to show instead is as `ptr << 32 | len`, or a negative number to
+///   drop the line.
+pub struct WasmProcessor {
+    name: String,
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    process: TypedFunc<(i32, i32), i64>,
+}
+
+impl WasmProcessor {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The line to show instead of `line`, `None` to drop it.
+    pub fn process(&mut self, line: &str) -> Result<Option<String>, wasmi::Error> {
+        self.store.set_fuel(FUEL_PER_LINE)?;
+        let len = i32::try_from(line.len())
+            .map_err(|_| wasmi::Error::new("the line is too long for a 32-bit module"))?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, line.as_bytes())?;
+        let result = self.process.call(&mut self.store, (ptr, len))?;
+        if result < 0 {
+            return Ok(None);
+        }
+        let (ptr, len) = ((result >> 32) as u32 as usize, result as u32 as usize);
+        let mut buffer = vec![0; len];
+        self.memory.read(&self.store, ptr, &mut buffer)?;
+        Ok(Some(String::from_utf8_lossy(&buffer).into_owned()))
+    }
+}
@@ -0,0 +1,110 @@
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+/// How long a TCP probe waits for the connection to be accepted.
+const PORT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How long to wait between two checks of a probe which has not passed yet.
+pub const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Decides when a running task is ready, e.g. to start the tasks depending on it.
+///
+/// ```toml
+/// ready = { socket = "/var/run/postgresql/.s.PGSQL.5432" }
+/// ready = { file = "target/debug/app" }
+/// ready = { port = 6379 }
+/// ready = { line = "ready to accept connections" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum ReadyProbe {
+    /// Ready once the file exists.
+    File(PathBuf),
+    /// Ready once a connection to the unix socket is accepted.
+    Socket(PathBuf),
+    /// Ready once a TCP connection to the port on localhost is accepted.
+    Port(u16),
+    /// Ready once the task prints a line containing the text, checked by the console as the
+    /// output arrives.
+    Line(String),
+    /// Ready once the docker container is healthy, or if its image has no healthcheck, once the
+    /// port accepts connections, or else once the container is running. Only used by services,
+    /// see [`crate::config::ServiceConfig::ready`].
+    #[serde(skip)]
+    Container { name: String, port: Option<u16> },
+}
+
+impl ReadyProbe {
+    /// Whether the probe is checked by [`ReadyProbe::passes`], rather than by the console as the
+    /// output arrives.
+    pub fn is_polled(&self) -> bool {
+        !matches!(self, ReadyProbe::Line(_))
+    }
+
+    /// Checks the probe once, a TCP connection is given up on after [`PORT_TIMEOUT`].
+    pub async fn passes(&self) -> bool {
+        match self {
+            ReadyProbe::File(path) => tokio::fs::try_exists(path).await.unwrap_or(false),
+            #[cfg(unix)]
+            ReadyProbe::Socket(path) => tokio::net::UnixStream::connect(path).await.is_ok(),
+            #[cfg(not(unix))]
+            ReadyProbe::Socket(path) => tokio::fs::try_exists(path).await.unwrap_or(false),
+            ReadyProbe::Port(port) => port_accepts(*port).await,
+            ReadyProbe::Line(_) => false,
+            ReadyProbe::Container { name, port } => match container_health(name).await.as_deref() {
+                Some("healthy") => true,
+                Some("none") => match port {
+                    Some(port) => port_accepts(*port).await,
+                    None => true,
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+async fn port_accepts(port: u16) -> bool {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    matches!(
+        tokio::time::timeout(PORT_TIMEOUT, TcpStream::connect(address)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// The health status of a running container as reported by docker, `none` if its image has no
+/// healthcheck, or `None` if it is not running (yet).
+async fn container_health(name: &str) -> Option<String> {
+    let output = Command::new("docker")
+        .args([
+            "inspect",
+            "--format",
+            "{{if .State.Running}}{{if .State.Health}}{{.State.Health.Status}}{{else}}none{{end}}{{end}}",
+            name,
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+    let health = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !health.is_empty()).then_some(health)
+}
+
+impl Display for ReadyProbe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadyProbe::File(path) => write!(f, "file {}", path.display()),
+            ReadyProbe::Socket(path) => write!(f, "socket {}", path.display()),
+            ReadyProbe::Port(port) => write!(f, "port {}", port),
+            ReadyProbe::Line(text) => write!(f, "line {:?}", text),
+            ReadyProbe::Container { name, .. } => write!(f, "container {}", name),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::event::ParallelyEvent;
+use crate::report;
+use crate::task_executor::child_ext::ChildSignal;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, BorderType, Clear, HighlightSpacing, List, ListState, StatefulWidget, Widget,
+};
+use std::ffi::OsStr;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, Signal, System, UpdateKind};
+
+/// How often the processes and their usage are read again while the tree is shown.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The processes a process started, and the ones they started in turn, depth first. Each comes
+/// with how many parents it has below `parent`.
+pub fn descendants(system: &System, parent: Pid) -> Vec<(usize, &Process)> {
+    let mut descendants = Vec::new();
+    collect_descendants(system, parent, 0, &mut descendants);
+    descendants
+}
+
+fn collect_descendants<'a>(
+    system: &'a System,
+    parent: Pid,
+    depth: usize,
+    into: &mut Vec<(usize, &'a Process)>,
+) {
+    let mut children = system
+        .processes()
+        .values()
+        .filter(|process| process.parent() == Some(parent))
+        // threads show up as processes of their own on linux
+        .filter(|process| process.thread_kind().is_none())
+        .collect::<Vec<_>>();
+    children.sort_by_key(|process| process.pid());
+    for child in children {
+        into.push((depth, child));
+        collect_descendants(system, child.pid(), depth + 1, into);
+    }
+}
+
+/// The command line of a process, or its name if it cannot be read.
+pub fn command_line(process: &Process) -> String {
+    match process.cmd() {
+        [] => process.name().to_string_lossy().into_owned(),
+        cmd => shell_words(cmd),
+    }
+}
+
+/// The arguments joined with spaces, quoted where they contain whitespace.
+pub fn shell_words(args: &[impl AsRef<OsStr>]) -> String {
+    args.iter()
+        .map(|arg| {
+            let arg = arg.as_ref().to_string_lossy();
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("{:?}", arg)
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A row of the [`ProcessTree`], a task or one of its processes.
+struct TreeRow {
+    label: String,
+    /// How far the row is indented, 0 for a task.
+    depth: usize,
+    pid: Option<u32>,
+    cpu: f32,
+    rss: u64,
+}
+
+pub enum ProcessTreeAction {
+    None,
+    Close,
+    /// Pick a signal to send to the process.
+    Signal(u32),
+}
+
+/// A popup listing the processes of every task and the ones they started, with their CPU usage
+/// and resident memory, to find e.g. the grandchild which is stuck. The selected process can be
+/// sent a signal.
+pub struct ProcessTree {
+    system: System,
+    refreshed_at: Option<Instant>,
+    rows: Vec<TreeRow>,
+    state: ListState,
+}
+
+impl ProcessTree {
+    pub fn new() -> Self {
+        ProcessTree {
+            system: System::new(),
+            refreshed_at: None,
+            rows: Vec::new(),
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    /// Reads the processes of the tasks, given by their names and PIDs, again once
+    /// [`REFRESH_INTERVAL`] has passed. The CPU usage is known from the second read on.
+    pub fn refresh(&mut self, tasks: &[(String, Option<u32>)]) {
+        if self
+            .refreshed_at
+            .is_some_and(|at| at.elapsed() < REFRESH_INTERVAL)
+        {
+            return;
+        }
+        self.refreshed_at = Some(Instant::now());
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cpu()
+                .with_memory()
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+        let selected = self.selected();
+        self.rows.clear();
+        for (label, pid) in tasks {
+            let process = pid.and_then(|pid| self.system.process(Pid::from_u32(pid)));
+            self.rows.push(TreeRow {
+                label: label.clone(),
+                depth: 0,
+                pid: *pid,
+                cpu: process.map_or(0.0, Process::cpu_usage),
+                rss: process.map_or(0, Process::memory),
+            });
+            let Some(process) = process else {
+                continue;
+            };
+            for (depth, descendant) in descendants(&self.system, process.pid()) {
+                self.rows.push(TreeRow {
+                    label: command_line(descendant),
+                    depth: depth + 1,
+                    pid: Some(descendant.pid().as_u32()),
+                    cpu: descendant.cpu_usage(),
+                    rss: descendant.memory(),
+                });
+            }
+        }
+        // keep the selected process selected while processes come and go
+        if let Some(index) =
+            selected.and_then(|pid| self.rows.iter().position(|row| row.pid == Some(pid)))
+        {
+            self.state.select(Some(index));
+        }
+    }
+
+    /// The selected process as plain text, for the screen reader frontend. Leaves out the usage,
+    /// which would have it read out again every second.
+    pub fn describe(&self) -> String {
+        let selected = self.state.selected().unwrap_or(0);
+        let Some(row) = self.rows.get(selected) else {
+            return "Processes: none".to_string();
+        };
+        let process = match (row.pid, row.depth) {
+            (Some(pid), 0) => format!("task {}, pid {}", row.label, pid),
+            (Some(pid), depth) => format!("{}, pid {}, depth {}", row.label, pid, depth),
+            (None, _) => format!("task {}, not running", row.label),
+        };
+        format!(
+            "Processes: {} ({} of {})",
+            process,
+            selected + 1,
+            self.rows.len()
+        )
+    }
+
+    fn selected(&self) -> Option<u32> {
+        self.state
+            .selected()
+            .and_then(|index| self.rows.get(index))
+            .and_then(|row| row.pid)
+    }
+
+    /// Sends the signal to the process, if it is still one of the processes of the tasks.
+    pub fn signal(&self, pid: u32, signal: ChildSignal) -> Result<(), String> {
+        if !self.rows.iter().any(|row| row.pid == Some(pid)) {
+            return Err(format!("Process {} is gone", pid));
+        }
+        let signal = match signal {
+            ChildSignal::Interrupt => Signal::Interrupt,
+            ChildSignal::Quit => Signal::Quit,
+            ChildSignal::Terminate => Signal::Term,
+            ChildSignal::Hangup => Signal::Hangup,
+            ChildSignal::User1 => Signal::User1,
+            ChildSignal::User2 => Signal::User2,
+            ChildSignal::Kill => Signal::Kill,
+            ChildSignal::Other(_) => return Err(format!("Cannot send {} to a process", signal)),
+        };
+        let process = self
+            .system
+            .process(Pid::from_u32(pid))
+            .ok_or_else(|| format!("Process {} is gone", pid))?;
+        match process.kill_with(signal) {
+            Some(true) => Ok(()),
+            Some(false) => Err(format!("Failed to send {} to process {}", signal, pid)),
+            None => Err(format!("{} is not supported on this platform", signal)),
+        }
+    }
+
+    /// `Up`/`Down` select a process, `S` picks a signal to send to it, `Esc`, `q` or `P` close.
+    /// Consumes every key event without a `Control` modifier, so that global shortcuts such as
+    /// `Ctrl+C` keep working.
+    pub fn handle_event(&mut self, event: &mut ParallelyEvent) -> ProcessTreeAction {
+        if let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                return ProcessTreeAction::None;
+            }
+            let action = match code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.state.select_previous();
+                    ProcessTreeAction::None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.state.select_next();
+                    ProcessTreeAction::None
+                }
+                KeyCode::Char('S') | KeyCode::Enter => match self.selected() {
+                    Some(pid) => ProcessTreeAction::Signal(pid),
+                    None => ProcessTreeAction::None,
+                },
+                KeyCode::Esc | KeyCode::Char('q' | 'P') => ProcessTreeAction::Close,
+                _ => ProcessTreeAction::None,
+            };
+            event.stop_propagation();
+            return action;
+        }
+        ProcessTreeAction::None
+    }
+}
+
+impl Widget for &mut ProcessTree {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let height = (self.rows.len() as u16 + 2).min(area.height);
+        let [popup_area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(90)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::bordered()
+            .title(" Processes ".magenta().bold())
+            .title_bottom(Line::from(vec![
+                " Select ".into(),
+                "<Up/Down> ".blue().bold(),
+                " Signal ".into(),
+                "<S> ".blue().bold(),
+                " Close ".into(),
+                "<Esc> ".blue().bold(),
+            ]))
+            .border_type(BorderType::Rounded);
+        let items = self.rows.iter().map(|row| {
+            let usage = match row.pid {
+                Some(pid) => format!(
+                    "{:>8} {:>6.1}% {:>10}  ",
+                    pid,
+                    row.cpu,
+                    report::format_bytes(row.rss)
+                ),
+                None => format!("{:>8} {:>7} {:>10}  ", "-", "", ""),
+            };
+            let label = match row.depth {
+                0 => Span::from(row.label.clone()).blue().bold(),
+                depth => Span::raw(format!("{}└ {}", "  ".repeat(depth - 1), row.label)),
+            };
+            Line::from(vec![usage.dark_gray(), label])
+        });
+        let list = List::new(items)
+            .block(block)
+            .highlight_symbol("> ")
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_style(Style::new().reversed());
+
+        Clear.render(popup_area, buf);
+        StatefulWidget::render(list, popup_area, buf, &mut self.state);
+    }
+}
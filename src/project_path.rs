@@ -0,0 +1,57 @@
+use crate::config::TaskConfig;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// The directory of the executables in a Python virtual environment.
+#[cfg(not(windows))]
+const VENV_BIN: &str = "bin";
+#[cfg(windows)]
+const VENV_BIN: &str = "Scripts";
+/// The names a virtual environment is looked for by, next to the project.
+const VENV_NAMES: [&str; 2] = [".venv", "venv"];
+
+/// The variables which put the executables of the project first on `PATH` for the task, with
+/// [`TaskConfig::node_modules`] the `node_modules/.bin` of the working directory and of its
+/// ancestors (the nearest first, as `npm run` does), with [`TaskConfig::venv`] the executables of
+/// the nearest virtual environment, which is also set as `VIRTUAL_ENV` as activating it would.
+/// Empty if neither is turned on or nothing is found.
+pub fn env(task: &TaskConfig) -> Vec<(&'static str, OsString)> {
+    if !task.node_modules && !task.venv {
+        return Vec::new();
+    }
+    // absolute, so that its ancestors are searched as well
+    let cwd = std::env::current_dir()
+        .unwrap_or_default()
+        .join(task.cwd.as_deref().unwrap_or(Path::new("")));
+    let mut env = Vec::new();
+    let mut dirs = Vec::new();
+    if task.venv {
+        if let Some(venv) = find_venv(&cwd) {
+            dirs.push(venv.join(VENV_BIN));
+            env.push(("VIRTUAL_ENV", venv.into_os_string()));
+        }
+    }
+    if task.node_modules {
+        dirs.extend(
+            cwd.ancestors()
+                .map(|dir| dir.join("node_modules").join(".bin"))
+                .filter(|dir| dir.is_dir()),
+        );
+    }
+    if dirs.is_empty() {
+        return env;
+    }
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    dirs.extend(std::env::split_paths(&path));
+    if let Ok(path) = std::env::join_paths(dirs) {
+        env.push(("PATH", path));
+    }
+    env
+}
+
+/// The nearest directory which is a virtual environment, told by its `pyvenv.cfg`.
+fn find_venv(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .flat_map(|dir| VENV_NAMES.iter().map(move |name| dir.join(name)))
+        .find(|venv| venv.join("pyvenv.cfg").is_file())
+}
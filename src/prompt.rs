@@ -0,0 +1,182 @@
+use crate::event::ParallelyEvent;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, BorderType, Clear, Paragraph, Widget};
+
+/// A popup single-line text input. `T` describes what the input is for and is handed back on
+/// submit.
+pub struct Prompt<T> {
+    title: String,
+    input: String,
+    /// Where the next character is inserted, counted in characters.
+    cursor: usize,
+    /// Earlier inputs recalled with `Up` and `Down`, the most recent last.
+    history: Vec<String>,
+    /// Which entry of the history is shown, `None` while editing the input.
+    history_index: Option<usize>,
+    purpose: T,
+}
+
+pub enum PromptAction<T> {
+    None,
+    Submitted(T, String),
+    Cancelled,
+}
+
+impl<T> Prompt<T>
+where
+    T: Clone,
+{
+    pub fn new(title: impl Into<String>, purpose: T) -> Self {
+        Self::with_input(title, String::new(), purpose)
+    }
+
+    pub fn with_input(title: impl Into<String>, input: String, purpose: T) -> Self {
+        Self {
+            title: title.into(),
+            cursor: input.chars().count(),
+            input,
+            history: Vec::new(),
+            history_index: None,
+            purpose,
+        }
+    }
+
+    /// Lets `Up` and `Down` walk through earlier inputs, the most recent last.
+    pub fn with_history(mut self, history: Vec<String>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// The title and the input as plain text, for the screen reader frontend.
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.title, self.input)
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.input.len(), |(index, _)| index)
+    }
+
+    fn recall(&mut self, index: Option<usize>) {
+        if let Some(entry) = index.and_then(|index| self.history.get(index)) {
+            self.input = entry.clone();
+            self.cursor = self.input.chars().count();
+            self.history_index = index;
+        }
+    }
+
+    /// Consumes every key event without a `Control` modifier, so that global shortcuts such as
+    /// `Ctrl+C` keep working while the prompt is open.
+    pub fn handle_event(&mut self, event: &mut ParallelyEvent) -> PromptAction<T> {
+        if let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event.as_ref()
+        {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                return PromptAction::None;
+            }
+            let action = match code {
+                KeyCode::Char(c) => {
+                    let index = self.byte_index(self.cursor);
+                    self.input.insert(index, *c);
+                    self.cursor += 1;
+                    PromptAction::None
+                }
+                KeyCode::Backspace => {
+                    if self.cursor > 0 {
+                        self.cursor -= 1;
+                        let index = self.byte_index(self.cursor);
+                        self.input.remove(index);
+                    }
+                    PromptAction::None
+                }
+                KeyCode::Delete => {
+                    if self.cursor < self.input.chars().count() {
+                        let index = self.byte_index(self.cursor);
+                        self.input.remove(index);
+                    }
+                    PromptAction::None
+                }
+                KeyCode::Left => {
+                    self.cursor = self.cursor.saturating_sub(1);
+                    PromptAction::None
+                }
+                KeyCode::Right => {
+                    self.cursor = (self.cursor + 1).min(self.input.chars().count());
+                    PromptAction::None
+                }
+                KeyCode::Home => {
+                    self.cursor = 0;
+                    PromptAction::None
+                }
+                KeyCode::End => {
+                    self.cursor = self.input.chars().count();
+                    PromptAction::None
+                }
+                KeyCode::Up => {
+                    let index = match self.history_index {
+                        Some(index) => index.checked_sub(1),
+                        None => self.history.len().checked_sub(1),
+                    };
+                    self.recall(index);
+                    PromptAction::None
+                }
+                KeyCode::Down => {
+                    self.recall(self.history_index.map(|index| index + 1));
+                    PromptAction::None
+                }
+                KeyCode::Enter => {
+                    PromptAction::Submitted(self.purpose.clone(), self.input.trim().to_string())
+                }
+                KeyCode::Esc => PromptAction::Cancelled,
+                _ => PromptAction::None,
+            };
+            event.stop_propagation();
+            return action;
+        }
+        PromptAction::None
+    }
+}
+
+impl<T> Widget for &Prompt<T> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let width = (self.input.chars().count().max(self.title.chars().count()) as u16 + 6)
+            .clamp(40, area.width);
+        let [popup_area] = Layout::vertical([Constraint::Length(3)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::bordered()
+            .title(format!(" {} ", self.title).magenta().bold())
+            .border_type(BorderType::Rounded);
+        // keep the cursor visible when the input is wider than the popup
+        let visible_width = block.inner(popup_area).width.saturating_sub(1) as usize;
+        let skip = self.cursor.saturating_sub(visible_width);
+        let mut chars = self.input.chars().skip(skip);
+        let before = chars.by_ref().take(self.cursor - skip).collect::<String>();
+        let under = chars.next().map_or(" ".to_string(), String::from);
+        let input = Line::from(vec![
+            before.into(),
+            under.reversed(),
+            chars.collect::<String>().into(),
+        ]);
+
+        Clear.render(popup_area, buf);
+        Paragraph::new(input).block(block).render(popup_area, buf);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::task_executor::{CpuTime, IoStats, TaskStatus};
+use crate::test_summary::TestSummary;
+use color_eyre::eyre::WrapErr;
+use serde_json::json;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What is known about a task when parallely exits.
+pub struct TaskReport {
+    pub command: String,
+    pub status: color_eyre::Result<TaskStatus>,
+    pub test_summary: Option<TestSummary>,
+    /// When the last run of the task was started.
+    pub started_at: Option<SystemTime>,
+    pub exited_at: Option<SystemTime>,
+    /// Peak resident set size in bytes, see [`crate::task_executor::TaskExecutor::peak_rss`].
+    pub peak_rss: Option<u64>,
+    /// The CPU time the last run used, see [`crate::task_executor::TaskExecutor::cpu_time`].
+    pub cpu_time: Option<CpuTime>,
+    /// What the last run read and wrote, see [`crate::task_executor::TaskExecutor::io_stats`].
+    pub io_stats: Option<IoStats>,
+    /// How many lines of output were dropped from the console, see
+    /// [`crate::config::TaskConfig::max_lines`].
+    pub dropped_lines: usize,
+    /// How many lines and bytes of output were received over all runs, see
+    /// [`crate::console::Console::received`].
+    pub received_lines: u64,
+    pub received_bytes: u64,
+    /// The last lines of output, see [`crate::summary::FAILED_TAIL_LINES`].
+    pub last_lines: Vec<String>,
+}
+
+impl TaskReport {
+    pub fn duration(&self) -> Option<Duration> {
+        self.exited_at?.duration_since(self.started_at?).ok()
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. `12.3 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Prints a table of when each task started and ended relative to `session_start`.
+pub fn print_timings(reports: &[TaskReport], session_start: SystemTime) {
+    let total = SystemTime::now()
+        .duration_since(session_start)
+        .unwrap_or_default();
+    let offset = |time: Option<SystemTime>| match time {
+        Some(time) => format!(
+            "{:.2}s",
+            time.duration_since(session_start)
+                .unwrap_or_default()
+                .as_secs_f64()
+        ),
+        None => "-".to_string(),
+    };
+    let width = reports
+        .iter()
+        .map(|report| report.command.chars().count())
+        .chain(std::iter::once("TASK".len()))
+        .max()
+        .unwrap_or(0);
+
+    println!("\nTimings (total {:.2}s)", total.as_secs_f64());
+    println!(
+        "{:<width$}  {:>9}  {:>9}  {:>9}",
+        "TASK", "START", "END", "DURATION"
+    );
+    for report in reports {
+        let duration = report
+            .duration()
+            .map(|duration| format!("{:.2}s", duration.as_secs_f64()))
+            .unwrap_or("-".to_string());
+        println!(
+            "{:<width$}  {:>9}  {:>9}  {:>9}",
+            report.command,
+            offset(report.started_at),
+            offset(report.exited_at),
+            duration
+        );
+    }
+}
+
+/// Writes the reports as JSON, times are milliseconds since the unix epoch.
+pub fn write_json(
+    reports: &[TaskReport],
+    session_start: SystemTime,
+    path: &Path,
+) -> color_eyre::Result<()> {
+    let millis = |time: Option<SystemTime>| {
+        time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as u64)
+    };
+    let tasks = reports
+        .iter()
+        .map(|report| {
+            let net = report.io_stats.and_then(|io_stats| io_stats.net);
+            json!({
+                "command": report.command,
+                "status": match &report.status {
+                    Ok(status) => status.to_string(),
+                    Err(error) => error.to_string(),
+                },
+                "start_ms": millis(report.started_at),
+                "end_ms": millis(report.exited_at),
+                "duration_ms": report.duration().map(|duration| duration.as_millis() as u64),
+                "peak_rss_bytes": report.peak_rss,
+                "cpu_user_ms": report.cpu_time.map(|cpu_time| cpu_time.user.as_millis() as u64),
+                "cpu_system_ms": report.cpu_time.map(|cpu_time| cpu_time.system.as_millis() as u64),
+                "disk_read_bytes": report.io_stats.map(|io| io.read_bytes),
+                "disk_write_bytes": report.io_stats.map(|io| io.write_bytes),
+                "net_received_bytes": net.map(|net| net.received_bytes),
+                "net_sent_bytes": net.map(|net| net.sent_bytes),
+                "dropped_lines": report.dropped_lines,
+                "received_lines": report.received_lines,
+                "received_bytes": report.received_bytes,
+                "tests": report.test_summary.map(|summary| json!({
+                    "passed": summary.passed,
+                    "failed": summary.failed,
+                    "skipped": summary.skipped,
+                })),
+            })
+        })
+        .collect::<Vec<_>>();
+    let now = SystemTime::now();
+    let json = json!({
+        "start_ms": millis(Some(session_start)),
+        "end_ms": millis(Some(now)),
+        "duration_ms": now.duration_since(session_start).unwrap_or_default().as_millis() as u64,
+        "tasks": tasks,
+    });
+    std::fs::write(path, serde_json::to_string_pretty(&json)?)
+        .wrap_err_with(|| format!("Failed to write timings to {}", path.display()))
+}
@@ -0,0 +1,150 @@
+use crate::builtin;
+use crate::config::TaskConfig;
+use crate::process_tree;
+use crate::project_path;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// Where the program of a command is found, to tell why a command which works in a shell does
+/// not in parallely, e.g. because another `PATH` is in effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// Found in the directory of `PATH`.
+    Path { executable: PathBuf, dir: PathBuf },
+    /// Given as a path, relative to the working directory of the task.
+    Direct { executable: PathBuf, exists: bool },
+    /// Not in any directory of `PATH`.
+    NotFound,
+}
+
+/// The program a command runs and its arguments as they are passed, see [`resolve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub resolution: Resolution,
+}
+
+/// Finds the program of the command the way it is spawned: in the directories of the `PATH` the
+/// command is given or else the one of parallely, or relative to its working directory if it
+/// is a path.
+pub fn resolve(command: &Command) -> ResolvedCommand {
+    let program = command.get_program();
+    let args = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let cwd = command
+        .get_current_dir()
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let path = command
+        .get_envs()
+        .find(|(name, _)| *name == "PATH")
+        .map_or_else(
+            || std::env::var_os("PATH"),
+            |(_, value)| value.map(OsString::from),
+        );
+    ResolvedCommand {
+        program: program.to_string_lossy().into_owned(),
+        args,
+        resolution: resolve_program(program, &cwd, path.as_deref()),
+    }
+}
+
+/// Resolves the command of the task as it would be spawned, for `--dry-run`.
+pub fn resolve_task(task: &TaskConfig) -> ResolvedCommand {
+    let (program, args) = builtin::command_line(&task.command.to_string(), task.shell);
+    let mut command = Command::new(program);
+    command.args(args);
+    if let Some(cwd) = task.cwd.as_ref() {
+        command.current_dir(cwd);
+    }
+    command.envs(project_path::env(task));
+    resolve(&command)
+}
+
+fn resolve_program(program: &OsStr, cwd: &Path, path: Option<&OsStr>) -> Resolution {
+    let given = Path::new(program);
+    if given.components().count() > 1 || given.is_absolute() {
+        let given = given
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect::<PathBuf>();
+        let executable = cwd.join(given);
+        let exists = executable.is_file();
+        return Resolution::Direct { executable, exists };
+    }
+    let dirs = path.map(std::env::split_paths).into_iter().flatten();
+    for dir in dirs {
+        let dir = match dir.is_relative() {
+            true => cwd.join(dir),
+            false => dir,
+        };
+        if let Some(executable) = executable_in(&dir, program) {
+            return Resolution::Path { executable, dir };
+        }
+    }
+    Resolution::NotFound
+}
+
+#[cfg(unix)]
+fn executable_in(dir: &Path, program: &OsStr) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    let candidate = dir.join(program);
+    let metadata = std::fs::metadata(&candidate).ok()?;
+    (metadata.is_file() && metadata.permissions().mode() & 0o111 != 0).then_some(candidate)
+}
+
+/// Tries the extensions of `PATHEXT` as well, e.g. `npm.cmd` for `npm`.
+#[cfg(windows)]
+fn executable_in(dir: &Path, program: &OsStr) -> Option<PathBuf> {
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into());
+    std::iter::once(dir.join(program))
+        .chain(extensions.split(';').map(|extension| {
+            let mut name = program.to_os_string();
+            name.push(extension.to_lowercase());
+            dir.join(name)
+        }))
+        .find(|candidate| candidate.is_file())
+}
+
+impl ResolvedCommand {
+    /// The executable followed by the arguments, e.g. `/usr/bin/npm run dev`.
+    pub fn command_line(&self) -> String {
+        let executable = match &self.resolution {
+            Resolution::Path { executable, .. } | Resolution::Direct { executable, .. } => {
+                executable.to_string_lossy().into_owned()
+            }
+            Resolution::NotFound => self.program.clone(),
+        };
+        let words = std::iter::once(executable).chain(self.args.iter().cloned());
+        process_tree::shell_words(&words.collect::<Vec<_>>())
+    }
+}
+
+/// E.g. `/usr/bin/npm run dev (npm found on PATH in /usr/bin)`.
+impl Display for ResolvedCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let command_line = self.command_line();
+        match &self.resolution {
+            Resolution::Path { dir, .. } => write!(
+                f,
+                "{} ({} found on PATH in {})",
+                command_line,
+                self.program,
+                dir.display()
+            ),
+            Resolution::Direct { exists: true, .. } => write!(f, "{}", command_line),
+            Resolution::Direct { exists: false, .. } => {
+                write!(f, "{} (no such file)", command_line)
+            }
+            Resolution::NotFound => {
+                write!(f, "{} ({} not found on PATH)", command_line, self.program)
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::config::TaskConfig;
+use crate::history;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{
+    Block, BorderType, HighlightSpacing, List, ListState, StatefulWidget, Widget,
+};
+use ratatui::DefaultTerminal;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use tokio_stream::StreamExt;
+
+/// From how many tasks on the tasks to run are picked at startup, see `--all`.
+pub const SELECT_THRESHOLD: usize = 10;
+
+/// Lets the user pick which of many tasks to run this session with a checkbox list. The
+/// selection is saved for `key` (see [`crate::parallely::Parallely::instance_key`]) and
+/// preselected the next time, every task is selected the first time. The tasks the selected
+/// ones depend on are run as well. Returns `None` if the user cancelled.
+pub async fn run(tasks: Vec<TaskConfig>, key: &str) -> color_eyre::Result<Option<Vec<TaskConfig>>> {
+    let mut saved = load();
+    let selected = match saved.get(key) {
+        Some(names) => tasks
+            .iter()
+            .map(|task| names.contains(task.key()))
+            .collect(),
+        None => vec![true; tasks.len()],
+    };
+    let mut terminal = ratatui::try_init()?;
+    let screen = SelectionScreen {
+        tasks,
+        selected,
+        state: ListState::default().with_selected(Some(0)),
+    };
+    let result = screen.run(&mut terminal).await;
+    ratatui::try_restore()?;
+    let Some(tasks) = result? else {
+        return Ok(None);
+    };
+    saved.insert(
+        key.to_string(),
+        tasks.iter().map(|task| task.key().to_string()).collect(),
+    );
+    if let Err(e) = save(&saved) {
+        eprintln!("Failed to save the selection of tasks: {:#}", e);
+    }
+    Ok(Some(tasks))
+}
+
+/// Where the selections are kept, next to the history.
+fn path() -> Option<PathBuf> {
+    history::path().and_then(|path| path.parent().map(|dir| dir.join("selections.json")))
+}
+
+fn load() -> BTreeMap<String, BTreeSet<String>> {
+    path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(selections: &BTreeMap<String, BTreeSet<String>>) -> color_eyre::Result<()> {
+    let Some(path) = path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(selections)?)?;
+    Ok(())
+}
+
+struct SelectionScreen {
+    tasks: Vec<TaskConfig>,
+    selected: Vec<bool>,
+    state: ListState,
+}
+
+enum SelectionAction {
+    None,
+    Launch,
+    Cancel,
+}
+
+impl SelectionScreen {
+    async fn run(
+        mut self,
+        terminal: &mut DefaultTerminal,
+    ) -> color_eyre::Result<Option<Vec<TaskConfig>>> {
+        let mut events = EventStream::new();
+        loop {
+            terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
+            let Some(event) = events.next().await else {
+                return Ok(None);
+            };
+            match self.handle_event(event?) {
+                SelectionAction::None => {}
+                SelectionAction::Launch => return Ok(Some(self.into_tasks())),
+                SelectionAction::Cancel => return Ok(None),
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> SelectionAction {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        else {
+            return SelectionAction::None;
+        };
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                return SelectionAction::Cancel
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return SelectionAction::Cancel,
+            KeyCode::Enter if self.selected.contains(&true) => return SelectionAction::Launch,
+            KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+            KeyCode::Char(' ') => {
+                if let Some(selected) = self
+                    .state
+                    .selected()
+                    .and_then(|index| self.selected.get_mut(index))
+                {
+                    *selected = !*selected;
+                }
+                self.state.select_next();
+            }
+            KeyCode::Char('a') => {
+                let all = !self.selected.iter().all(|selected| *selected);
+                self.selected.fill(all);
+            }
+            _ => {}
+        }
+        SelectionAction::None
+    }
+
+    /// The selected tasks along with the tasks they depend on, in their configured order.
+    fn into_tasks(self) -> Vec<TaskConfig> {
+        let mut needed = self.selected;
+        let mut pending = (0..needed.len())
+            .filter(|&index| needed[index])
+            .collect::<Vec<_>>();
+        while let Some(index) = pending.pop() {
+            for dependency in self.tasks[index].depends_on.iter() {
+                if let Some(position) = self
+                    .tasks
+                    .iter()
+                    .position(|task| task.key() == dependency)
+                    .filter(|&position| !needed[position])
+                {
+                    needed[position] = true;
+                    pending.push(position);
+                }
+            }
+        }
+        self.tasks
+            .into_iter()
+            .zip(needed)
+            .filter(|(_, needed)| *needed)
+            .map(|(task, _)| task)
+            .collect()
+    }
+}
+
+impl Widget for &mut SelectionScreen {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let height = (self.tasks.len() as u16 + 2).min(area.height);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let count = self.selected.iter().filter(|selected| **selected).count();
+        let block = Block::bordered()
+            .title(
+                format!(" Tasks to run ({}/{}) ", count, self.tasks.len())
+                    .magenta()
+                    .bold(),
+            )
+            .title_bottom(Line::from(vec![
+                " Toggle ".into(),
+                "<Space> ".blue().bold(),
+                " All/None ".into(),
+                "<a> ".blue().bold(),
+                " Launch ".into(),
+                "<Enter> ".blue().bold(),
+                " Cancel ".into(),
+                "<Esc> ".blue().bold(),
+            ]))
+            .border_type(BorderType::Rounded);
+        let items = self
+            .tasks
+            .iter()
+            .zip(self.selected.iter())
+            .map(|(task, selected)| {
+                let checkbox = if *selected { "[x] " } else { "[ ] " };
+                let mut line = Line::from(vec![checkbox.green().bold(), task.key().into()]);
+                if task.name.is_some() {
+                    line.push_span(format!("  {}", task.command).dark_gray());
+                }
+                line
+            });
+        let list = List::new(items)
+            .block(block)
+            .highlight_symbol("> ")
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_style(Style::new().reversed());
+        StatefulWidget::render(list, area, buf, &mut self.state);
+    }
+}
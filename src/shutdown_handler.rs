@@ -1,8 +1,21 @@
 use crate::event::ParallelyEvent;
 use crate::message::MessageSender;
+#[cfg(unix)]
+use crate::task_executor::child_ext::ChildSignal;
+use clap::ValueEnum;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use tokio::signal;
 
+/// What parallely does with the tasks when the terminal it runs in is closed.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum HangupAction {
+    /// Stop the tasks as on quitting.
+    #[default]
+    Stop,
+    /// Leave the tasks running, without reading their output anymore.
+    Detach,
+}
+
 #[derive(Clone)]
 pub struct ShutdownHandler {
     message_sender: MessageSender,
@@ -15,7 +28,7 @@ impl ShutdownHandler {
 
     pub fn listen_for_signal(&self) {
         let message_sender = self.message_sender.clone();
-        tokio::spawn(async move {
+        self.message_sender.supervisor().spawn(async move {
             if let Err(e) = Self::listen_for_signal_inner(message_sender.clone()).await {
                 message_sender.send_error(e);
             }
@@ -42,6 +55,66 @@ impl ShutdownHandler {
         Ok(())
     }
 
+    /// Shuts down as told by `on_hangup` when parallely receives `SIGHUP` because the terminal
+    /// has been closed. Any other `SIGHUP` requests a reload with `reload`, else shuts down too.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    pub fn listen_for_hangup(&self, on_hangup: HangupAction, reload: bool) {
+        #[cfg(unix)]
+        {
+            let message_sender = self.message_sender.clone();
+            self.message_sender.supervisor().spawn(async move {
+                match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+                    Ok(mut hangup) => {
+                        while hangup.recv().await.is_some() {
+                            if reload && !terminal_closed() {
+                                message_sender.send_reload();
+                            } else {
+                                message_sender.send_shutdown(ShutdownReason::Hangup(on_hangup));
+                            }
+                        }
+                    }
+                    Err(e) => message_sender.send_error(e),
+                }
+            });
+        }
+    }
+
+    /// Passes `SIGHUP`, `SIGUSR1` and `SIGUSR2` received by parallely on as
+    /// [`crate::message::Message::Signal`] instead of their default action, see
+    /// `--forward-signals`. A `SIGHUP` because the terminal has been closed shuts down as told
+    /// by `on_hangup` instead.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    pub fn listen_for_forwarded_signals(&self, on_hangup: HangupAction) {
+        #[cfg(unix)]
+        for (kind, signal) in [
+            (signal::unix::SignalKind::hangup(), ChildSignal::Hangup),
+            (
+                signal::unix::SignalKind::user_defined1(),
+                ChildSignal::User1,
+            ),
+            (
+                signal::unix::SignalKind::user_defined2(),
+                ChildSignal::User2,
+            ),
+        ] {
+            let message_sender = self.message_sender.clone();
+            self.message_sender.supervisor().spawn(async move {
+                match signal::unix::signal(kind) {
+                    Ok(mut stream) => {
+                        while stream.recv().await.is_some() {
+                            if signal == ChildSignal::Hangup && terminal_closed() {
+                                message_sender.send_shutdown(ShutdownReason::Hangup(on_hangup));
+                            } else {
+                                message_sender.send_signal(signal);
+                            }
+                        }
+                    }
+                    Err(e) => message_sender.send_error(e),
+                }
+            });
+        }
+    }
+
     pub fn handle_event(&mut self, event: &mut ParallelyEvent) {
         if let Event::Key(KeyEvent {
             code,
@@ -77,4 +150,44 @@ pub enum ShutdownReason {
     CtrlC,
     Quit,
     End,
+    /// The task at the index failed with `--cancel-on-failure`.
+    Failure(usize),
+    /// The terminal has been closed, see `--on-hangup`.
+    Hangup(HangupAction),
+}
+
+impl ShutdownReason {
+    /// Whether the tasks are left running instead of being stopped.
+    pub fn detaches(&self) -> bool {
+        matches!(self, ShutdownReason::Hangup(HangupAction::Detach))
+    }
+}
+
+/// Sends everything parallely still writes to stdout and stderr to `/dev/null`, since writing
+/// to a closed terminal fails.
+pub fn discard_output() {
+    #[cfg(unix)]
+    if let Ok(null) = std::fs::OpenOptions::new().write(true).open("/dev/null") {
+        use std::os::fd::AsRawFd;
+        unsafe {
+            libc::dup2(null.as_raw_fd(), libc::STDOUT_FILENO);
+            libc::dup2(null.as_raw_fd(), libc::STDERR_FILENO);
+        }
+    }
+}
+
+/// Whether the controlling terminal of parallely has been closed (hung up), after which it
+/// cannot be opened anymore.
+#[cfg(unix)]
+pub fn terminal_closed() -> bool {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .is_err()
+}
+
+#[cfg(not(unix))]
+pub fn terminal_closed() -> bool {
+    false
 }
@@ -0,0 +1,247 @@
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Copy, Clone)]
+pub enum SnapshotFormat {
+    /// Plain text with ANSI escape sequences, can be replayed with `cat`.
+    Ansi,
+    /// A standalone HTML page.
+    Html,
+}
+
+impl SnapshotFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Ansi => "ans",
+            SnapshotFormat::Html => "html",
+        }
+    }
+}
+
+/// Writes the buffer of a rendered frame into `parallely-snapshot-<timestamp>.<ext>` in the
+/// current directory, preserving colors and layout. Returns the path of the written file.
+pub fn write(buffer: &Buffer, format: SnapshotFormat) -> color_eyre::Result<PathBuf> {
+    let content = match format {
+        SnapshotFormat::Ansi => to_ansi(buffer),
+        SnapshotFormat::Html => to_html(buffer),
+    };
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let path = std::env::current_dir()?.join(format!(
+        "parallely-snapshot-{}.{}",
+        timestamp,
+        format.extension()
+    ));
+    std::fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// Iterates the rows of the buffer, skipping the cells covered by wide characters.
+fn rows(buffer: &Buffer) -> impl Iterator<Item = Vec<&Cell>> {
+    buffer
+        .content
+        .chunks(buffer.area.width.max(1) as usize)
+        .map(|row| {
+            let mut cells = Vec::with_capacity(row.len());
+            let mut covered = 0;
+            for cell in row {
+                if covered > 0 {
+                    covered -= 1;
+                    continue;
+                }
+                covered = textwrap::core::display_width(cell.symbol()).saturating_sub(1);
+                cells.push(cell);
+            }
+            cells
+        })
+}
+
+fn to_ansi(buffer: &Buffer) -> String {
+    let mut output = String::new();
+    for row in rows(buffer) {
+        let mut previous = None;
+        for cell in row {
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if previous != Some(style) {
+                output.push_str(&sgr(cell.fg, cell.bg, cell.modifier));
+                previous = Some(style);
+            }
+            output.push_str(cell.symbol());
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, param) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifier.contains(flag) {
+            params.push(param.to_string());
+        }
+    }
+    if let Some(fg) = sgr_color(fg, 30) {
+        params.push(fg);
+    }
+    if let Some(bg) = sgr_color(bg, 40) {
+        params.push(bg);
+    }
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// `base` is 30 for foreground and 40 for background colors.
+fn sgr_color(color: Color, base: u8) -> Option<String> {
+    let param = match color {
+        Color::Reset => return None,
+        Color::Black => format!("{}", base),
+        Color::Red => format!("{}", base + 1),
+        Color::Green => format!("{}", base + 2),
+        Color::Yellow => format!("{}", base + 3),
+        Color::Blue => format!("{}", base + 4),
+        Color::Magenta => format!("{}", base + 5),
+        Color::Cyan => format!("{}", base + 6),
+        Color::Gray => format!("{}", base + 7),
+        Color::DarkGray => format!("{}", base + 60),
+        Color::LightRed => format!("{}", base + 61),
+        Color::LightGreen => format!("{}", base + 62),
+        Color::LightYellow => format!("{}", base + 63),
+        Color::LightBlue => format!("{}", base + 64),
+        Color::LightMagenta => format!("{}", base + 65),
+        Color::LightCyan => format!("{}", base + 66),
+        Color::White => format!("{}", base + 67),
+        Color::Indexed(index) => format!("{};5;{}", base + 8, index),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+    };
+    Some(param)
+}
+
+fn to_html(buffer: &Buffer) -> String {
+    let mut output = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>parallely snapshot</title></head>\n",
+        "<body style=\"background:#000000\">\n",
+        "<pre style=\"color:#e5e5e5;background:#000000;font-family:monospace;line-height:1.2\">\n",
+    ));
+    for row in rows(buffer) {
+        let mut previous = None;
+        for cell in row {
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if previous != Some(style) {
+                if previous.is_some() {
+                    output.push_str("</span>");
+                }
+                let _ = write!(
+                    output,
+                    "<span style=\"{}\">",
+                    css(cell.fg, cell.bg, cell.modifier)
+                );
+                previous = Some(style);
+            }
+            for c in cell.symbol().chars() {
+                match c {
+                    '&' => output.push_str("&amp;"),
+                    '<' => output.push_str("&lt;"),
+                    '>' => output.push_str("&gt;"),
+                    c => output.push(c),
+                }
+            }
+        }
+        if previous.is_some() {
+            output.push_str("</span>");
+        }
+        output.push('\n');
+    }
+    output.push_str("</pre>\n</body>\n</html>\n");
+    output
+}
+
+fn css(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let (fg, bg) = if modifier.contains(Modifier::REVERSED) {
+        (
+            rgb(bg).unwrap_or((0x00, 0x00, 0x00)),
+            rgb(fg).or(Some((0xe5, 0xe5, 0xe5))),
+        )
+    } else {
+        (rgb(fg).unwrap_or((0xe5, 0xe5, 0xe5)), rgb(bg))
+    };
+    let mut css = format!("color:#{:02x}{:02x}{:02x};", fg.0, fg.1, fg.2);
+    if let Some(bg) = bg {
+        let _ = write!(css, "background:#{:02x}{:02x}{:02x};", bg.0, bg.1, bg.2);
+    }
+    if modifier.contains(Modifier::BOLD) {
+        css.push_str("font-weight:bold;");
+    }
+    if modifier.contains(Modifier::DIM) {
+        css.push_str("opacity:0.6;");
+    }
+    if modifier.contains(Modifier::ITALIC) {
+        css.push_str("font-style:italic;");
+    }
+    if modifier.contains(Modifier::UNDERLINED) {
+        css.push_str("text-decoration:underline;");
+    } else if modifier.contains(Modifier::CROSSED_OUT) {
+        css.push_str("text-decoration:line-through;");
+    }
+    css
+}
+
+/// Resolves a color into RGB using the xterm default palette, `None` for the default color.
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    let rgb = match color {
+        Color::Reset => return None,
+        Color::Black => ANSI[0],
+        Color::Red => ANSI[1],
+        Color::Green => ANSI[2],
+        Color::Yellow => ANSI[3],
+        Color::Blue => ANSI[4],
+        Color::Magenta => ANSI[5],
+        Color::Cyan => ANSI[6],
+        Color::Gray => ANSI[7],
+        Color::DarkGray => ANSI[8],
+        Color::LightRed => ANSI[9],
+        Color::LightGreen => ANSI[10],
+        Color::LightYellow => ANSI[11],
+        Color::LightBlue => ANSI[12],
+        Color::LightMagenta => ANSI[13],
+        Color::LightCyan => ANSI[14],
+        Color::White => ANSI[15],
+        Color::Indexed(index) if index < 16 => ANSI[index as usize],
+        Color::Indexed(index) if index < 232 => {
+            let index = index - 16;
+            let level = |value: u8| if value == 0 { 0 } else { value * 40 + 55 };
+            (level(index / 36), level((index / 6) % 6), level(index % 6))
+        }
+        Color::Indexed(index) => {
+            let gray = (index - 232) * 10 + 8;
+            (gray, gray, gray)
+        }
+        Color::Rgb(r, g, b) => (r, g, b),
+    };
+    Some(rgb)
+}
@@ -0,0 +1,267 @@
+use crate::config::TaskConfig;
+use crate::event::ParallelyEvent;
+use crate::history;
+use crate::history::HistoryEntry;
+use crate::picker::{Picker, PickerAction};
+use crossterm::event::{
+    DisableBracketedPaste, EnableBracketedPaste, Event, EventStream, KeyCode, KeyEvent,
+    KeyEventKind, KeyModifiers,
+};
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::ExecutableCommand;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, BorderType, Paragraph, Widget};
+use ratatui::DefaultTerminal;
+use tokio_stream::StreamExt;
+
+/// What to run, as chosen on the startup screen.
+pub enum StartupChoice {
+    Tasks(Vec<TaskConfig>),
+    /// Run a past session again.
+    Rerun(HistoryEntry),
+}
+
+/// Lets the user type or paste the commands, one per line, when parallely is started without
+/// any, or pick a past session. Returns `None` if the user cancelled.
+pub async fn run() -> color_eyre::Result<Option<StartupChoice>> {
+    let history = history::load().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load the history: {:?}", e);
+        Vec::new()
+    });
+    let mut terminal = ratatui::try_init()?;
+    std::io::stdout().execute(EnableBracketedPaste)?;
+    let screen = StartupScreen {
+        history,
+        ..StartupScreen::default()
+    };
+    let result = screen.run(&mut terminal).await;
+    std::io::stdout().execute(DisableBracketedPaste)?;
+    ratatui::try_restore()?;
+    result
+}
+
+#[derive(Default)]
+struct StartupScreen {
+    commands: Vec<String>,
+    input: String,
+    /// The selected command, `None` while typing into the input line.
+    selected: Option<usize>,
+    history: Vec<HistoryEntry>,
+    /// Picks the index of a past session.
+    history_picker: Option<Picker<usize>>,
+}
+
+enum StartupAction {
+    None,
+    Launch,
+    Rerun(usize),
+    Cancel,
+}
+
+impl StartupScreen {
+    async fn run(
+        mut self,
+        terminal: &mut DefaultTerminal,
+    ) -> color_eyre::Result<Option<StartupChoice>> {
+        let mut events = EventStream::new();
+        loop {
+            terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
+            let Some(event) = events.next().await else {
+                return Ok(None);
+            };
+            match self.handle_event(event?) {
+                StartupAction::None => {}
+                StartupAction::Launch => {
+                    return Ok(Some(StartupChoice::Tasks(
+                        self.commands.into_iter().map(TaskConfig::new).collect(),
+                    )))
+                }
+                StartupAction::Rerun(index) => {
+                    return Ok(Some(StartupChoice::Rerun(self.history.swap_remove(index))))
+                }
+                StartupAction::Cancel => return Ok(None),
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> StartupAction {
+        if let Some(picker) = self.history_picker.as_mut() {
+            let mut event = ParallelyEvent::from(event.clone());
+            match picker.handle_event(&mut event) {
+                PickerAction::None => {}
+                PickerAction::Picked(index) => return StartupAction::Rerun(index),
+                PickerAction::Cancelled => self.history_picker = None,
+            }
+            if !event.propagate() {
+                return StartupAction::None;
+            }
+        }
+        let (code, modifiers) = match event {
+            Event::Paste(text) => {
+                self.selected = None;
+                self.input.push_str(&text);
+                let input = std::mem::take(&mut self.input);
+                let mut lines = input.lines().collect::<Vec<_>>();
+                // keep the last line in the input unless the pasted text ends with a newline
+                if !input.ends_with('\n') {
+                    self.input = lines.pop().unwrap_or_default().to_string();
+                }
+                self.commands.extend(
+                    lines
+                        .into_iter()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(str::to_string),
+                );
+                return StartupAction::None;
+            }
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => (code, modifiers),
+            _ => return StartupAction::None,
+        };
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                return StartupAction::Cancel
+            }
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                let items = self
+                    .history
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| (entry.to_string(), index))
+                    .collect();
+                let picker = Picker::new("Run a past session again", items);
+                if !picker.is_empty() {
+                    self.history_picker = Some(picker);
+                }
+            }
+            KeyCode::Esc => match self.selected {
+                Some(_) => self.selected = None,
+                None => return StartupAction::Cancel,
+            },
+            KeyCode::Enter => match self.selected {
+                Some(_) => self.selected = None,
+                None if !self.input.trim().is_empty() => {
+                    self.commands.push(self.input.trim().to_string());
+                    self.input.clear();
+                }
+                None if !self.commands.is_empty() => return StartupAction::Launch,
+                None => {}
+            },
+            KeyCode::Char(c) => {
+                self.selected = None;
+                self.input.push(c);
+            }
+            KeyCode::Backspace if self.selected.is_none() => {
+                self.input.pop();
+            }
+            KeyCode::Up => match self.selected {
+                Some(index) if index > 0 && modifiers.contains(KeyModifiers::SHIFT) => {
+                    self.commands.swap(index, index - 1);
+                    self.selected = Some(index - 1);
+                }
+                Some(index) => self.selected = Some(index.saturating_sub(1)),
+                None => self.selected = self.commands.len().checked_sub(1),
+            },
+            KeyCode::Down => match self.selected {
+                Some(index)
+                    if index + 1 < self.commands.len()
+                        && modifiers.contains(KeyModifiers::SHIFT) =>
+                {
+                    self.commands.swap(index, index + 1);
+                    self.selected = Some(index + 1);
+                }
+                Some(index) if index + 1 < self.commands.len() => self.selected = Some(index + 1),
+                Some(_) if !modifiers.contains(KeyModifiers::SHIFT) => self.selected = None,
+                _ => {}
+            },
+            KeyCode::Delete | KeyCode::Backspace => {
+                if let Some(index) = self.selected {
+                    self.commands.remove(index);
+                    self.selected = match self.commands.len() {
+                        0 => None,
+                        len => Some(index.min(len - 1)),
+                    };
+                }
+            }
+            _ => {}
+        }
+        StartupAction::None
+    }
+}
+
+impl Widget for &mut StartupScreen {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let full_area = area;
+        let height = (self.commands.len() as u16 + 3).clamp(8, area.height);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let block = Block::bordered()
+            .title(" Commands to run in parallel ".magenta().bold())
+            .title_bottom(Line::from(vec![
+                " Add/Launch ".into(),
+                "<Enter> ".blue().bold(),
+                " Select ".into(),
+                "<↑/↓> ".blue().bold(),
+                " Move ".into(),
+                "<Shift+↑/↓> ".blue().bold(),
+                " Remove ".into(),
+                "<Del> ".blue().bold(),
+                " History ".into(),
+                "<Ctrl+R> ".blue().bold(),
+                " Cancel ".into(),
+                "<Esc> ".blue().bold(),
+            ]))
+            .border_type(BorderType::Rounded);
+
+        let mut lines = self
+            .commands
+            .iter()
+            .enumerate()
+            .map(|(index, command)| {
+                let line = Line::from(format!("{:>2}. {}", index + 1, command));
+                if self.selected == Some(index) {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>();
+        let prompt = if self.commands.is_empty() || !self.input.is_empty() {
+            "> ".green().bold()
+        } else {
+            "> (press Enter to launch) ".dark_gray()
+        };
+        let mut input = Line::from(vec![prompt, self.input.clone().into()]);
+        if self.selected.is_none() {
+            input.push_span(" ".reversed());
+        }
+        lines.push(input);
+
+        // keep the input line visible when there are more commands than rows
+        let visible_rows = block.inner(area).height as usize;
+        let scroll = lines.len().saturating_sub(visible_rows) as u16;
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll, 0))
+            .render(area, buf);
+
+        if let Some(picker) = self.history_picker.as_mut() {
+            picker.render(full_area, buf);
+        }
+    }
+}
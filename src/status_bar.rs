@@ -0,0 +1,192 @@
+use crate::i18n::{Lang, Phrase};
+use crate::task_state::TaskState;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Widget;
+use std::time::Duration;
+
+/// What the keys do at the moment, which decides the hints of the [`StatusBar`].
+pub enum Mode {
+    Normal,
+    /// Typing into the focused console.
+    Interact,
+    /// That many consoles are marked for bulk actions.
+    Select(usize),
+    /// A prompt, e.g. of `:` to go to a line, is open.
+    Prompt,
+}
+
+/// The top row of the screen: the workspaces, how many tasks are in which state and how long
+/// parallely has been running on the left, the title in the middle and the keys of the current
+/// [`Mode`] on the right.
+pub struct StatusBar {
+    pub states: Vec<TaskState>,
+    /// The workspaces, switched with `F1` … `F11`, and the one shown.
+    pub workspaces: Vec<String>,
+    pub workspace: String,
+    pub elapsed: Duration,
+    pub mode: Mode,
+    pub frozen: bool,
+    pub zoomed: bool,
+    pub read_only: bool,
+    pub lang: Lang,
+}
+
+impl StatusBar {
+    /// The workspace tabs followed by e.g. `3 running · 1 failed · 2 done`, leaving out the
+    /// states no task is in.
+    fn counts(&self) -> Line<'static> {
+        let count = |states: &[TaskState]| {
+            self.states
+                .iter()
+                .filter(|state| states.contains(state))
+                .count()
+        };
+        let counts = [
+            (
+                count(&[
+                    TaskState::Starting,
+                    TaskState::Running,
+                    TaskState::Ready,
+                    TaskState::Stopping,
+                ]),
+                Phrase::Running,
+            ),
+            (count(&[TaskState::Pending]), Phrase::Waiting),
+            (
+                count(&[TaskState::Failed, TaskState::TimedOut]),
+                Phrase::Failed,
+            ),
+            (count(&[TaskState::Exited, TaskState::Killed]), Phrase::Done),
+        ];
+        let mut line = Line::from(" ");
+        for (index, workspace) in self.workspaces.iter().enumerate() {
+            let tab = Span::from(format!(" F{} {} ", index + 1, workspace));
+            line.push_span(match *workspace == self.workspace {
+                true => tab.bold().reversed(),
+                false => tab.dark_gray(),
+            });
+        }
+        if !self.workspaces.is_empty() {
+            line.push_span("  ");
+        }
+        let first_count = line.spans.len();
+        for (count, phrase) in counts.into_iter().filter(|(count, _)| *count > 0) {
+            if line.spans.len() > first_count {
+                line.push_span(" · ".dark_gray());
+            }
+            let span = Span::from(format!("{} {}", count, self.lang.text(phrase)));
+            line.push_span(match phrase {
+                Phrase::Running => span.green(),
+                Phrase::Failed => span.red().bold(),
+                _ => span,
+            });
+        }
+        line.push_span(format!("  ⏱ {} ", format_elapsed(self.elapsed)).dark_gray());
+        line
+    }
+
+    fn title(&self) -> Line<'static> {
+        let text = |phrase| format!(" {} ", self.lang.text(phrase));
+        let pid = std::process::id();
+        let mut title = Line::from(format!(" Parallely - ({pid}) ").bold());
+        if self.frozen {
+            title.push_span(text(Phrase::Frozen).cyan().bold().reversed());
+        }
+        if self.zoomed {
+            title.push_span(text(Phrase::Zoomed).green().bold().reversed());
+        }
+        if self.read_only {
+            title.push_span(text(Phrase::ReadOnly).magenta().bold().reversed());
+        }
+        title
+    }
+
+    fn hints(&self) -> Line<'static> {
+        let text = |phrase| Span::from(format!(" {} ", self.lang.text(phrase)));
+        let key = |key: &str| format!("<{}> ", key).blue().bold();
+        let mut hints = match self.mode {
+            Mode::Normal => vec![
+                text(Phrase::Focus),
+                key("Tab"),
+                text(Phrase::Hide),
+                key("H"),
+                text(Phrase::Unhide),
+                key("Shift+H"),
+                text(Phrase::Snapshot),
+                key("E"),
+            ],
+            Mode::Interact => vec![
+                text(Phrase::Typing).cyan().bold().reversed(),
+                text(Phrase::Paste),
+                key("Ctrl+V"),
+                text(Phrase::EndInput),
+                key("Ctrl+D"),
+                text(Phrase::Leave),
+                key("Esc"),
+            ],
+            Mode::Select(marked) => {
+                let mut hints = vec![format!(" ◆ {} {} ", marked, self.lang.text(Phrase::Marked))
+                    .yellow()
+                    .bold()
+                    .reversed()];
+                if !self.read_only {
+                    hints.extend([
+                        text(Phrase::Restart),
+                        key("R"),
+                        text(Phrase::Stop),
+                        key("S"),
+                    ]);
+                }
+                hints.extend([text(Phrase::Unmark), key("Esc")]);
+                hints
+            }
+            Mode::Prompt => vec![
+                text(Phrase::Submit),
+                key("Enter"),
+                text(Phrase::Close),
+                key("Esc"),
+            ],
+        };
+        if matches!(self.mode, Mode::Normal) && !self.read_only {
+            hints.extend([text(Phrase::Quit), key("Q")]);
+        }
+        Line::from(hints)
+    }
+}
+
+impl Widget for &StatusBar {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let counts = self.counts();
+        let hints = self.hints();
+        let title = self.title();
+        // the title goes between the counts and the hints, if there is room for it
+        let left = counts.width() as u16;
+        let gap = area.width.saturating_sub(left + hints.width() as u16);
+        if gap >= title.width() as u16 {
+            let gap_area = Rect {
+                x: area.x + left,
+                width: gap,
+                ..area
+            };
+            title.centered().render(gap_area, buf);
+        }
+        counts.render(area, buf);
+        hints.right_aligned().render(area, buf);
+    }
+}
+
+/// Formats how long parallely has been running, e.g. `42s`, `5m 03s` or `1h 02m`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
@@ -0,0 +1,92 @@
+use crate::console::Console;
+use crate::task_executor::TaskStatus;
+use color_eyre::eyre::WrapErr;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often the status file is rewritten at most.
+const WRITE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Periodically writes the state of every task as JSON, see `--status-file`.
+pub struct StatusFile {
+    path: PathBuf,
+    lines: usize,
+    written_at: Option<Instant>,
+}
+
+impl StatusFile {
+    pub fn new(path: PathBuf, lines: usize) -> Self {
+        Self {
+            path,
+            lines,
+            written_at: None,
+        }
+    }
+
+    /// Writes the status file unless it was written recently or `force` is set.
+    pub fn update(
+        &mut self,
+        consoles: &[Console],
+        tasks_status: &[color_eyre::Result<TaskStatus>],
+        force: bool,
+    ) -> color_eyre::Result<()> {
+        if !force
+            && self
+                .written_at
+                .is_some_and(|at| at.elapsed() < WRITE_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.written_at = Some(Instant::now());
+        let tasks = consoles
+            .iter()
+            .zip(tasks_status)
+            .map(|(console, status)| {
+                let (state, pid, exit_code) = match status {
+                    Ok(_) if console.is_restarting() => ("restarting", None, None),
+                    Ok(_) if console.is_waiting() => ("waiting", None, None),
+                    Ok(TaskStatus::Ready(_)) => ("ready", None, None),
+                    Ok(TaskStatus::Executing { pid, .. }) => ("running", *pid, None),
+                    Ok(TaskStatus::Killed { pid, .. }) => ("killed", *pid, None),
+                    Ok(TaskStatus::Exited { pid, status, .. }) => ("exited", *pid, status.code()),
+                    Err(_) => ("error", None, None),
+                };
+                json!({
+                    "name": console.task().key(),
+                    "command": console.task().command.as_str(),
+                    "state": state,
+                    "pid": pid,
+                    "exit_code": exit_code,
+                    "failed": status.as_ref().map_or(true, TaskStatus::is_failed),
+                    "error": status.as_ref().err().map(|error| error.to_string()),
+                    "started_ms": millis(console.started_at()),
+                    "ready_ms": millis(console.ready_at()),
+                    "exited_ms": millis(console.exited_at()),
+                    "last_lines": console.last_lines(self.lines),
+                })
+            })
+            .collect::<Vec<_>>();
+        let json = json!({
+            "pid": std::process::id(),
+            "updated_ms": millis(Some(SystemTime::now())),
+            "tasks": tasks,
+        });
+        write_atomically(&self.path, serde_json::to_string_pretty(&json)?.as_bytes())
+            .wrap_err_with(|| format!("Failed to write status file {}", self.path.display()))
+    }
+}
+
+fn millis(time: Option<SystemTime>) -> Option<u64> {
+    time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+}
+
+/// Writes into a temporary file next to `path` and renames it, so that readers never see a
+/// partially written file.
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)
+}
@@ -0,0 +1,206 @@
+use crate::report;
+use crate::report::TaskReport;
+use crate::task_executor::TaskStatus;
+use clap::ValueEnum;
+use crossterm::style::{StyledContent, Stylize};
+use std::io::IsTerminal;
+use std::time::Duration;
+
+/// How many of the last lines of output are printed beneath a task which failed, unless they are
+/// printed in full with `--tail-on-exit`.
+pub const FAILED_TAIL_LINES: usize = 5;
+
+/// Whether the summary printed at exit is colored.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color if stdout is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(&self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// The sections of the summary, in the order they are printed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    Succeeded,
+    Failed,
+    Killed,
+    /// Never started or still running, e.g. waiting for a dependency.
+    Unfinished,
+}
+
+impl Outcome {
+    const ALL: [Outcome; 4] = [
+        Outcome::Succeeded,
+        Outcome::Failed,
+        Outcome::Killed,
+        Outcome::Unfinished,
+    ];
+
+    fn of(report: &TaskReport) -> Self {
+        match &report.status {
+            Err(_) => Outcome::Failed,
+            Ok(TaskStatus::Ready(_) | TaskStatus::Executing { .. }) => Outcome::Unfinished,
+            Ok(TaskStatus::Killed { .. }) => Outcome::Killed,
+            Ok(TaskStatus::Exited { success: true, .. }) => Outcome::Succeeded,
+            Ok(TaskStatus::Exited { status, .. }) if status.code().is_none() => Outcome::Killed,
+            Ok(TaskStatus::Exited { .. }) => Outcome::Failed,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Outcome::Succeeded => "Succeeded",
+            Outcome::Failed => "Failed",
+            Outcome::Killed => "Killed",
+            Outcome::Unfinished => "Unfinished",
+        }
+    }
+
+    fn glyph(&self) -> &'static str {
+        match self {
+            Outcome::Succeeded => "✔",
+            Outcome::Failed | Outcome::Killed => "✖",
+            Outcome::Unfinished => "↻",
+        }
+    }
+
+    fn paint<'a>(&self, text: &'a str) -> StyledContent<&'a str> {
+        match self {
+            Outcome::Succeeded => text.green(),
+            Outcome::Failed => text.red(),
+            Outcome::Killed => text.yellow(),
+            Outcome::Unfinished => text.dark_grey(),
+        }
+    }
+}
+
+/// Prints how every task ended, grouped into succeeded, failed, killed and unfinished tasks,
+/// with the last `tail` lines of output beneath each failed task. Lines are cut to the width of
+/// the terminal.
+pub fn print(reports: &[TaskReport], color: ColorChoice, tail: usize) {
+    let color = color.enabled();
+    let width = crossterm::terminal::size()
+        .ok()
+        .filter(|_| std::io::stdout().is_terminal())
+        .map_or(usize::MAX, |(columns, _)| columns as usize);
+    for outcome in Outcome::ALL {
+        let reports = reports
+            .iter()
+            .filter(|report| Outcome::of(report) == outcome)
+            .collect::<Vec<_>>();
+        if reports.is_empty() {
+            continue;
+        }
+        let title = format!("{} ({})", outcome.title(), reports.len());
+        match color {
+            true => println!("{}", outcome.paint(&title).bold()),
+            false => println!("{}", title),
+        }
+        for report in reports {
+            let line = ellipsize(&describe(report), width.saturating_sub(4));
+            match color {
+                true => println!("  {} {}", outcome.paint(outcome.glyph()), line),
+                false => println!("  {} {}", outcome.glyph(), line),
+            }
+            if outcome != Outcome::Failed {
+                continue;
+            }
+            let skip = report.last_lines.len().saturating_sub(tail);
+            for output in report.last_lines.iter().skip(skip) {
+                let output = ellipsize(output, width.saturating_sub(8));
+                match color {
+                    true => println!("      {} {}", "│".dark_grey(), output.as_str().dark_grey()),
+                    false => println!("      │ {}", output),
+                }
+            }
+        }
+    }
+}
+
+/// Prints the last `count` lines of output of each failed task to stderr without cutting them,
+/// for `--tail-on-exit`.
+pub fn print_failed_output(reports: &[TaskReport], count: usize) {
+    for report in reports {
+        if Outcome::of(report) != Outcome::Failed {
+            continue;
+        }
+        eprintln!("\n==> {} <==", describe(report));
+        let skip = report.last_lines.len().saturating_sub(count);
+        for line in report.last_lines.iter().skip(skip) {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+/// The command followed by how long it ran, its exit status and what else is known about it.
+fn describe(report: &TaskReport) -> String {
+    let mut parts = vec![report.command.clone()];
+    if let Some(duration) = report.duration() {
+        parts.push(format_duration(duration));
+    }
+    match &report.status {
+        Ok(TaskStatus::Exited {
+            status,
+            success: false,
+            ..
+        }) => parts.push(status.to_string()),
+        Ok(_) => {}
+        Err(error) => parts.push(error.to_string()),
+    }
+    if let Some(test_summary) = report.test_summary {
+        parts.push(format!("[tests: {}]", test_summary));
+    }
+    if let Some(cpu_time) = report.cpu_time {
+        parts.push(format!("[CPU: {}]", cpu_time));
+    }
+    if let Some(peak_rss) = report.peak_rss {
+        parts.push(format!("[peak RSS: {}]", report::format_bytes(peak_rss)));
+    }
+    if let Some(io_stats) = report.io_stats {
+        parts.push(format!("[{}]", io_stats));
+    }
+    if report.dropped_lines > 0 {
+        parts.push(format!("[{} lines dropped]", report.dropped_lines));
+    }
+    parts.join("  ")
+}
+
+/// Formats a duration as e.g. `0.42s`, `12.3s` or `2m 05s`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds < 10.0 {
+        format!("{:.2}s", seconds)
+    } else if seconds < 60.0 {
+        format!("{:.1}s", seconds)
+    } else {
+        let seconds = duration.as_secs();
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+/// Cuts the text to `width` characters, ending it with `…` if it is longer.
+fn ellipsize(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut text = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    text.push('…');
+    text
+}
@@ -0,0 +1,61 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+/// How long [`Supervisor::shutdown`] waits for the cancelled tasks to finish.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Owns the background tasks of parallely, e.g. the readers of the output of the tasks and the
+/// listeners for signals and input, so that they are all cancelled and awaited when the main loop
+/// ends, also when it ends early with an error, instead of living on and sending messages nobody
+/// receives anymore. Clones share the same tasks.
+#[derive(Clone, Default)]
+pub struct Supervisor {
+    token: CancellationToken,
+    tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl Supervisor {
+    /// Runs the future in the background until it completes or the supervisor shuts down.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.token.clone();
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        // drop the tasks which are done, so that the set does not grow with every run of a task
+        while tasks.try_join_next().is_some() {}
+        if token.is_cancelled() {
+            return;
+        }
+        tasks.spawn(async move {
+            tokio::select! {
+                _ = token.cancelled() => {}
+                _ = future => {}
+            }
+        });
+    }
+
+    /// Cancelled once the supervisor shuts down, for work which cannot be spawned as a future,
+    /// e.g. a thread blocking on reads, to check.
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Cancels every task and waits until they have finished, at most for [`SHUTDOWN_TIMEOUT`].
+    /// Tasks spawned afterwards are not run.
+    pub async fn shutdown(&self) {
+        self.token.cancel();
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(|e| e.into_inner()));
+        let joined = async { while tasks.join_next().await.is_some() {} };
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, joined)
+            .await
+            .is_err()
+        {
+            tracing::warn!("[Supervisor] Tasks still running after shutdown, aborting them");
+            tasks.abort_all();
+        }
+    }
+}
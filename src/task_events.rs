@@ -0,0 +1,58 @@
+use crate::console::Console;
+use crate::task_executor::{Executable, TaskStatus};
+use crate::task_state::{TaskState, Transition};
+use clap::ValueEnum;
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How the events of the tasks are printed along with their output, see `parallely up --events`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum EventFormat {
+    /// A line of JSON per event, e.g. `{"event":"exit","task":"api","time_ms":1700000000000,
+    /// "state":"failed","code":1}`.
+    Ndjson,
+}
+
+/// Prints the event of the transition of the task of `console`, if it started, became ready,
+/// timed out or exited, right away so that it is in order with the lines of output echoed by
+/// the console. Times are milliseconds since the unix epoch.
+pub fn print(format: EventFormat, transition: &Transition, console: &mut Console) {
+    let EventFormat::Ndjson = format;
+    let time_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let event = match transition.to {
+        TaskState::Running => json!({
+            "event": "start",
+            "task": transition.task,
+            "time_ms": time_ms,
+            "pid": console.pid(),
+        }),
+        TaskState::Ready => json!({
+            "event": "ready",
+            "task": transition.task,
+            "time_ms": time_ms,
+        }),
+        TaskState::TimedOut => json!({
+            "event": "timeout",
+            "task": transition.task,
+            "time_ms": time_ms,
+        }),
+        TaskState::Exited | TaskState::Killed | TaskState::Failed => {
+            let code = match console.try_wait() {
+                Ok(TaskStatus::Exited { status, .. }) => status.code(),
+                _ => None,
+            };
+            json!({
+                "event": "exit",
+                "task": transition.task,
+                "time_ms": time_ms,
+                "state": transition.to.to_string(),
+                "code": code,
+            })
+        }
+        TaskState::Pending | TaskState::Starting | TaskState::Stopping => return,
+    };
+    println!("{}", event);
+}
@@ -1,14 +1,78 @@
+mod binary;
 pub mod child_ext;
+mod cpu_time;
+mod io_stats;
+mod parent_death;
+#[cfg(unix)]
+mod pty;
 
+use crate::builtin;
+use crate::config::{OutputEncoding, Shell};
 use crate::message::MessageSender;
+use crate::plugin::{PluginError, Plugins};
+use crate::supervisor::Supervisor;
+use crate::task_executor::binary::OutputStream;
+pub use crate::task_executor::binary::{hexdump, Pipe, TaskOutput};
 use crate::task_executor::child_ext::{ChildExt, ChildSignal};
+pub use crate::task_executor::cpu_time::CpuTime;
+pub use crate::task_executor::io_stats::IoStats;
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
 use std::fmt::{Display, Formatter};
 use std::process::ExitStatus;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::{mpsc, oneshot};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use thiserror::Error;
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, oneshot, watch};
 
-pub type TaskOutputReceiver = mpsc::UnboundedReceiver<String>;
+pub type TaskOutputReceiver = mpsc::UnboundedReceiver<(Pipe, TaskOutput)>;
+/// The raw bytes written to the pseudo terminal of a task, see [`TaskExecutor::execute_pty`].
+pub type TaskPtyReceiver = mpsc::UnboundedReceiver<Vec<u8>>;
+
+#[derive(Debug, Error)]
+#[error("The standard input of `{0}` is not connected")]
+pub struct StdinClosedError(String);
+
+/// How often the peak resident set size and the I/O counters of a running child are read, which
+/// takes a few reads of `/proc`, however often it is polled.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often the reader of a paused pseudo terminal checks whether it has been resumed.
+#[cfg(unix)]
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How fast the output of a child is read. Output which is not read stays in the pipe, so a
+/// child writing faster blocks once the pipe is full and is slowed down to the same pace.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum OutputRate {
+    #[default]
+    Unlimited,
+    /// At most this many lines (or reads of the pseudo terminal) per second.
+    LinesPerSecond(u32),
+    Paused,
+}
+
+impl OutputRate {
+    /// The pause between two reads.
+    fn interval(&self) -> Option<Duration> {
+        match self {
+            OutputRate::LinesPerSecond(lines) => Some(Duration::from_secs(1) / (*lines).max(1)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for OutputRate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputRate::Unlimited => write!(f, "unlimited"),
+            OutputRate::LinesPerSecond(lines) => write!(f, "{} lines/s", lines),
+            OutputRate::Paused => write!(f, "paused"),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum TaskStatus {
@@ -25,9 +89,33 @@ pub enum TaskStatus {
         command: String,
         pid: Option<u32>,
         status: ExitStatus,
+        /// Whether the exit code is one of [`crate::config::TaskConfig::success_exit_codes`], see
+        /// [`TaskExecutor::set_success_exit_codes`].
+        success: bool,
     },
 }
 
+impl TaskStatus {
+    pub fn is_failed(&self) -> bool {
+        match self {
+            TaskStatus::Killed { .. } => true,
+            TaskStatus::Exited { success, .. } => !success,
+            _ => false,
+        }
+    }
+
+    /// A single-character summary of the status: `↻` waiting to be (re)started, `●` running,
+    /// `✔` exited successfully and `✖` failed or killed.
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            TaskStatus::Ready(_) => "↻",
+            TaskStatus::Executing { .. } => "●",
+            _ if self.is_failed() => "✖",
+            _ => "✔",
+        }
+    }
+}
+
 impl Display for TaskStatus {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -44,6 +132,7 @@ impl Display for TaskStatus {
                 command,
                 pid,
                 status,
+                ..
             } => {
                 write!(
                     f,
@@ -101,12 +190,50 @@ pub struct TaskExecutor {
     pid: Option<u32>,
     shutdown_sender: Option<oneshot::Sender<()>>,
     message_sender: MessageSender,
+    started_at: Option<SystemTime>,
+    /// When the exit of the child was first observed.
+    exited_at: Option<SystemTime>,
+    /// The highest peak resident set size of the child seen while polling it, in bytes.
+    peak_rss: Option<u64>,
+    /// The CPU time the last run used, known once it has exited.
+    cpu_time: Option<CpuTime>,
+    /// The I/O counters of the child the last time it was sampled.
+    io_stats: Option<IoStats>,
+    /// When `peak_rss` and `io_stats` were last sampled, see [`SAMPLE_INTERVAL`].
+    sampled_at: Option<Instant>,
+    /// Shared with the reader of the output, so that it can be changed while the child runs.
+    output_rate: watch::Sender<OutputRate>,
+    /// Whether the child gets a pipe as its standard input instead of `/dev/null`.
+    stdin_piped: bool,
+    /// The encoding of the output of the child, UTF-8 if `None`.
+    encoding: Option<OutputEncoding>,
+    /// The exit codes the child succeeds with, see [`crate::config::TaskConfig::success_exit_codes`].
+    success_exit_codes: Vec<i32>,
+    /// Whether the child is killed once parallely dies, see [`TaskExecutor::kill_with_parent`].
+    kill_with_parent: bool,
+    /// The variables the command had before any were loaded, see
+    /// [`TaskExecutor::set_loaded_env`].
+    base_env: Option<BTreeMap<OsString, Option<OsString>>>,
+    /// The names of the variables loaded for the last start.
+    loaded_env: Vec<String>,
+    /// Whether the child makes the pseudo terminal its controlling terminal, see
+    /// [`TaskExecutor::execute_pty`].
+    #[cfg(unix)]
+    controlling_terminal: bool,
+    /// Their output processors process the lines of the child as they are read.
+    plugins: Option<Arc<Plugins>>,
+    /// Feeds the standard input or the pseudo terminal of the running child, see
+    /// [`TaskExecutor::write_stdin`].
+    stdin: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    /// The master side of the pseudo terminal the child runs in, if any.
+    #[cfg(unix)]
+    pty_master: Option<std::fs::File>,
 }
 
 impl TaskExecutor {
-    pub fn new(raw_command: String, message_sender: MessageSender) -> Self {
-        let mut args = raw_command.split_whitespace().collect::<Vec<_>>();
-        let mut command = Command::new(args.remove(0));
+    pub fn new(raw_command: String, shell: Shell, message_sender: MessageSender) -> Self {
+        let (program, args) = builtin::command_line(&raw_command, shell);
+        let mut command = Command::new(program);
         command
             .args(args)
             .stdin(std::process::Stdio::null())
@@ -119,6 +246,104 @@ impl TaskExecutor {
             pid: None,
             shutdown_sender: None,
             message_sender,
+            started_at: None,
+            exited_at: None,
+            peak_rss: None,
+            cpu_time: None,
+            io_stats: None,
+            sampled_at: None,
+            output_rate: watch::Sender::new(OutputRate::default()),
+            stdin_piped: false,
+            encoding: None,
+            success_exit_codes: vec![0],
+            kill_with_parent: false,
+            base_env: None,
+            loaded_env: Vec::new(),
+            #[cfg(unix)]
+            controlling_terminal: false,
+            plugins: None,
+            stdin: None,
+            #[cfg(unix)]
+            pty_master: None,
+        }
+    }
+
+    /// Connects the standard input of the child to a pipe from the next start on, so that it
+    /// can be written with [`TaskExecutor::write_stdin`].
+    pub fn pipe_stdin(&mut self) {
+        self.stdin_piped = true;
+        self.command.stdin(self.stdin_stdio());
+    }
+
+    /// Passes the lines of the child through the output processors from the next start on.
+    pub fn set_plugins(&mut self, plugins: Arc<Plugins>) {
+        self.plugins = Some(plugins);
+    }
+
+    /// Transcodes the output of the child from `encoding` to UTF-8 from the next start on.
+    pub fn set_encoding(&mut self, encoding: Option<OutputEncoding>) {
+        self.encoding = encoding;
+    }
+
+    /// Counts an exit with any of `codes` as a success instead of only `0`, e.g. `130` for a
+    /// task exiting on `SIGINT`.
+    pub fn set_success_exit_codes(&mut self, codes: Vec<i32>) {
+        self.success_exit_codes = codes;
+    }
+
+    /// Kills the child from the next start on once parallely dies, even if it is killed or
+    /// crashes before stopping it, on Linux and Windows, see [`parent_death`].
+    pub fn kill_with_parent(&mut self) {
+        if !self.kill_with_parent {
+            self.kill_with_parent = true;
+            parent_death::kill_with_parent(&mut self.command);
+        }
+    }
+
+    /// Passes the variables loaded for the next start, see
+    /// [`crate::config::TaskConfig::load_env`], in place of the ones loaded for the last one, so
+    /// that a variable which is not loaded anymore is not passed on either.
+    pub fn set_loaded_env(&mut self, env: BTreeMap<String, String>) {
+        let base = self.base_env.get_or_insert_with(|| {
+            self.command
+                .as_std()
+                .get_envs()
+                .map(|(name, value)| (name.to_os_string(), value.map(OsStr::to_os_string)))
+                .collect()
+        });
+        for name in std::mem::take(&mut self.loaded_env) {
+            // back to what the command had, or else to what it inherits
+            let value = match base.get(OsStr::new(&name)) {
+                Some(value) => value.clone(),
+                None => std::env::var_os(&name),
+            };
+            match value {
+                Some(value) => self.command.env(&name, value),
+                None => self.command.env_remove(&name),
+            };
+        }
+        self.command.envs(&env);
+        self.loaded_env = env.into_keys().collect();
+    }
+
+    /// Whether the child succeeded, a child terminated by a signal counts as exiting with 128
+    /// plus the signal, as a shell reports it.
+    fn succeeded(&self, status: ExitStatus) -> bool {
+        #[cfg(unix)]
+        let code = {
+            use std::os::unix::process::ExitStatusExt;
+            status.code().or(status.signal().map(|signal| 128 + signal))
+        };
+        #[cfg(not(unix))]
+        let code = status.code();
+        code.is_some_and(|code| self.success_exit_codes.contains(&code))
+    }
+
+    fn stdin_stdio(&self) -> std::process::Stdio {
+        if self.stdin_piped {
+            std::process::Stdio::piped()
+        } else {
+            std::process::Stdio::null()
         }
     }
 
@@ -126,40 +351,151 @@ impl TaskExecutor {
         let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
         let (output_sender, output_receiver) = mpsc::unbounded_channel();
         let message_sender = self.message_sender.clone();
+        // in a process group of its own, the child does not get the SIGHUP sent to parallely's
+        // when the terminal is closed, but is stopped by parallely as on quitting
+        #[cfg(unix)]
+        self.command.process_group(0);
         let mut child = self.command.spawn()?;
-        let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
-        let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
-        self.child = Some(child);
-        self.pid = self.child.as_ref().unwrap().id();
-        self.shutdown_sender = Some(shutdown_sender);
-        tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    _ = &mut shutdown_receiver => {
-                        break;
-                    }
-                    Ok(line) = stdout.next_line() => {
-                        match line {
-                            Some(line) => {
-                                if output_sender.send(line).is_err() {
-                                    break;
-                                }
-                            }
-                            None => {
+        let mut stdout =
+            OutputStream::new(BufReader::new(child.stdout.take().unwrap()), self.encoding);
+        let mut stderr =
+            OutputStream::new(BufReader::new(child.stderr.take().unwrap()), self.encoding);
+        let supervisor = self.message_sender.supervisor();
+        let stdin = child
+            .stdin
+            .take()
+            .map(|stdin| spawn_stdin_writer(supervisor, stdin));
+        let mut output_rate = self.output_rate.subscribe();
+        self.spawned(child, shutdown_sender);
+        self.stdin = stdin;
+        let notice = |e: PluginError| (Pipe::Stderr, TaskOutput::Notice(e.to_string()));
+        let mut processors = self
+            .plugins
+            .as_ref()
+            .map(|plugins| {
+                plugins.processors(|e| {
+                    let _ = output_sender.send(notice(e));
+                })
+            })
+            .filter(|processors| !processors.is_empty());
+        self.message_sender.supervisor().spawn(async move {
+            while !(stdout.is_done() && stderr.is_done()) {
+                let rate = *output_rate.borrow_and_update();
+                if rate != OutputRate::Unlimited {
+                    let interval = async {
+                        match rate.interval() {
+                            Some(interval) => tokio::time::sleep(interval).await,
+                            None => std::future::pending().await,
+                        }
+                    };
+                    tokio::select! {
+                        _ = &mut shutdown_receiver => {
+                            break;
+                        }
+                        changed = output_rate.changed() => {
+                            if changed.is_err() {
                                 break;
                             }
+                            continue;
                         }
+                        _ = interval => {}
                     }
-                    Ok(line) = stderr.next_line() => {
-                        match line {
-                            Some(line) => {
-                                if output_sender.send(line).is_err() {
-                                    break;
-                                }
+                }
+                let (pipe, mut outputs) = tokio::select! {
+                    _ = &mut shutdown_receiver => {
+                        break;
+                    }
+                    outputs = stdout.next(), if !stdout.is_done() => (Pipe::Stdout, outputs),
+                    outputs = stderr.next(), if !stderr.is_done() => (Pipe::Stderr, outputs),
+                };
+                if let Some(processors) = processors.as_mut() {
+                    outputs = outputs
+                        .into_iter()
+                        .flat_map(|output| match output {
+                            TaskOutput::Line(line) => {
+                                let mut processed = Vec::new();
+                                let line = processors.process(line, |e| {
+                                    processed.push(TaskOutput::Notice(e.to_string()))
+                                });
+                                processed.extend(line.map(TaskOutput::Line));
+                                processed
                             }
-                            None => {
-                                break;
+                            output => vec![output],
+                        })
+                        .collect();
+                }
+                if outputs
+                    .into_iter()
+                    .any(|output| output_sender.send((pipe, output)).is_err())
+                {
+                    break;
+                }
+                message_sender.need_update();
+            }
+        });
+        Ok(output_receiver)
+    }
+
+    /// Executes the command in a pseudo terminal of the given size instead of pipes, so that it
+    /// behaves as in an interactive terminal, e.g. redraws in place and uses colors.
+    #[cfg(unix)]
+    pub fn execute_pty(&mut self, rows: u16, cols: u16) -> color_eyre::Result<TaskPtyReceiver> {
+        use std::io::Read;
+
+        let (shutdown_sender, mut shutdown_receiver) = oneshot::channel();
+        let (output_sender, output_receiver) = mpsc::unbounded_channel();
+        let message_sender = self.message_sender.clone();
+        let pty = pty::open(rows, cols)?;
+        self.command
+            .stdin(pty.slave.try_clone()?)
+            .stdout(pty.slave.try_clone()?)
+            .stderr(pty.slave)
+            .env("TERM", "xterm-256color");
+        // the hooks stay with the command, so it would start another session on a restart
+        if !self.controlling_terminal {
+            self.controlling_terminal = true;
+            unsafe {
+                self.command.pre_exec(pty::make_controlling_terminal);
+            }
+        }
+        let child = self.command.spawn();
+        // drop the slave side held by the command, so that reading the master fails once the
+        // child has exited
+        self.command
+            .stdin(self.stdin_stdio())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        self.spawned(child?, shutdown_sender);
+        let mut master = pty.master;
+        self.pty_master = Some(master.try_clone()?);
+        self.stdin = Some(spawn_pty_writer(master.try_clone()?));
+        let output_rate = self.output_rate.subscribe();
+        let mut transcoder = self
+            .encoding
+            .map(|encoding| encoding.encoding().new_decoder());
+        let cancelled = self.message_sender.supervisor().token();
+        std::thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            while shutdown_receiver.try_recv().is_err() && !cancelled.is_cancelled() {
+                let rate = *output_rate.borrow();
+                if rate == OutputRate::Paused {
+                    std::thread::sleep(PAUSED_POLL_INTERVAL);
+                    continue;
+                }
+                if let Some(interval) = rate.interval() {
+                    std::thread::sleep(interval);
+                }
+                match master.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(len) => {
+                        let bytes = match transcoder.as_mut() {
+                            Some(transcoder) => {
+                                binary::transcode(transcoder, &buffer[..len]).into()
                             }
+                            None => buffer[..len].to_vec(),
+                        };
+                        if output_sender.send(bytes).is_err() {
+                            break;
                         }
                     }
                 }
@@ -168,6 +504,144 @@ impl TaskExecutor {
         });
         Ok(output_receiver)
     }
+
+    /// Resizes the pseudo terminal of a child started with [`TaskExecutor::execute_pty`].
+    #[cfg(unix)]
+    pub fn resize_pty(&self, rows: u16, cols: u16) -> color_eyre::Result<()> {
+        if let Some(master) = self.pty_master.as_ref() {
+            pty::resize(master, rows, cols)?;
+        }
+        Ok(())
+    }
+
+    fn spawned(&mut self, child: Child, shutdown_sender: oneshot::Sender<()>) {
+        if self.kill_with_parent {
+            if let Err(e) = parent_death::adopt(&child) {
+                tracing::warn!(
+                    "{} is not killed along with parallely: {}",
+                    self.raw_command,
+                    e
+                );
+            }
+        }
+        self.pid = child.id();
+        self.child = Some(child);
+        self.started_at = Some(SystemTime::now());
+        self.exited_at = None;
+        self.peak_rss = None;
+        self.cpu_time = None;
+        self.io_stats = None;
+        self.sampled_at = None;
+        self.shutdown_sender = Some(shutdown_sender);
+        self.stdin = None;
+        #[cfg(unix)]
+        {
+            self.pty_master = None;
+        }
+    }
+
+    pub fn started_at(&self) -> Option<SystemTime> {
+        self.started_at
+    }
+
+    pub fn output_rate(&self) -> OutputRate {
+        *self.output_rate.borrow()
+    }
+
+    /// Changes how fast the output is read, takes effect right away if the child is running.
+    pub fn set_output_rate(&self, rate: OutputRate) {
+        self.output_rate.send_replace(rate);
+    }
+
+    pub fn exited_at(&self) -> Option<SystemTime> {
+        self.exited_at
+    }
+
+    /// Only sampled every [`SAMPLE_INTERVAL`] while the child is polled, so short spikes may be
+    /// missed. Always `None` on platforms other than Linux.
+    pub fn peak_rss(&self) -> Option<u64> {
+        self.peak_rss
+    }
+
+    /// Only known on Linux and Windows, once the child has exited.
+    pub fn cpu_time(&self) -> Option<CpuTime> {
+        self.cpu_time
+    }
+
+    /// Sampled while the child is polled, so the I/O right before the exit may be missed.
+    /// Always `None` on platforms other than Linux, see [`io_stats::of_process`].
+    pub fn io_stats(&self) -> Option<IoStats> {
+        self.io_stats
+    }
+
+    /// Records the CPU time of the child if it has exited, on Linux this has to happen before
+    /// the child is reaped.
+    #[cfg(any(target_os = "linux", windows))]
+    fn record_cpu_time(&mut self) {
+        if self.cpu_time.is_some() {
+            return;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(pid) = self.pid {
+            self.cpu_time = cpu_time::of_exited(pid, false);
+        }
+        #[cfg(windows)]
+        if let Some(handle) = self.child.as_ref().and_then(Child::raw_handle) {
+            self.cpu_time = cpu_time::of_process(handle);
+        }
+    }
+
+    /// Sends a signal to the running child without detaching its output, so that anything the
+    /// child prints while shutting down is still shown.
+    pub fn send_signal(&mut self, signal: ChildSignal) -> color_eyre::Result<()> {
+        if let Some(child) = self.child.as_ref() {
+            child.send_signal(signal)?;
+        }
+        Ok(())
+    }
+
+    pub fn stdin_connected(&self) -> bool {
+        #[cfg(unix)]
+        if self.pty_master.is_some() {
+            return true;
+        }
+        self.stdin.as_ref().is_some_and(|stdin| !stdin.is_closed())
+    }
+
+    /// Writes to the standard input of the running child, which is the pseudo terminal of a
+    /// child started with [`TaskExecutor::execute_pty`]. The bytes are queued, so this never
+    /// blocks on a child which does not read its input.
+    pub fn write_stdin(&mut self, bytes: &[u8]) -> color_eyre::Result<()> {
+        match self.stdin.as_ref() {
+            Some(stdin) if stdin.send(bytes.to_vec()).is_ok() => Ok(()),
+            _ => Err(StdinClosedError(self.raw_command.clone()).into()),
+        }
+    }
+
+    /// Closes the standard input of the running child once everything written to it has been
+    /// written, so that it reads the end of its input. A pseudo terminal is sent `Ctrl+D`
+    /// instead, which ends the input of a program reading a line.
+    pub fn close_stdin(&mut self) -> color_eyre::Result<()> {
+        #[cfg(unix)]
+        if self.pty_master.is_some() {
+            return self.write_stdin(b"\x04");
+        }
+        match self.stdin.take() {
+            Some(stdin) if !stdin.is_closed() => Ok(()),
+            _ => Err(StdinClosedError(self.raw_command.clone()).into()),
+        }
+    }
+
+    /// Forcefully kills the running child without waiting for it to exit.
+    pub fn start_kill(&mut self) -> color_eyre::Result<()> {
+        if let Some(child) = self.child.as_mut() {
+            // the rest of its process group too, the child itself is killed below either way
+            #[cfg(unix)]
+            let _ = child.send_signal(ChildSignal::Kill);
+            child.start_kill()?;
+        }
+        Ok(())
+    }
 }
 
 impl Executable for TaskExecutor {
@@ -180,6 +654,10 @@ impl Executable for TaskExecutor {
     }
 
     fn try_wait(&mut self) -> color_eyre::Result<TaskStatus> {
+        if self.child.is_some() && self.exited_at.is_none() {
+            #[cfg(target_os = "linux")]
+            self.record_cpu_time();
+        }
         if let Some(child) = self.child.as_mut() {
             let result = child.try_wait().map(|status| {
                 status
@@ -187,12 +665,34 @@ impl Executable for TaskExecutor {
                         command: self.raw_command.clone(),
                         pid: self.pid(),
                         status,
+                        success: self.succeeded(status),
                     })
                     .unwrap_or(TaskStatus::Executing {
                         command: self.raw_command.clone(),
                         pid: self.pid(),
                     })
             })?;
+            match result {
+                TaskStatus::Executing { pid: Some(pid), .. }
+                    if self
+                        .sampled_at
+                        .is_none_or(|at| at.elapsed() >= SAMPLE_INTERVAL) =>
+                {
+                    self.sampled_at = Some(Instant::now());
+                    if let Some(rss) = peak_rss(pid) {
+                        self.peak_rss = Some(self.peak_rss.map_or(rss, |peak| peak.max(rss)));
+                    }
+                    if let Some(io_stats) = io_stats::of_process(pid) {
+                        self.io_stats = Some(io_stats);
+                    }
+                }
+                TaskStatus::Exited { .. } if self.exited_at.is_none() => {
+                    self.exited_at = Some(SystemTime::now());
+                    #[cfg(windows)]
+                    self.record_cpu_time();
+                }
+                _ => {}
+            }
             Ok(result)
         } else {
             Ok(TaskStatus::Ready(self.raw_command.clone()))
@@ -200,12 +700,22 @@ impl Executable for TaskExecutor {
     }
 
     async fn wait(&mut self) -> color_eyre::Result<TaskStatus> {
+        #[cfg(target_os = "linux")]
+        if let (Some(pid), None, None) = (self.pid, self.exited_at, self.cpu_time) {
+            // waits for the exit without reaping the child, which is left to tokio
+            let cpu_time = tokio::task::spawn_blocking(move || cpu_time::of_exited(pid, true));
+            self.cpu_time = cpu_time.await.ok().flatten();
+        }
         if let Some(child) = self.child.as_mut() {
             let result = child.wait().await?;
+            self.exited_at.get_or_insert_with(SystemTime::now);
+            #[cfg(windows)]
+            self.record_cpu_time();
             Ok(TaskStatus::Exited {
                 command: self.raw_command.clone(),
                 pid: self.pid(),
                 status: result,
+                success: self.succeeded(result),
             })
         } else {
             Ok(TaskStatus::Ready(self.raw_command.clone()))
@@ -217,6 +727,8 @@ impl Executable for TaskExecutor {
             if let Some(sender) = self.shutdown_sender.take() {
                 let _ = sender.send(());
             }
+            #[cfg(unix)]
+            let _ = child.send_signal(ChildSignal::Kill);
             child.kill().await?;
         }
         Ok(())
@@ -238,3 +750,51 @@ impl Executable for TaskExecutor {
         Ok(())
     }
 }
+
+/// Writes everything sent to the returned sender into the standard input of a child, until the
+/// child stops reading it.
+fn spawn_stdin_writer(
+    supervisor: &Supervisor,
+    mut stdin: ChildStdin,
+) -> mpsc::UnboundedSender<Vec<u8>> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<u8>>();
+    supervisor.spawn(async move {
+        while let Some(bytes) = receiver.recv().await {
+            if stdin.write_all(&bytes).await.is_err() || stdin.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+    sender
+}
+
+/// Writes what is sent to the master side of a pseudo terminal on a thread of its own, since the
+/// writes block once the child stops reading and the terminal's buffer is full.
+#[cfg(unix)]
+fn spawn_pty_writer(mut master: std::fs::File) -> mpsc::UnboundedSender<Vec<u8>> {
+    use std::io::Write;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<u8>>();
+    std::thread::spawn(move || {
+        while let Some(bytes) = receiver.blocking_recv() {
+            if master.write_all(&bytes).is_err() {
+                break;
+            }
+        }
+    });
+    sender
+}
+
+/// Reads the peak resident set size (`VmHWM`) of a running process in bytes.
+#[cfg(target_os = "linux")]
+fn peak_rss(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_rss(_pid: u32) -> Option<u64> {
+    None
+}
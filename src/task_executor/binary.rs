@@ -0,0 +1,200 @@
+use crate::config::OutputEncoding;
+use encoding_rs::Decoder;
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// The longest chunk of output read at once, longer lines are split.
+const MAX_CHUNK: usize = 64 * 1024;
+/// How many bytes of a run of binary output are kept for a preview.
+const PREVIEW_LEN: usize = 64;
+/// How long a run of binary output is collected at most before it is reported.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What the reader of a child hands to its console.
+#[derive(Debug)]
+pub enum TaskOutput {
+    Line(String),
+    /// A run of output which is not text, reported instead of shown.
+    Binary {
+        bytes: usize,
+        preview: Vec<u8>,
+    },
+    /// A line parallely adds to the output, e.g. that an output processor failed.
+    Notice(String),
+}
+
+/// Which pipe of a child a [`TaskOutput`] was read from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pipe {
+    Stdout,
+    Stderr,
+}
+
+/// Reads the output of a child from one pipe and decodes it.
+pub struct OutputStream<R> {
+    reader: R,
+    chunk: Vec<u8>,
+    decoder: OutputDecoder,
+    done: bool,
+}
+
+impl<R> OutputStream<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    /// Reads output in `encoding`, UTF-8 without one.
+    pub fn new(reader: R, encoding: Option<OutputEncoding>) -> Self {
+        Self {
+            reader,
+            chunk: Vec::new(),
+            decoder: OutputDecoder {
+                binary: None,
+                transcoder: encoding.map(|encoding| encoding.encoding().new_decoder()),
+            },
+            done: false,
+        }
+    }
+
+    /// Whether the end of the output has been reached or reading it failed.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Reads the next line, which may be reported as binary output. Nothing is lost when the
+    /// future is dropped, so it can be used in `select!`.
+    pub async fn next(&mut self) -> Vec<TaskOutput> {
+        let complete = read_chunk(&mut self.reader, &mut self.chunk).await;
+        let mut outputs = Vec::new();
+        if !self.chunk.is_empty() {
+            outputs = self.decoder.decode(&self.chunk);
+            self.chunk.clear();
+        }
+        if !matches!(complete, Ok(true)) {
+            self.done = true;
+            outputs.extend(self.decoder.finish());
+        }
+        outputs
+    }
+}
+
+/// Appends the output up to and including the next line break to `chunk`, or up to
+/// [`MAX_CHUNK`] bytes. Returns `false` at the end of the output, `chunk` may still hold the last
+/// line then.
+async fn read_chunk<R>(reader: &mut R, chunk: &mut Vec<u8>) -> std::io::Result<bool>
+where
+    R: AsyncBufRead + Unpin,
+{
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(false);
+        }
+        let newline = available.iter().position(|&byte| byte == b'\n');
+        let len = newline
+            .map_or(available.len(), |index| index + 1)
+            .min(MAX_CHUNK - chunk.len());
+        chunk.extend_from_slice(&available[..len]);
+        reader.consume(len);
+        if chunk.ends_with(b"\n") || chunk.len() >= MAX_CHUNK {
+            return Ok(true);
+        }
+    }
+}
+
+/// Turns chunks of output into lines, collecting consecutive chunks which are not text into a
+/// single [`TaskOutput::Binary`].
+struct OutputDecoder {
+    binary: Option<(usize, Vec<u8>, Instant)>,
+    /// Decodes output which is not UTF-8, keeping a character split between two chunks.
+    transcoder: Option<Decoder>,
+}
+
+impl OutputDecoder {
+    fn decode(&mut self, chunk: &[u8]) -> Vec<TaskOutput> {
+        let mut outputs = Vec::new();
+        let text = match self.transcoder.as_mut() {
+            Some(transcoder) => Cow::Owned(transcode(transcoder, chunk)),
+            None => String::from_utf8_lossy(chunk),
+        };
+        if chunk.contains(&0) || is_binary(&text) {
+            let (bytes, preview, _) = self
+                .binary
+                .get_or_insert_with(|| (0, Vec::new(), Instant::now()));
+            *bytes += chunk.len();
+            let missing = PREVIEW_LEN.saturating_sub(preview.len()).min(chunk.len());
+            preview.extend_from_slice(&chunk[..missing]);
+            if self
+                .binary
+                .as_ref()
+                .is_some_and(|(_, _, since)| since.elapsed() >= REPORT_INTERVAL)
+            {
+                outputs.extend(self.finish());
+            }
+            return outputs;
+        }
+        outputs.extend(self.finish());
+        let line = text.strip_suffix('\n').unwrap_or(&text);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        outputs.push(TaskOutput::Line(line.to_string()));
+        outputs
+    }
+
+    /// Reports the pending run of binary output, if any.
+    fn finish(&mut self) -> Option<TaskOutput> {
+        self.binary
+            .take()
+            .map(|(bytes, preview, _)| TaskOutput::Binary { bytes, preview })
+    }
+}
+
+/// Decodes the next bytes of output which is not UTF-8. Bytes at the end which start a character
+/// are kept by the decoder until the rest of it follows.
+pub fn transcode(transcoder: &mut Decoder, bytes: &[u8]) -> String {
+    let capacity = transcoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len() * 3);
+    let mut text = String::with_capacity(capacity);
+    let _ = transcoder.decode_to_string(bytes, &mut text, false);
+    text
+}
+
+/// Decoded output counts as binary if more than 30% of it is invalid or control characters which
+/// do not appear in text. Output with a NUL byte is binary anyway.
+fn is_binary(text: &str) -> bool {
+    let (mut total, mut suspicious) = (0, 0);
+    for c in text.chars() {
+        total += 1;
+        let text_control = matches!(c, '\t' | '\n' | '\r' | '\x1b' | '\x07' | '\x08' | '\x0c');
+        if c == char::REPLACEMENT_CHARACTER || (c.is_control() && !text_control) {
+            suspicious += 1;
+        }
+    }
+    suspicious * 10 > total * 3
+}
+
+/// Formats the bytes like `hexdump -C`: the offset, 16 bytes in hex and the printable ones.
+pub fn hexdump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let printable = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!("{:08x}  {:<47}  |{}|", row * 16, hex, printable)
+        })
+        .collect()
+}
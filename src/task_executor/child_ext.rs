@@ -1,4 +1,7 @@
 use crate::shutdown_handler::ShutdownReason;
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -21,16 +24,88 @@ pub enum KillError {
     "#
     )]
     NoWait,
+    #[error("The signal `{0}` is not supported on this platform")]
+    Unsupported(ChildSignal),
     #[cfg(windows)]
     #[error("An unknown error occurred")]
     Win32Error(u32),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
+#[error("Unknown signal `{0}`, expected a name such as `SIGUSR2`/`USR2` or a signal number")]
+pub struct ParseSignalError(String);
+
+/// A signal which can be sent to a child. Besides the named signals, any signal number can be
+/// sent on unix with [`ChildSignal::Other`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
 pub enum ChildSignal {
     Interrupt,
     Quit,
     Terminate,
+    Hangup,
+    User1,
+    User2,
+    Kill,
+    Other(i32),
+}
+
+impl ChildSignal {
+    pub const ALL: [ChildSignal; 7] = [
+        ChildSignal::Interrupt,
+        ChildSignal::Terminate,
+        ChildSignal::Hangup,
+        ChildSignal::User1,
+        ChildSignal::User2,
+        ChildSignal::Quit,
+        ChildSignal::Kill,
+    ];
+}
+
+impl Display for ChildSignal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChildSignal::Interrupt => write!(f, "SIGINT"),
+            ChildSignal::Quit => write!(f, "SIGQUIT"),
+            ChildSignal::Terminate => write!(f, "SIGTERM"),
+            ChildSignal::Hangup => write!(f, "SIGHUP"),
+            ChildSignal::User1 => write!(f, "SIGUSR1"),
+            ChildSignal::User2 => write!(f, "SIGUSR2"),
+            ChildSignal::Kill => write!(f, "SIGKILL"),
+            ChildSignal::Other(number) => write!(f, "signal {}", number),
+        }
+    }
+}
+
+impl FromStr for ChildSignal {
+    type Err = ParseSignalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.trim().to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        let signal = match name {
+            "INT" => ChildSignal::Interrupt,
+            "QUIT" => ChildSignal::Quit,
+            "TERM" => ChildSignal::Terminate,
+            "HUP" => ChildSignal::Hangup,
+            "USR1" => ChildSignal::User1,
+            "USR2" => ChildSignal::User2,
+            "KILL" => ChildSignal::Kill,
+            number => match number.parse::<i32>() {
+                Ok(number) if number > 0 => ChildSignal::Other(number),
+                _ => return Err(ParseSignalError(s.to_string())),
+            },
+        };
+        Ok(signal)
+    }
+}
+
+impl TryFrom<String> for ChildSignal {
+    type Error = ParseSignalError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
 }
 
 #[cfg(unix)]
@@ -40,6 +115,11 @@ impl From<ChildSignal> for libc::c_int {
             ChildSignal::Interrupt => libc::SIGINT,
             ChildSignal::Quit => libc::SIGQUIT,
             ChildSignal::Terminate => libc::SIGTERM,
+            ChildSignal::Hangup => libc::SIGHUP,
+            ChildSignal::User1 => libc::SIGUSR1,
+            ChildSignal::User2 => libc::SIGUSR2,
+            ChildSignal::Kill => libc::SIGKILL,
+            ChildSignal::Other(number) => number,
         }
     }
 }
@@ -53,6 +133,8 @@ impl From<ShutdownReason> for ChildSignal {
             ShutdownReason::Sigterm => ChildSignal::Terminate,
             ShutdownReason::Sigquit => ChildSignal::Quit,
             ShutdownReason::End => ChildSignal::Terminate,
+            ShutdownReason::Failure(_) => ChildSignal::Terminate,
+            ShutdownReason::Hangup(_) => ChildSignal::Terminate,
         }
     }
 }
@@ -75,17 +157,23 @@ pub trait ChildExt {
 }
 
 impl ChildExt for tokio::process::Child {
+    /// Signals the process group the child leads, see [`crate::task_executor::TaskExecutor`],
+    /// so that e.g. the `node` started by `npm` gets the signal as well, as it would from the
+    /// terminal.
     #[cfg(unix)]
     fn send_signal(&self, signal: ChildSignal) -> color_eyre::Result<(), KillError> {
-        let pid = self.id().take();
+        let pid = self.id();
         match pid {
             Some(0) | None => Err(KillError::InvalidPid),
             Some(pid) => {
-                let result = unsafe { libc::kill(pid as i32, signal.into()) };
-                match result {
-                    libc::EPERM => Err(KillError::NoPermission),
-                    libc::ESRCH => Err(KillError::NoWait),
-                    _ => Ok(()),
+                let result = unsafe { libc::kill(-(pid as i32), signal.into()) };
+                if result == 0 {
+                    return Ok(());
+                }
+                match std::io::Error::last_os_error().raw_os_error() {
+                    Some(libc::EPERM) => Err(KillError::NoPermission),
+                    Some(libc::ESRCH) => Err(KillError::NoWait),
+                    _ => Err(KillError::Unsupported(signal)),
                 }
             }
         }
@@ -93,7 +181,7 @@ impl ChildExt for tokio::process::Child {
 
     #[cfg(windows)]
     fn send_signal(&self, signal: ChildSignal) -> color_eyre::Result<(), KillError> {
-        let pid = self.id().take();
+        let pid = self.id();
         match pid {
             Some(0) | None => Err(KillError::InvalidPid),
             Some(pid) => match signal {
@@ -110,7 +198,7 @@ impl ChildExt for tokio::process::Child {
                         }
                     }
                 }
-                ChildSignal::Quit | ChildSignal::Terminate => {
+                ChildSignal::Quit | ChildSignal::Terminate | ChildSignal::Kill => {
                     use windows_sys::Win32::Foundation::GetLastError;
                     use windows_sys::Win32::Foundation::FALSE;
                     use windows_sys::Win32::System::Threading::OpenProcess;
@@ -130,6 +218,7 @@ impl ChildExt for tokio::process::Child {
                         }
                     }
                 }
+                _ => Err(KillError::Unsupported(signal)),
             },
         }
     }
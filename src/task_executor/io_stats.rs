@@ -0,0 +1,89 @@
+use crate::report;
+use std::fmt::{Display, Formatter};
+
+/// The bytes a child has read from and written to storage, and sent and received over the
+/// network where that can be told apart from the rest of the system.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct IoStats {
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    /// Only known for a child in a network namespace of its own, e.g. a container, since Linux
+    /// does not count the traffic of single processes.
+    pub net: Option<NetStats>,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct NetStats {
+    pub received_bytes: u64,
+    pub sent_bytes: u64,
+}
+
+impl Display for IoStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "disk {} read, {} written",
+            report::format_bytes(self.read_bytes),
+            report::format_bytes(self.write_bytes)
+        )?;
+        if let Some(net) = self.net {
+            write!(
+                f,
+                ", net {} in, {} out",
+                report::format_bytes(net.received_bytes),
+                report::format_bytes(net.sent_bytes)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the I/O counters of a running process from `/proc/<pid>/io`, which include the
+/// descendants it has waited for.
+#[cfg(target_os = "linux")]
+pub fn of_process(pid: u32) -> Option<IoStats> {
+    let io = std::fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+    let field = |name: &str| {
+        io.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    Some(IoStats {
+        read_bytes: field("read_bytes")?,
+        // writes to files truncated before they reached the disk never happened
+        write_bytes: field("write_bytes")?
+            .saturating_sub(field("cancelled_write_bytes").unwrap_or(0)),
+        net: net_of_process(pid),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn of_process(_pid: u32) -> Option<IoStats> {
+    None
+}
+
+/// Sums up `/proc/<pid>/net/dev` of every interface but the loopback, if the process is in
+/// another network namespace than parallely.
+#[cfg(target_os = "linux")]
+fn net_of_process(pid: u32) -> Option<NetStats> {
+    let namespace = std::fs::read_link(format!("/proc/{}/ns/net", pid)).ok()?;
+    if std::fs::read_link("/proc/self/ns/net").ok()? == namespace {
+        return None;
+    }
+    let dev = std::fs::read_to_string(format!("/proc/{}/net/dev", pid)).ok()?;
+    let mut stats = NetStats::default();
+    // two header lines, then `iface: rx_bytes rx_packets ... (8 fields) tx_bytes ...`
+    for line in dev.lines().skip(2) {
+        let (interface, counters) = line.split_once(':')?;
+        if interface.trim() == "lo" {
+            continue;
+        }
+        let counters = counters
+            .split_whitespace()
+            .map(|counter| counter.parse::<u64>().unwrap_or(0))
+            .collect::<Vec<_>>();
+        stats.received_bytes += counters.first().copied().unwrap_or(0);
+        stats.sent_bytes += counters.get(8).copied().unwrap_or(0);
+    }
+    Some(stats)
+}
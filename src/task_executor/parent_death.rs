@@ -0,0 +1,90 @@
+use std::io;
+use tokio::process::{Child, Command};
+
+/// Has the kernel kill the child with `SIGKILL` once parallely dies, even if it is killed with
+/// `SIGKILL` itself or crashes before stopping its tasks. The signal is actually sent once the
+/// thread which spawned the child exits, the tasks are spawned by the main thread.
+#[cfg(target_os = "linux")]
+pub fn kill_with_parent(command: &mut Command) {
+    let parent = std::process::id();
+    let hook = move || {
+        if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // parallely died before the signal was asked for, the child has been adopted already
+        if unsafe { libc::getppid() } as u32 != parent {
+            return Err(io::Error::from_raw_os_error(libc::ESRCH));
+        }
+        Ok(())
+    };
+    unsafe {
+        command.pre_exec(hook);
+    }
+}
+
+/// Elsewhere the child is left running if parallely dies without stopping it, except on
+/// Windows, see [`adopt`].
+#[cfg(not(target_os = "linux"))]
+pub fn kill_with_parent(_command: &mut Command) {}
+
+/// Puts the child into a job object which kills its processes once its last handle is closed.
+/// The handle is held by parallely until it exits, so the system closes it however parallely
+/// ends. Processes the child starts before it is put into the job are left out.
+#[cfg(windows)]
+pub fn adopt(child: &Child) -> io::Result<()> {
+    use windows_sys::Win32::System::JobObjects::AssignProcessToJobObject;
+
+    let Some(handle) = child.raw_handle() else {
+        return Ok(());
+    };
+    let job = job()?;
+    if unsafe { AssignProcessToJobObject(job as _, handle as _) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The child is killed through [`kill_with_parent`] instead.
+#[cfg(not(windows))]
+pub fn adopt(_child: &Child) -> io::Result<()> {
+    Ok(())
+}
+
+/// The job object of parallely, created on first use and never closed, as an address since a
+/// handle cannot be shared between threads.
+#[cfg(windows)]
+fn job() -> io::Result<usize> {
+    use std::sync::OnceLock;
+    use windows_sys::Win32::System::JobObjects::{
+        CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    static JOB: OnceLock<Result<usize, i32>> = OnceLock::new();
+    let job = JOB.get_or_init(|| {
+        let last_error = || {
+            io::Error::last_os_error()
+                .raw_os_error()
+                .unwrap_or_default()
+        };
+        let job = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if job.is_null() {
+            return Err(last_error());
+        }
+        let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+        limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        let ok = unsafe {
+            SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                &limits as *const _ as *const _,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        };
+        if ok == 0 {
+            return Err(last_error());
+        }
+        Ok(job as usize)
+    });
+    (*job).map_err(io::Error::from_raw_os_error)
+}
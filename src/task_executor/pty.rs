@@ -0,0 +1,64 @@
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd};
+
+/// A pseudo terminal, the child gets the slave side as its stdin, stdout and stderr.
+pub struct Pty {
+    pub master: File,
+    pub slave: File,
+}
+
+pub fn open(rows: u16, cols: u16) -> io::Result<Pty> {
+    let mut master = 0;
+    let mut slave = 0;
+    let mut size = window_size(rows, cols);
+    // the size is only read, but taken as `*mut` on some platforms
+    #[allow(clippy::unnecessary_mut_passed)]
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut size,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+    Ok(Pty { master, slave })
+}
+
+/// Resizes the terminal, the child is notified with `SIGWINCH`.
+pub fn resize(master: &File, rows: u16, cols: u16) -> io::Result<()> {
+    let size = window_size(rows, cols);
+    let result = unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &size) };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Runs in the child before `exec`: starts a new session with the pseudo terminal on stdin as
+/// its controlling terminal.
+pub fn make_controlling_terminal() -> io::Result<()> {
+    unsafe {
+        if libc::setsid() == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn window_size(rows: u16, cols: u16) -> libc::winsize {
+    libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
@@ -0,0 +1,213 @@
+use crate::ansi;
+use crate::instance;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Where and how the output of the tasks is persisted, see `--log-dir`.
+#[derive(Debug, Clone)]
+pub struct LogOptions {
+    pub dir: PathBuf,
+    /// Rotate the log once it has grown to this many bytes.
+    pub max_size: u64,
+    /// Rotate the log once it has been written to for this long.
+    pub rotate_every: Option<Duration>,
+    /// How many rotated logs to keep per task.
+    pub keep: usize,
+}
+
+#[derive(Debug, Error)]
+#[error("More than one task is named `{0}`, their output cannot be logged to separate files")]
+pub struct SharedLogError(String);
+
+/// Writes the output of a task into `<dir>/<task>.log` without ANSI escape sequences. Rotated
+/// logs are compressed into `<task>.<timestamp>.log.gz` in the background.
+pub struct TaskLog {
+    options: LogOptions,
+    stem: String,
+    file: Option<BufWriter<File>>,
+    size: u64,
+    opened_at: Instant,
+}
+
+impl TaskLog {
+    /// The file is only created once the first line is written.
+    pub fn new(options: LogOptions, key: &str) -> Self {
+        Self {
+            options,
+            stem: stem(key),
+            file: None,
+            size: 0,
+            opened_at: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.options.dir.join(format!("{}.log", self.stem))
+    }
+
+    pub fn write_line(&mut self, line: &str) -> color_eyre::Result<()> {
+        let expired = self
+            .options
+            .rotate_every
+            .is_some_and(|every| self.opened_at.elapsed() >= every);
+        if self.file.is_some() && (self.size >= self.options.max_size || expired) {
+            self.rotate()?;
+        }
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.path())?;
+                self.size = file.metadata()?.len();
+                self.opened_at = Instant::now();
+                self.file.insert(BufWriter::new(file))
+            }
+        };
+        let line = ansi::strip(line);
+        writeln!(file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> color_eyre::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Renames the current log and compresses it in the background, the next line starts a new
+    /// log.
+    fn rotate(&mut self) -> color_eyre::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let rotated = self
+            .options
+            .dir
+            .join(format!("{}.{}.log", self.stem, timestamp));
+        std::fs::rename(self.path(), &rotated)?;
+        let dir = self.options.dir.clone();
+        let stem = self.stem.clone();
+        let keep = self.options.keep;
+        std::thread::spawn(move || {
+            if let Err(e) = compress(&rotated) {
+                tracing::error!("Failed to compress {}: {:?}", rotated.display(), e);
+            }
+            if let Err(e) = prune(&dir, &stem, keep) {
+                tracing::error!("Failed to remove old logs of {}: {:?}", stem, e);
+            }
+        });
+        Ok(())
+    }
+}
+
+/// The name of the logs of the task with the key: the key with every character other than ASCII
+/// letters, digits, `-` and `_` replaced, followed by a hash of the key if it was changed, so that
+/// two keys never share their logs.
+fn stem(key: &str) -> String {
+    let stem = key
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .take(64)
+        .collect::<String>();
+    match stem == key {
+        true => stem,
+        false => format!("{}-{:08x}", stem, instance::fnv1a(key.as_bytes()) as u32),
+    }
+}
+
+/// Fails if two of the tasks with the keys would write into the same log, i.e. have the same key.
+pub fn check_unique<'a>(keys: impl IntoIterator<Item = &'a str>) -> Result<(), SharedLogError> {
+    let mut stems = HashSet::new();
+    for key in keys {
+        if !stems.insert(stem(key)) {
+            return Err(SharedLogError(key.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Compresses the file into `<file>.gz` and removes it.
+fn compress(path: &Path) -> std::io::Result<()> {
+    let mut compressed_path = path.as_os_str().to_owned();
+    compressed_path.push(".gz");
+    let mut encoder = GzEncoder::new(File::create(compressed_path)?, Compression::default());
+    std::io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(path)
+}
+
+/// Removes all but the newest `keep` compressed logs of the task.
+fn prune(dir: &Path, stem: &str, keep: usize) -> std::io::Result<()> {
+    let mut rotated = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            name.strip_prefix(stem)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .and_then(|rest| rest.strip_suffix(".log.gz"))
+                .is_some_and(|timestamp| timestamp.chars().all(|c| c.is_ascii_digit()))
+        })
+        .collect::<Vec<_>>();
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(keep);
+    for name in rotated.into_iter().take(excess) {
+        std::fs::remove_file(dir.join(name))?;
+    }
+    Ok(())
+}
+
+/// Parses a size such as `512K`, `10M` or `1G` (powers of 1024), or a plain number of bytes.
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let (number, unit) = size.split_at(size.trim_end_matches(|c: char| c.is_alphabetic()).len());
+    let multiplier = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(format!("unknown size unit `{}`, expected K, M or G", unit)),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .map(|number| number * multiplier)
+        .map_err(|e| format!("invalid size `{}`: {}", size, e))
+}
+
+/// Parses a duration such as `30s`, `15m`, `12h` or `1d`, or a plain number of seconds.
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+    let (number, unit) =
+        duration.split_at(duration.trim_end_matches(|c: char| c.is_alphabetic()).len());
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "unknown duration unit `{}`, expected s, m, h or d",
+                unit
+            ))
+        }
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .map(|number| Duration::from_secs(number * seconds))
+        .map_err(|e| format!("invalid duration `{}`: {}", duration, e))
+}
@@ -0,0 +1,162 @@
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+/// Where a task is in its life, richer than the [`crate::task_executor::TaskStatus`] of its
+/// process. The state only changes through [`TaskState::transition`], which rejects changes
+/// that cannot happen, and every change is announced as a [`Transition`] on the message bus.
+///
+/// ```text
+/// Pending → Starting → Running → Ready → Stopping → Exited / Killed / Failed
+///                         └──────────────────────→ TimedOut
+/// ```
+///
+/// A task which has ended is started again from any of the final states.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TaskState {
+    /// Waiting for the tasks it depends on to become ready.
+    Pending,
+    /// Its process is being spawned.
+    Starting,
+    Running,
+    /// Its ready probe has passed.
+    Ready,
+    /// It was asked to stop and has not exited yet.
+    Stopping,
+    /// It exited successfully.
+    Exited,
+    /// It was killed by a signal.
+    Killed,
+    /// It exited with a failure, could not be spawned or was never started.
+    Failed,
+    /// It did not become ready in time.
+    TimedOut,
+}
+
+#[derive(Debug, Error)]
+#[error("A task cannot go from {from} to {to}")]
+pub struct TransitionError {
+    pub from: TaskState,
+    pub to: TaskState,
+}
+
+/// A change of the state of a task, sent on the message bus.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// The [`crate::config::TaskConfig::key`] of the task.
+    pub task: String,
+    pub from: TaskState,
+    pub to: TaskState,
+}
+
+impl TaskState {
+    /// Whether the task has a process which has not been seen exiting yet.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            TaskState::Starting | TaskState::Running | TaskState::Ready | TaskState::Stopping
+        )
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TaskState::Exited | TaskState::Killed | TaskState::Failed | TaskState::TimedOut
+        )
+    }
+
+    /// The state after changing to `to`, if the task can change from this state to it.
+    pub fn transition(self, to: TaskState) -> Result<TaskState, TransitionError> {
+        use TaskState::*;
+        let valid = match (self, to) {
+            (Pending, Starting | Failed | TimedOut) => true,
+            (Starting, Running | Failed) => true,
+            (Running, Ready | Stopping | Exited | Killed | Failed | TimedOut) => true,
+            (Ready, Stopping | Exited | Killed | Failed) => true,
+            (Stopping, Exited | Killed | Failed) => true,
+            (from, Starting) => from.is_final(),
+            _ => false,
+        };
+        match valid {
+            true => Ok(to),
+            false => Err(TransitionError { from: self, to }),
+        }
+    }
+}
+
+impl Display for TaskState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TaskState::Pending => "pending",
+            TaskState::Starting => "starting",
+            TaskState::Running => "running",
+            TaskState::Ready => "ready",
+            TaskState::Stopping => "stopping",
+            TaskState::Exited => "exited",
+            TaskState::Killed => "killed",
+            TaskState::Failed => "failed",
+            TaskState::TimedOut => "timed out",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskState::{self, *};
+
+    const STATES: [TaskState; 9] = [
+        Pending, Starting, Running, Ready, Stopping, Exited, Killed, Failed, TimedOut,
+    ];
+
+    /// The changes [`TaskState::transition`] allows, every other one is rejected.
+    const ALLOWED: [(TaskState, TaskState); 22] = [
+        (Pending, Starting),
+        (Pending, Failed),
+        (Pending, TimedOut),
+        (Starting, Running),
+        (Starting, Failed),
+        (Running, Ready),
+        (Running, Stopping),
+        (Running, Exited),
+        (Running, Killed),
+        (Running, Failed),
+        (Running, TimedOut),
+        (Ready, Stopping),
+        (Ready, Exited),
+        (Ready, Killed),
+        (Ready, Failed),
+        (Stopping, Exited),
+        (Stopping, Killed),
+        (Stopping, Failed),
+        (Exited, Starting),
+        (Killed, Starting),
+        (Failed, Starting),
+        (TimedOut, Starting),
+    ];
+
+    #[test]
+    fn allows_only_the_listed_changes() {
+        for from in STATES {
+            for to in STATES {
+                let allowed = ALLOWED.contains(&(from, to));
+                assert_eq!(
+                    from.transition(to).is_ok(),
+                    allowed,
+                    "{} -> {} should be {}",
+                    from,
+                    to,
+                    if allowed { "allowed" } else { "rejected" }
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn restarts_only_once_the_run_has_ended() {
+        let stopping = Running.transition(Stopping).unwrap();
+        assert!(stopping.transition(Starting).is_err());
+        let killed = stopping.transition(Killed).unwrap();
+        assert_eq!(killed.transition(Starting).unwrap(), Starting);
+        assert!(Ready.transition(Starting).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::ansi;
+use std::fmt::{Display, Formatter};
+
+/// Pass/fail counts extracted from the output of a test runner. Recognizes the summary lines of
+/// `cargo test`, jest and pytest.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+impl TestSummary {
+    /// Updates the summary if the line of output is the summary line of a test runner.
+    pub fn observe(summary: &mut Option<TestSummary>, line: &str) {
+        let line = ansi::strip(line);
+        let line = line.trim();
+        if let Some(counts) = line.strip_prefix("test result:") {
+            // cargo prints one summary per test binary
+            let parsed = Self::parse_counts(counts);
+            let total = summary.get_or_insert_with(TestSummary::default);
+            total.passed += parsed.passed;
+            total.failed += parsed.failed;
+            total.skipped += parsed.skipped;
+        } else if let Some(counts) = line.strip_prefix("Tests:") {
+            // jest
+            *summary = Some(Self::parse_counts(counts));
+        } else if line.starts_with('=') && line.ends_with('=') && line.contains(" in ") {
+            // pytest, e.g. `==== 1 failed, 2 passed in 0.12s ====`
+            let parsed = Self::parse_counts(line);
+            if parsed != TestSummary::default() {
+                *summary = Some(parsed);
+            }
+        }
+    }
+
+    /// Picks up every `<number> <passed|failed|...>` pair.
+    fn parse_counts(counts: &str) -> TestSummary {
+        let mut summary = TestSummary::default();
+        let words = counts
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+        for pair in words.windows(2) {
+            let Ok(count) = pair[0].parse::<usize>() else {
+                continue;
+            };
+            match pair[1] {
+                "passed" => summary.passed += count,
+                "failed" | "error" | "errors" => summary.failed += count,
+                "ignored" | "skipped" | "todo" => summary.skipped += count,
+                _ => {}
+            }
+        }
+        summary
+    }
+}
+
+impl Display for TestSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} passed, {} failed", self.passed, self.failed)?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped", self.skipped)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TestSummary;
+
+    fn observe(lines: &[&str]) -> Option<TestSummary> {
+        let mut summary = None;
+        for line in lines {
+            TestSummary::observe(&mut summary, line);
+        }
+        summary
+    }
+
+    fn counts(passed: usize, failed: usize, skipped: usize) -> Option<TestSummary> {
+        Some(TestSummary {
+            passed,
+            failed,
+            skipped,
+        })
+    }
+
+    #[test]
+    fn adds_up_the_summary_of_every_cargo_test_binary() {
+        let summary = observe(&[
+            "test result: ok. 6 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s",
+            "\x1b[31mtest result: FAILED. 2 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out\x1b[0m",
+        ]);
+        assert_eq!(summary, counts(8, 1, 1));
+    }
+
+    #[test]
+    fn reads_the_jest_summary() {
+        let summary = observe(&["Tests:       1 failed, 2 skipped, 1 todo, 4 passed, 8 total"]);
+        assert_eq!(summary, counts(4, 1, 3));
+    }
+
+    #[test]
+    fn reads_the_pytest_summary() {
+        let summary = observe(&[
+            "============================= test session starts ==============================",
+            "=================== 1 failed, 2 passed, 1 skipped, 1 error in 0.12s ===================",
+        ]);
+        assert_eq!(summary, counts(2, 2, 1));
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        let summary = observe(&[
+            "running 6 tests",
+            "test config::tests::finds_the_file_of_an_ancestor ... ok",
+            "==================== warnings summary ====================",
+            "Tests passed in 3 files",
+        ]);
+        assert_eq!(summary, None);
+    }
+
+    #[test]
+    fn counts_what_a_cut_off_summary_line_has() {
+        assert_eq!(
+            observe(&["test result: ok. 3 passed; 0 fai"]),
+            counts(3, 0, 0)
+        );
+        assert_eq!(observe(&["Tests:"]), counts(0, 0, 0));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::i18n::{Lang, Phrase};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Clear, Paragraph, Widget};
+use std::time::{Duration, SystemTime};
+
+/// When one task was started, became ready and stopped, see [`Timeline`].
+pub struct TimelineEntry {
+    pub label: String,
+    pub started_at: Option<SystemTime>,
+    pub ready_at: Option<SystemTime>,
+    pub exited_at: Option<SystemTime>,
+    pub failed: bool,
+    /// Whether the task is still waiting for its dependencies if it has not been started.
+    pub waiting: bool,
+}
+
+/// A popup with a Gantt-style chart of the startup of the tasks since `since`: booting tasks are
+/// drawn yellow until they become ready, from then on green, and red if they failed.
+pub struct Timeline {
+    pub since: SystemTime,
+    pub entries: Vec<TimelineEntry>,
+    pub lang: Lang,
+}
+
+const LABEL_WIDTH: usize = 24;
+const SUMMARY_WIDTH: usize = 14;
+
+impl Timeline {
+    fn offset(&self, time: SystemTime) -> Duration {
+        time.duration_since(self.since).unwrap_or_default()
+    }
+
+    fn summary(&self, entry: &TimelineEntry) -> String {
+        let text = |phrase| self.lang.text(phrase);
+        match (entry.started_at, entry.ready_at, entry.exited_at) {
+            (None, _, _) if entry.waiting => text(Phrase::Waiting).to_string(),
+            (None, _, _) => text(Phrase::NotStarted).to_string(),
+            (Some(started_at), Some(ready_at), _) => format!(
+                "{} {:.1}s",
+                text(Phrase::ReadyAfter),
+                ready_at
+                    .duration_since(started_at)
+                    .unwrap_or_default()
+                    .as_secs_f64()
+            ),
+            (Some(started_at), None, Some(exited_at)) => format!(
+                "{} {:.1}s",
+                text(Phrase::Ran),
+                exited_at
+                    .duration_since(started_at)
+                    .unwrap_or_default()
+                    .as_secs_f64()
+            ),
+            (Some(_), None, None) => text(Phrase::Booting).to_string(),
+        }
+    }
+}
+
+impl Widget for &Timeline {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let height = (self.entries.len() as u16 + 3).min(area.height);
+        let [popup_area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::horizontal([Constraint::Percentage(90)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let now = SystemTime::now();
+        let total = self.offset(now).max(Duration::from_millis(1));
+        let text = |phrase| format!(" {} ", self.lang.text(phrase));
+        let block = Block::bordered()
+            .title(text(Phrase::StartupTimeline).magenta().bold())
+            .title_bottom(Line::from(vec![
+                text(Phrase::Booting).into(),
+                "▆▆ ".yellow(),
+                text(Phrase::ReadyRunning).into(),
+                "▆▆ ".green(),
+                text(Phrase::Failed).into(),
+                "▆▆ ".red(),
+                text(Phrase::Close).into(),
+                "<T> ".blue().bold(),
+            ]))
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(popup_area);
+        let bar_width = (inner.width as usize)
+            .saturating_sub(LABEL_WIDTH + SUMMARY_WIDTH + 2)
+            .max(1);
+        // the column of the bar a point in time falls into
+        let column = |time: SystemTime| {
+            let ratio = self.offset(time).as_secs_f64() / total.as_secs_f64();
+            ((ratio * bar_width as f64) as usize).min(bar_width - 1)
+        };
+
+        let mut lines = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut label = entry
+                    .label
+                    .chars()
+                    .take(LABEL_WIDTH - 1)
+                    .collect::<String>();
+                label = format!("{:<width$} ", label, width = LABEL_WIDTH - 1);
+                let mut spans = vec![Span::from(label).blue()];
+                let mut bar = vec![Span::raw(" "); bar_width];
+                if let Some(started_at) = entry.started_at {
+                    let end = column(entry.exited_at.unwrap_or(now));
+                    let ready = entry.ready_at.map(column);
+                    for (index, cell) in bar
+                        .iter_mut()
+                        .enumerate()
+                        .take(end + 1)
+                        .skip(column(started_at))
+                    {
+                        let style = match ready {
+                            _ if entry.failed => Style::new().red(),
+                            Some(ready) if index >= ready => Style::new().green(),
+                            _ => Style::new().yellow(),
+                        };
+                        *cell = Span::styled("█", style);
+                    }
+                }
+                spans.extend(bar);
+                spans.push(Span::raw(format!(
+                    " {:>width$}",
+                    self.summary(entry),
+                    width = SUMMARY_WIDTH
+                )));
+                Line::from(spans)
+            })
+            .collect::<Vec<_>>();
+        let end_label = format!("{:.1}s", total.as_secs_f64());
+        lines.push(Line::from(vec![
+            Span::raw(" ".repeat(LABEL_WIDTH)),
+            "0s".dark_gray(),
+            Span::raw(" ".repeat(bar_width.saturating_sub(2 + end_label.len()))),
+            end_label.dark_gray(),
+        ]));
+
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines).block(block).render(popup_area, buf);
+    }
+}
@@ -0,0 +1,64 @@
+use jiff::civil::{DateTime, Time};
+use jiff::tz::TimeZone;
+use jiff::{SignedDuration, Timestamp, Zoned};
+use std::time::SystemTime;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error(
+    "Invalid time `{0}`, expected e.g. `14:03`, `14:03:21`, `2024-05-01 14:03:21`, \
+     `2024-05-01T12:03:21Z` or `5m ago`"
+)]
+pub struct TimestampError(String);
+
+/// Parses a point in time typed in to go to, e.g. from an error report or another log:
+///
+/// * a time of day in the local time zone, `14:03` or `14:03:21.250`, today or yesterday if it
+///   is still to come
+/// * a date and time in the local time zone, `2024-05-01 14:03:21`
+/// * a date and time with an offset, `2024-05-01T12:03:21Z` or `2024-05-01T14:03:21+02:00`
+/// * a time before now, `5m ago`, `-90s` or `-1h30m`
+pub fn parse(input: &str) -> Result<SystemTime, TimestampError> {
+    let input = input.trim();
+    let error = || TimestampError(input.to_string());
+    let now = Zoned::now();
+
+    if let Some(ago) = input
+        .strip_suffix("ago")
+        .or_else(|| input.strip_prefix('-'))
+    {
+        let duration = ago.trim().parse::<SignedDuration>().map_err(|_| error())?;
+        return now
+            .timestamp()
+            .checked_sub(duration)
+            .map(SystemTime::from)
+            .map_err(|_| error());
+    }
+    if let Ok(timestamp) = input.parse::<Timestamp>() {
+        return Ok(SystemTime::from(timestamp));
+    }
+    if let Ok(datetime) = input.parse::<DateTime>() {
+        return datetime
+            .to_zoned(TimeZone::system())
+            .map(|zoned| SystemTime::from(zoned.timestamp()))
+            .map_err(|_| error());
+    }
+    let time = input.parse::<Time>().map_err(|_| error())?;
+    let mut zoned = now.with().time(time).build().map_err(|_| error())?;
+    if zoned > now {
+        zoned = zoned.yesterday().map_err(|_| error())?;
+    }
+    Ok(SystemTime::from(zoned.timestamp()))
+}
+
+/// The time of day in the local time zone, e.g. `14:03:21`.
+pub fn format(time: SystemTime) -> String {
+    Timestamp::try_from(time)
+        .map(|timestamp| {
+            timestamp
+                .to_zoned(TimeZone::system())
+                .strftime("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,189 @@
+use crate::config::TaskConfig;
+use crate::console::Console;
+use crate::message;
+use crate::message::Message;
+use crate::shutdown_handler;
+use crate::shutdown_handler::{HangupAction, ShutdownHandler, ShutdownReason};
+use crate::task_events::EventFormat;
+use crate::task_executor::{Executable, TaskStatus};
+use crate::task_state::TaskState;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio_stream::StreamExt;
+
+/// How often the readiness of the tasks is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long the rest of the output of a task which exited is waited for, with `--events`.
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum UpError {
+    #[error("No tasks to run, give COMMANDS or a config file")]
+    NoTasks,
+    #[error("`{0}` stopped before becoming ready")]
+    Stopped(String),
+    #[error("Not ready after {}s: {1}", .0.as_secs())]
+    Timeout(Duration, String),
+    #[error("Failed: {0}")]
+    Failed(String),
+}
+
+/// Runs the tasks without the UI for `parallely up`, until they have all exited or parallely is
+/// interrupted. With `wait`, a line is printed once every task is up, see [`is_up`], and the tasks are
+/// stopped with an error if one of them stops before becoming ready or they are not ready
+/// within `timeout`. With `forward_signals`, `SIGHUP`, `SIGUSR1` and `SIGUSR2` are passed on
+/// to the tasks, else `SIGHUP` shuts down as told by `on_hangup`. With `events`, a line is
+/// printed whenever a task starts, becomes ready or exits, see [`crate::task_events::print`].
+pub async fn run(
+    tasks: Vec<TaskConfig>,
+    wait: bool,
+    timeout: Option<Duration>,
+    forward_signals: bool,
+    on_hangup: HangupAction,
+    events: Option<EventFormat>,
+) -> color_eyre::Result<()> {
+    if tasks.is_empty() {
+        return Err(UpError::NoTasks.into());
+    }
+    let (message_sender, mut message_stream) = message::message_queue();
+    let shutdown_handler = ShutdownHandler::new(message_sender.clone());
+    shutdown_handler.listen_for_signal();
+    if forward_signals {
+        shutdown_handler.listen_for_forwarded_signals(on_hangup);
+    } else {
+        shutdown_handler.listen_for_hangup(on_hangup, false);
+    }
+    let mut consoles = tasks
+        .into_iter()
+        .map(|task| {
+            let mut console = Console::new(task, message_sender.clone());
+            console.set_echo(true);
+            console.set_events(events);
+            console
+        })
+        .collect::<Vec<_>>();
+    for console in consoles.iter_mut() {
+        console.start()?;
+    }
+
+    let started_at = Instant::now();
+    let mut waiting = wait;
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let outcome = loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            Some(message) = message_stream.next() => match message {
+                Message::Shutdown(reason) => break Ok(reason),
+                Message::Error(error) => eprintln!("parallely: {:#}", error),
+                Message::Signal(signal) => {
+                    let count = Console::forward_signal(&mut consoles, signal);
+                    eprintln!("parallely: forwarded {} to {} tasks", signal, count);
+                }
+                _ => {}
+            },
+        }
+        for console in consoles.iter_mut() {
+            console.poll_env();
+            console.receive(usize::MAX)?;
+            // the last lines of a task which exited go before its exit event
+            let exited = events.is_some()
+                && console.state().is_active()
+                && !matches!(console.try_wait(), Ok(TaskStatus::Executing { .. }));
+            if exited {
+                console
+                    .drain(tokio::time::Instant::now() + DRAIN_TIMEOUT)
+                    .await?;
+            }
+        }
+        Console::start_waiting(&mut consoles);
+        let stopped = consoles
+            .iter_mut()
+            .map(|console| match console.try_wait() {
+                Ok(status) => {
+                    // a task may have exited since its readiness was polled
+                    console.poll_ready(&status);
+                    console.poll_exit(&status);
+                    // the task may have been started again by its restart policy
+                    let status = console.try_wait().unwrap_or(status);
+                    !console.is_waiting()
+                        && !console.is_restarting()
+                        && !matches!(status, TaskStatus::Executing { .. })
+                }
+                Err(_) => true,
+            })
+            .collect::<Vec<_>>();
+        if waiting {
+            let not_ready = consoles
+                .iter()
+                .filter(|console| !is_up(console))
+                .collect::<Vec<_>>();
+            if not_ready.is_empty() {
+                println!(
+                    "parallely: all {} tasks are ready after {:.1}s",
+                    consoles.len(),
+                    started_at.elapsed().as_secs_f64()
+                );
+                waiting = false;
+            } else if let Some(index) =
+                (0..consoles.len()).find(|&index| stopped[index] && !is_up(&consoles[index]))
+            {
+                break Err(UpError::Stopped(consoles[index].task().key().to_string()));
+            } else if let Some(timeout) = timeout.filter(|t| started_at.elapsed() >= *t) {
+                let names = not_ready
+                    .iter()
+                    .map(|console| console.task().key())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                consoles
+                    .iter_mut()
+                    .filter(|console| !is_up(console))
+                    .for_each(Console::time_out);
+                break Err(UpError::Timeout(timeout, names));
+            }
+        }
+        if !stopped.contains(&false) {
+            break Ok(ShutdownReason::End);
+        }
+    };
+
+    let reason = match &outcome {
+        Ok(reason) => *reason,
+        Err(_) => ShutdownReason::Sigterm,
+    };
+    if matches!(reason, ShutdownReason::Hangup(_)) && shutdown_handler::terminal_closed() {
+        shutdown_handler::discard_output();
+    }
+    if reason.detaches() {
+        return Ok(());
+    }
+    let statuses = futures::future::join_all(
+        consoles
+            .iter_mut()
+            .map(|console| console.signal_or_wait(reason)),
+    )
+    .await;
+    for console in consoles.iter_mut() {
+        console.receive(usize::MAX)?;
+    }
+    message_sender.supervisor().shutdown().await;
+    if !matches!(outcome?, ShutdownReason::End) {
+        return Ok(());
+    }
+    let failed = consoles
+        .iter()
+        .zip(statuses)
+        .filter(|(_, status)| status.as_ref().map_or(true, TaskStatus::is_failed))
+        .map(|(console, _)| console.task().key())
+        .collect::<Vec<_>>();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(UpError::Failed(failed.join(", ")).into())
+    }
+}
+
+/// Whether the task counts as ready for `--wait`. A task without a probe counts once it is
+/// running, since a server without one would otherwise only be ready once it has exited.
+fn is_up(console: &Console) -> bool {
+    console.is_ready() || (console.task().ready.is_none() && console.state() == TaskState::Running)
+}
@@ -0,0 +1,102 @@
+use crate::message::MessageSender;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long the main loop may go without a heartbeat before it counts as stalled.
+const STALL_THRESHOLD: Duration = Duration::from_secs(2);
+/// How often the watchdog checks the heartbeat.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+/// How often the latency of the main loop is written to the debug log.
+const METRICS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Detects when the main loop stops drawing and handling messages, e.g. because something on the
+/// render path blocks, which the ticks of the app would otherwise hide. The loop calls
+/// [`Watchdog::beat`] every time before it waits for the next message, a background task checks
+/// that it does so at least every [`STALL_THRESHOLD`], logs a warning if not and wakes the loop up
+/// to redraw once it gets going again. With `--debug`, the time the loop spends between two
+/// messages is logged every [`METRICS_INTERVAL`].
+#[derive(Clone)]
+pub struct Watchdog {
+    heartbeat: Arc<Mutex<Heartbeat>>,
+}
+
+struct Heartbeat {
+    last: Instant,
+    /// Since when the loop has been stalled, set by the watchdog and taken by the next beat.
+    stalled_since: Option<Instant>,
+    metrics: Metrics,
+}
+
+/// The latency of the iterations of the main loop since the metrics were last logged.
+struct Metrics {
+    since: Instant,
+    iterations: u32,
+    total: Duration,
+    max: Duration,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics {
+            since: Instant::now(),
+            iterations: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Watchdog {
+            heartbeat: Arc::new(Mutex::new(Heartbeat {
+                last: Instant::now(),
+                stalled_since: None,
+                metrics: Metrics::new(),
+            })),
+        }
+    }
+
+    /// Starts checking the heartbeat in a background task of the supervisor of `message_sender`.
+    pub fn watch(&self, message_sender: MessageSender) {
+        let heartbeat = self.heartbeat.clone();
+        let supervisor = message_sender.supervisor().clone();
+        supervisor.spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                let mut heartbeat = heartbeat.lock().unwrap_or_else(|e| e.into_inner());
+                let silent = heartbeat.last.elapsed();
+                if silent >= STALL_THRESHOLD && heartbeat.stalled_since.is_none() {
+                    tracing::warn!("[Watchdog] Main loop stalled for {:?}", silent);
+                    heartbeat.stalled_since = Some(heartbeat.last);
+                    // redraw right away once the loop handles messages again
+                    message_sender.need_update();
+                }
+            }
+        });
+    }
+
+    /// Records that the main loop made progress, `latency` being how long it was busy since it
+    /// received the last message. Returns how long it was stalled if the watchdog noticed.
+    pub fn beat(&self, latency: Duration) -> Option<Duration> {
+        let mut heartbeat = self.heartbeat.lock().unwrap_or_else(|e| e.into_inner());
+        heartbeat.last = Instant::now();
+        let metrics = &mut heartbeat.metrics;
+        metrics.iterations += 1;
+        metrics.total += latency;
+        metrics.max = metrics.max.max(latency);
+        if metrics.since.elapsed() >= METRICS_INTERVAL {
+            tracing::debug!(
+                "[Watchdog] Main loop latency over {} iterations: mean {:?}, max {:?}",
+                metrics.iterations,
+                metrics.total / metrics.iterations,
+                metrics.max
+            );
+            *metrics = Metrics::new();
+        }
+        let stalled = heartbeat.stalled_since.take()?.elapsed();
+        tracing::warn!("[Watchdog] Main loop recovered after {:?}", stalled);
+        Some(stalled)
+    }
+}
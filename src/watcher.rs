@@ -0,0 +1,111 @@
+use crate::message::{Message, MessageSender};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+/// How often the watched files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// The directories which are not watched, besides hidden ones, where builds write to.
+const IGNORED_DIRS: [&str; 2] = ["target", "node_modules"];
+
+/// When each watched file was last modified.
+type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+/// Watches the files of a task, see [`crate::config::TaskConfig::watch`], by checking when they
+/// were modified every [`POLL_INTERVAL`]. A change is sent as [`Message::Changed`] with the
+/// first file which changed. Symlinked directories inside the watched ones are not followed.
+/// Watching stops once the watcher is dropped.
+pub struct Watcher {
+    _guard: DropGuard,
+}
+
+impl Watcher {
+    /// Watches the files and directories at `paths` for the task with the key.
+    pub fn start(task: String, paths: Vec<PathBuf>, message_sender: &MessageSender) -> Self {
+        let token = CancellationToken::new();
+        let cancelled = token.clone();
+        let sender = message_sender.clone();
+        message_sender.supervisor().spawn(async move {
+            let scan_paths = paths.clone();
+            let Ok(mut snapshot) = tokio::task::spawn_blocking(move || scan(&scan_paths)).await
+            else {
+                return;
+            };
+            loop {
+                tokio::select! {
+                    _ = cancelled.cancelled() => break,
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+                let scan_paths = paths.clone();
+                let Ok(current) = tokio::task::spawn_blocking(move || scan(&scan_paths)).await
+                else {
+                    break;
+                };
+                if let Some(path) = changed(&snapshot, &current) {
+                    tracing::info!("[Watcher] `{}` changed by {}", task, path.display());
+                    if sender
+                        .send(Message::Changed(task.clone(), path.to_path_buf()))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                snapshot = current;
+            }
+        });
+        Watcher {
+            _guard: token.drop_guard(),
+        }
+    }
+}
+
+fn scan(paths: &[PathBuf]) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    for path in paths {
+        scan_path(path, &mut snapshot);
+    }
+    snapshot
+}
+
+fn scan_path(path: &Path, snapshot: &mut Snapshot) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return;
+    };
+    if !metadata.is_dir() {
+        if let Ok(modified) = metadata.modified() {
+            snapshot.insert(path.to_path_buf(), modified);
+        }
+        return;
+    }
+    for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        // a symlinked directory is not descended into, it may link back to one of its parents
+        if file_type.is_symlink() && path.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let ignored = name.starts_with('.') || IGNORED_DIRS.contains(&name.as_ref());
+        if !ignored || file_type.is_file() {
+            scan_path(&path, snapshot);
+        }
+    }
+}
+
+/// The first file which was added, modified or removed.
+fn changed<'a>(before: &'a Snapshot, after: &'a Snapshot) -> Option<&'a Path> {
+    let modified = after
+        .iter()
+        .find(|(path, modified)| before.get(*path) != Some(modified))
+        .map(|(path, _)| path.as_path());
+    modified.or_else(|| {
+        before
+            .keys()
+            .find(|path| !after.contains_key(*path))
+            .map(PathBuf::as_path)
+    })
+}